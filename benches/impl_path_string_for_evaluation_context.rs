@@ -1,7 +1,7 @@
 use std::borrow::Cow;
 use std::collections::BTreeMap;
 
-use async_graphql::{Name, Value};
+use async_graphql::{Name, SelectionField, Value};
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use hyper::header::HeaderValue;
 use hyper::HeaderMap;
@@ -88,6 +88,10 @@ impl<'a> ResolverContextLike<'a> for MockGraphqlContext {
   fn args(&'a self) -> Option<&'a IndexMap<Name, Value>> {
     Some(&TEST_ARGS)
   }
+
+  fn field(&'a self) -> Option<SelectionField<'a>> {
+    None
+  }
 }
 
 // assert that everything was set up correctly for the benchmark
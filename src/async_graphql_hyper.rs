@@ -1,12 +1,19 @@
 use std::any::Any;
+use std::collections::HashMap;
 
 use anyhow::Result;
-use async_graphql::{BatchResponse, Executor};
+use async_graphql::futures_util::stream::{BoxStream, StreamExt};
+use async_graphql::{BatchRequest, BatchResponse, Executor};
 use hyper::header::{HeaderValue, CACHE_CONTROL, CONTENT_TYPE};
 use hyper::{Body, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+use crate::blueprint::is_request_error;
+use crate::http::encode_event;
+use crate::persisted_queries::{PersistedQueries, PersistedQueryExecutor};
+
+/// A single request, or a JSON array of requests that are executed as a batch.
+#[derive(Debug, Deserialize)]
 pub struct GraphQLBatchRequest(pub async_graphql::BatchRequest);
 impl GraphQLBatchRequest {
   /// Shortcut method to execute the request on the executor.
@@ -16,6 +23,29 @@ impl GraphQLBatchRequest {
   {
     GraphQLResponse(executor.execute_batch(self.0).await)
   }
+
+  /// Executes the requests one after another, after resolving their Automatic Persisted Queries.
+  /// Every request that can't be resolved is answered with the error that the client expects.
+  pub async fn execute_persisted<E>(self, executor: &E, persisted_queries: Option<&PersistedQueries>) -> GraphQLResponse
+  where
+    E: Executor,
+  {
+    PersistedQueryExecutor::new(executor.clone(), persisted_queries.cloned())
+      .execute_batch(self.0)
+      .await
+      .into()
+  }
+
+  /// Insert some data for every request of the batch.
+  #[must_use]
+  pub fn data<D: Any + Clone + Send + Sync>(self, data: D) -> Self {
+    Self(self.0.data(data))
+  }
+}
+impl From<GraphQLRequest> for GraphQLBatchRequest {
+  fn from(request: GraphQLRequest) -> Self {
+    Self(BatchRequest::Single(request.0))
+  }
 }
 #[derive(Debug, Deserialize)]
 pub struct GraphQLRequest(pub async_graphql::Request);
@@ -29,6 +59,27 @@ impl GraphQLRequest {
     GraphQLResponse(executor.execute(self.0).await.into())
   }
 
+  /// Shortcut method to execute the request as a stream, which is how subscriptions are resolved.
+  pub fn execute_stream<E>(self, executor: &E) -> GraphQLStreamResponse
+  where
+    E: Executor,
+  {
+    GraphQLStreamResponse(executor.execute_stream(self.0, None))
+  }
+
+  /// Resolves the query of an Automatic Persisted Query.
+  /// When it can't be resolved, the error is returned as the response that the client expects.
+  pub async fn persisted(self, persisted_queries: Option<&PersistedQueries>) -> Result<Self, GraphQLResponse> {
+    match persisted_queries {
+      Some(persisted_queries) => persisted_queries
+        .resolve(self.0)
+        .await
+        .map(GraphQLRequest)
+        .map_err(|error| async_graphql::Response::from_errors(vec![error]).into()),
+      None => Ok(self),
+    }
+  }
+
   /// Insert some data for this request.
   #[must_use]
   pub fn data<D: Any + Send + Sync>(mut self, data: D) -> Self {
@@ -63,15 +114,24 @@ impl From<GraphQLQuery> for GraphQLRequest {
       request = request.variables(variables);
     }
 
+    if let Some(extensions) = query.extensions {
+      request.extensions =
+        serde_json::from_str::<HashMap<String, async_graphql::Value>>(&extensions).unwrap_or_default();
+    }
+
     GraphQLRequest(request)
   }
 }
 
-#[derive(Debug)]
+/// Request that is sent as the query string of a GET request, with its variables and extensions encoded as JSON.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GraphQLQuery {
+  #[serde(default)]
   query: String,
   operation_name: Option<String>,
   variables: Option<String>,
+  extensions: Option<String>,
 }
 
 impl GraphQLQuery {
@@ -87,13 +147,93 @@ impl GraphQLQuery {
 
 lazy_static::lazy_static! {
   static ref APPLICATION_JSON:HeaderValue = HeaderValue::from_static("application/json");
+  static ref APPLICATION_GRAPHQL_RESPONSE_JSON:HeaderValue = HeaderValue::from_static("application/graphql-response+json");
+  static ref TEXT_EVENT_STREAM:HeaderValue = HeaderValue::from_static("text/event-stream");
 }
 
-impl GraphQLResponse {
+/// Responses of a request that was executed as a stream.
+/// They are sent to the client as server-sent events.
+pub struct GraphQLStreamResponse(pub BoxStream<'static, async_graphql::Response>);
+
+impl GraphQLStreamResponse {
   pub fn to_response(self) -> Result<Response<hyper::Body>> {
+    let events = self
+      .0
+      .map(|res| serde_json::to_string(&res).map(|data| encode_event(&data)));
+    let response = Response::builder()
+      .status(StatusCode::OK)
+      .header(CONTENT_TYPE, TEXT_EVENT_STREAM.as_ref())
+      .header(CACHE_CONTROL, "no-cache")
+      .body(Body::wrap_stream(events))?;
+
+    Ok(response)
+  }
+}
+
+/// Response of an operation that was executed through a REST endpoint.
+/// The data is sent as plain JSON, unless the operation failed.
+pub struct GraphQLRestResponse(pub async_graphql::Response);
+
+impl GraphQLRestResponse {
+  /// Sets the `max_age` of the `cache_control` of the response.
+  pub fn set_cache_control(mut self, min_cache: i32) -> Self {
+    self.0.cache_control.max_age = min_cache;
+    self
+  }
+
+  /// Errors that aren't tied to a field (eg. invalid variables) are reported as a bad request.
+  pub fn to_response(self) -> Result<Response<hyper::Body>> {
+    if self.0.is_err() {
+      let status = if self.0.errors.iter().all(|error| error.path.is_empty()) {
+        StatusCode::BAD_REQUEST
+      } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+      };
+      let body = serde_json::json!({ "errors": self.0.errors });
+      return Ok(
+        Response::builder()
+          .status(status)
+          .header(CONTENT_TYPE, APPLICATION_JSON.as_ref())
+          .body(Body::from(body.to_string()))?,
+      );
+    }
+
     let mut response = Response::builder()
       .status(StatusCode::OK)
       .header(CONTENT_TYPE, APPLICATION_JSON.as_ref())
+      .body(Body::from(serde_json::to_string(&self.0.data)?))?;
+    if let Some(cache_control) = self.0.cache_control.value() {
+      response
+        .headers_mut()
+        .insert(CACHE_CONTROL, HeaderValue::from_str(cache_control.as_str())?);
+    }
+
+    Ok(response)
+  }
+}
+
+impl GraphQLResponse {
+  pub fn to_response(self) -> Result<Response<hyper::Body>> {
+    self.build_response(StatusCode::OK, &APPLICATION_JSON)
+  }
+
+  /// Responds with the `application/graphql-response+json` media type of the GraphQL over HTTP spec,
+  /// where a single request that couldn't be parsed or validated is answered with a bad request.
+  /// The errors of an executed request are answered like its data, even when there's no data.
+  pub fn to_graphql_response_json(self) -> Result<Response<hyper::Body>> {
+    let status = match &self.0 {
+      BatchResponse::Single(response) if response.is_err() && response.errors.iter().all(is_request_error) => {
+        StatusCode::BAD_REQUEST
+      }
+      _ => StatusCode::OK,
+    };
+    self.build_response(status, &APPLICATION_GRAPHQL_RESPONSE_JSON)
+  }
+
+  fn build_response(self, status: StatusCode, content_type: &HeaderValue) -> Result<Response<hyper::Body>> {
+    let mut response = Response::builder()
+      .status(status)
+      .header(CONTENT_TYPE, content_type)
       .body(Body::from(serde_json::to_string(&self.0)?))?;
 
     if self.0.is_ok() {
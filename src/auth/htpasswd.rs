@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+/// Htpasswd verifies `Basic` credentials against the users of an htpasswd file.
+/// Passwords can be hashed with bcrypt (`htpasswd -B`) or with the MD5, SHA-256 and SHA-512 crypt schemes.
+#[derive(Clone, Debug)]
+pub struct Htpasswd {
+  users: BTreeMap<String, String>,
+}
+
+impl Htpasswd {
+  pub fn from_file(path: &str) -> Result<Self> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Couldn't read htpasswd file '{path}'"))?;
+    Self::parse(&content)
+  }
+
+  /// Parses the `user:hash` lines of an htpasswd file, skipping blank lines and comments.
+  pub fn parse(content: &str) -> Result<Self> {
+    let mut users = BTreeMap::new();
+    for (index, line) in content.lines().enumerate() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+      let (user, hash) = line
+        .split_once(':')
+        .ok_or_else(|| anyhow!("Line {} is not of the form user:hash", index + 1))?;
+      users.insert(user.to_string(), hash.to_string());
+    }
+
+    Ok(Self { users })
+  }
+
+  /// Verifies the base64 encoded `user:password` credentials and returns the name of the user.
+  pub fn verify(&self, credentials: &str) -> Result<String> {
+    let decoded = String::from_utf8(STANDARD.decode(credentials)?)?;
+    let (user, password) = decoded
+      .split_once(':')
+      .ok_or_else(|| anyhow!("Credentials are not of the form user:password"))?;
+    match self.users.get(user) {
+      Some(hash) if pwhash::unix::verify(password, hash) => Ok(user.to_string()),
+      _ => bail!("Invalid credentials for user '{user}'"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  // The password of alice is `secret`
+  const HTPASSWD: &str = "# users\nalice:$2y$10$8t1JEJ2VdhzIbxHEZ.4waut8ktv.TCT0Kn5ABsRJu2M.QjIK98jIS\n";
+
+  fn credentials(user: &str, password: &str) -> String {
+    STANDARD.encode(format!("{user}:{password}"))
+  }
+
+  #[test]
+  fn test_verify() {
+    let htpasswd = Htpasswd::parse(HTPASSWD).unwrap();
+    assert_eq!(htpasswd.verify(&credentials("alice", "secret")).unwrap(), "alice");
+  }
+
+  #[test]
+  fn test_verify_invalid_credentials() {
+    let htpasswd = Htpasswd::parse(HTPASSWD).unwrap();
+    assert!(htpasswd.verify(&credentials("alice", "wrong")).is_err());
+    assert!(htpasswd.verify(&credentials("bob", "secret")).is_err());
+    assert!(htpasswd.verify("not base64").is_err());
+  }
+
+  #[test]
+  fn test_parse_invalid_line() {
+    assert!(Htpasswd::parse("alice").is_err());
+  }
+}
@@ -0,0 +1,114 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use derive_setters::Setters;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+
+/// Jwks verifies `Bearer` tokens with the keys of a JSON Web Key Set.
+#[derive(Clone, Debug, Setters)]
+pub struct Jwks {
+  #[setters(skip)]
+  keys: JwkSet,
+  issuer: Option<String>,
+  audiences: BTreeSet<String>,
+}
+
+impl Jwks {
+  pub fn new(keys: JwkSet) -> Self {
+    Self { keys, issuer: None, audiences: BTreeSet::new() }
+  }
+
+  pub fn from_file(path: &str) -> Result<Self> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Couldn't read JWKS file '{path}'"))?;
+    Ok(Self::new(serde_json::from_str(&content)?))
+  }
+
+  /// Verifies the signature and the claims of the token and returns its claims.
+  /// Tokens with a `kid` are only checked against the key with the same id, others against every key.
+  pub fn verify(&self, token: &str) -> Result<serde_json::Value> {
+    let header = decode_header(token)?;
+    let mut error = anyhow!("No key found for the token");
+    for jwk in self.keys.keys.iter() {
+      if header.kid.is_some() && header.kid != jwk.common.key_id {
+        continue;
+      }
+      let algorithm = match jwk.common.key_algorithm {
+        Some(algorithm) => Algorithm::from_str(&algorithm.to_string())?,
+        None => header.alg,
+      };
+      match decode::<serde_json::Value>(token, &DecodingKey::from_jwk(jwk)?, &self.validation(algorithm)) {
+        Ok(data) => return Ok(data.claims),
+        Err(e) => error = e.into(),
+      }
+    }
+
+    Err(error)
+  }
+
+  fn validation(&self, algorithm: Algorithm) -> Validation {
+    let mut validation = Validation::new(algorithm);
+    if let Some(issuer) = &self.issuer {
+      validation.set_issuer(&[issuer]);
+    }
+    if self.audiences.is_empty() {
+      validation.validate_aud = false;
+    } else {
+      validation.set_audience(&self.audiences.iter().collect::<Vec<_>>());
+    }
+    validation
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use jsonwebtoken::{encode, EncodingKey, Header};
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  use super::*;
+
+  const SECRET: &[u8] = b"secret";
+
+  fn jwks() -> Jwks {
+    // `k` is the base64url encoding of SECRET
+    let keys = json!({"keys": [{"kty": "oct", "kid": "1", "alg": "HS256", "k": "c2VjcmV0"}]});
+    Jwks::new(serde_json::from_value(keys).unwrap())
+  }
+
+  fn token(kid: &str, claims: serde_json::Value) -> String {
+    let header = Header { kid: Some(kid.to_string()), ..Header::new(Algorithm::HS256) };
+    encode(&header, &claims, &EncodingKey::from_secret(SECRET)).unwrap()
+  }
+
+  #[test]
+  fn test_verify() {
+    let claims = json!({"sub": "alice", "exp": 4102444800u64});
+    assert_eq!(jwks().verify(&token("1", claims.clone())).unwrap(), claims);
+  }
+
+  #[test]
+  fn test_verify_expired_token() {
+    let claims = json!({"sub": "alice", "exp": 946684800u64});
+    assert!(jwks().verify(&token("1", claims)).is_err());
+  }
+
+  #[test]
+  fn test_verify_unknown_key() {
+    let claims = json!({"sub": "alice", "exp": 4102444800u64});
+    assert!(jwks().verify(&token("2", claims)).is_err());
+  }
+
+  #[test]
+  fn test_verify_claims() {
+    let jwks = jwks()
+      .issuer(Some("tailcall".to_string()))
+      .audiences(BTreeSet::from(["api".to_string()]));
+    let valid = json!({"iss": "tailcall", "aud": "api", "exp": 4102444800u64});
+    let invalid = json!({"iss": "other", "aud": "api", "exp": 4102444800u64});
+
+    assert!(jwks.verify(&token("1", valid)).is_ok());
+    assert!(jwks.verify(&token("1", invalid)).is_err());
+  }
+}
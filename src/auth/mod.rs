@@ -0,0 +1,44 @@
+mod htpasswd;
+mod jwks;
+
+pub use htpasswd::Htpasswd;
+use hyper::header::AUTHORIZATION;
+use hyper::HeaderMap;
+pub use jwks::Jwks;
+
+/// Identity of an authenticated request.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Identity {
+  /// Claims of a verified JSON Web Token.
+  Jwt(serde_json::Value),
+  /// Name of a user of the htpasswd file.
+  User(String),
+}
+
+/// Auth holds the providers that are used to authenticate incoming requests.
+#[derive(Clone, Debug, Default)]
+pub struct Auth {
+  pub jwks: Option<Jwks>,
+  pub htpasswd: Option<Htpasswd>,
+}
+
+impl Auth {
+  /// Authenticates the request with the credentials of its `Authorization` header.
+  /// `Bearer` tokens are verified with the JWKS provider and `Basic` credentials with the htpasswd provider.
+  /// Requests with missing or invalid credentials have no identity.
+  pub fn authenticate(&self, headers: &HeaderMap) -> Option<Identity> {
+    let authorization = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, credentials) = authorization.split_once(' ')?;
+    let credentials = credentials.trim();
+
+    let identity = if scheme.eq_ignore_ascii_case("bearer") {
+      self.jwks.as_ref()?.verify(credentials).map(Identity::Jwt)
+    } else if scheme.eq_ignore_ascii_case("basic") {
+      self.htpasswd.as_ref()?.verify(credentials).map(Identity::User)
+    } else {
+      return None;
+    };
+
+    identity.map_err(|e| log::debug!("Authentication failed: {}", e)).ok()
+  }
+}
@@ -1,4 +1,4 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use async_graphql::dynamic::{Schema, SchemaBuilder};
 use async_graphql::extensions::ApolloTracing;
@@ -6,10 +6,11 @@ use async_graphql::*;
 use derive_setters::Setters;
 use serde_json::Value;
 
-use super::GlobalTimeout;
+use super::{ErrorCodes, GlobalTimeout, OperationMetrics, OperationTracing, QueryLimiter};
 use crate::blueprint::server::Server;
-use crate::config::Upstream;
-use crate::lambda::{Expression, Lambda};
+use crate::config::{Upstream, UpstreamTls};
+use crate::lambda::{Expression, Lambda, Stream};
+use crate::rest::RestEndpoint;
 
 /// Blueprint is an intermediary representation that allows us to generate graphQL APIs.
 /// It can only be generated from a valid Config.
@@ -21,6 +22,11 @@ pub struct Blueprint {
   pub schema: SchemaDefinition,
   pub server: Server,
   pub upstream: Upstream,
+  /// Named upstream profiles, merged on top of `upstream`.
+  pub upstreams: BTreeMap<String, Upstream>,
+  pub rest: Vec<RestEndpoint>,
+  /// TLS settings of `@http` fields, that override the ones of the upstream for their host.
+  pub tls_hosts: BTreeMap<String, UpstreamTls>,
 }
 
 #[derive(Clone, Debug)]
@@ -45,6 +51,20 @@ impl Type {
   }
 }
 
+impl std::fmt::Display for Type {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Type::NamedType { name, .. } => write!(f, "{name}")?,
+      Type::ListType { of_type, .. } => write!(f, "[{of_type}]")?,
+    }
+    if self.is_nullable() {
+      Ok(())
+    } else {
+      write!(f, "!")
+    }
+  }
+}
+
 #[derive(Clone, Debug)]
 pub enum Definition {
   InterfaceTypeDefinition(InterfaceTypeDefinition),
@@ -80,6 +100,15 @@ pub struct ObjectTypeDefinition {
   pub fields: Vec<FieldDefinition>,
   pub description: Option<String>,
   pub implements: BTreeSet<String>,
+  pub key: Option<EntityKey>,
+}
+
+/// EntityKey identifies an entity of a federated schema.
+/// Entities are resolved through `resolver`, a field of the query type that takes the key fields as arguments.
+#[derive(Clone, Debug)]
+pub struct EntityKey {
+  pub fields: Vec<String>,
+  pub resolver: String,
 }
 
 #[derive(Clone, Debug)]
@@ -108,6 +137,7 @@ pub struct EnumValueDefinition {
 pub struct SchemaDefinition {
   pub query: String,
   pub mutation: Option<String>,
+  pub subscription: Option<String>,
   pub directives: Vec<Directive>,
 }
 
@@ -125,8 +155,11 @@ pub struct FieldDefinition {
   pub args: Vec<InputFieldDefinition>,
   pub of_type: Type,
   pub resolver: Option<Expression>,
+  pub stream: Option<Stream>,
   pub directives: Vec<Directive>,
   pub description: Option<String>,
+  /// Weight of the field in the complexity of a query.
+  pub cost: u64,
 }
 
 impl FieldDefinition {
@@ -171,7 +204,15 @@ pub struct UnionTypeDefinition {
 impl Blueprint {
   #[allow(clippy::too_many_arguments)]
   pub fn new(schema: SchemaDefinition, definitions: Vec<Definition>, server: Server, upstream: Upstream) -> Self {
-    Self { schema, definitions, server, upstream }
+    Self {
+      schema,
+      definitions,
+      server,
+      upstream,
+      upstreams: BTreeMap::new(),
+      rest: Vec::new(),
+      tls_hosts: BTreeMap::new(),
+    }
   }
 
   pub fn query(&self) -> String {
@@ -182,9 +223,15 @@ impl Blueprint {
     self.schema.mutation.clone()
   }
 
+  pub fn subscription(&self) -> Option<String> {
+    self.schema.subscription.clone()
+  }
+
   pub fn to_schema(&self) -> Schema {
     let server = &self.server;
-    let mut schema = SchemaBuilder::from(self);
+    let mut schema = SchemaBuilder::from(self)
+      .extension(OperationTracing)
+      .extension(OperationMetrics);
 
     if server.enable_apollo_tracing {
       schema = schema.extension(ApolloTracing);
@@ -196,6 +243,11 @@ impl Blueprint {
         .extension(GlobalTimeout);
     }
 
+    if let Some(query_limits) = server.query_limits.clone() {
+      schema = schema.extension(QueryLimiter::new(self, query_limits));
+    }
+    schema = schema.extension(ErrorCodes);
+
     if server.get_enable_query_validation() {
       schema = schema.validation_mode(ValidationMode::Strict);
     } else {
@@ -4,7 +4,17 @@ use super::{Blueprint, Definition};
 
 pub fn compress(mut blueprint: Blueprint) -> Blueprint {
   let graph = build_dependency_graph(&blueprint);
-  let mut referenced_types = identify_referenced_types(&graph, vec!["Query", "Mutation", "Subscription"]);
+  let schema = &blueprint.schema;
+  let roots = [
+    Some(&schema.query),
+    schema.mutation.as_ref(),
+    schema.subscription.as_ref(),
+  ]
+  .into_iter()
+  .flatten()
+  .map(String::as_str)
+  .collect::<Vec<_>>();
+  let mut referenced_types = identify_referenced_types(&graph, roots);
   referenced_types.insert("Query".to_string());
   referenced_types.insert("Mutation".to_string());
   referenced_types.insert("Subscription".to_string());
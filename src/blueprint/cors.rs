@@ -0,0 +1,60 @@
+use hyper::header::{
+  HeaderValue, ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+  ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_MAX_AGE, VARY,
+};
+use hyper::HeaderMap;
+
+/// Cors holds the validated CORS headers of `@server(cors)`.
+#[derive(Clone, Debug)]
+pub struct Cors {
+  /// `None` allows any origin.
+  pub allow_origins: Option<Vec<HeaderValue>>,
+  pub allow_methods: HeaderValue,
+  pub allow_headers: HeaderValue,
+  pub expose_headers: Option<HeaderValue>,
+  pub max_age: Option<HeaderValue>,
+  pub allow_credentials: bool,
+}
+
+impl Cors {
+  /// The `Access-Control-Allow-Origin` of a request from the origin, if it is allowed.
+  fn allow_origin(&self, origin: Option<&HeaderValue>) -> Option<HeaderValue> {
+    match &self.allow_origins {
+      None => Some(HeaderValue::from_static("*")),
+      Some(allow_origins) => origin.filter(|origin| allow_origins.contains(origin)).cloned(),
+    }
+  }
+
+  /// The headers of the response to a request from the origin.
+  pub fn response_headers(&self, origin: Option<&HeaderValue>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if self.allow_origins.is_some() {
+      // The allowed origin depends on the origin of the request
+      headers.insert(VARY, HeaderValue::from_static("origin"));
+    }
+    let Some(allow_origin) = self.allow_origin(origin) else {
+      return headers;
+    };
+    headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+    if self.allow_credentials {
+      headers.insert(ACCESS_CONTROL_ALLOW_CREDENTIALS, HeaderValue::from_static("true"));
+    }
+    if let Some(expose_headers) = &self.expose_headers {
+      headers.insert(ACCESS_CONTROL_EXPOSE_HEADERS, expose_headers.clone());
+    }
+    headers
+  }
+
+  /// The headers of the response to a preflight request from the origin.
+  pub fn preflight_headers(&self, origin: Option<&HeaderValue>) -> HeaderMap {
+    let mut headers = self.response_headers(origin);
+    if headers.contains_key(ACCESS_CONTROL_ALLOW_ORIGIN) {
+      headers.insert(ACCESS_CONTROL_ALLOW_METHODS, self.allow_methods.clone());
+      headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, self.allow_headers.clone());
+      if let Some(max_age) = &self.max_age {
+        headers.insert(ACCESS_CONTROL_MAX_AGE, max_age.clone());
+      }
+    }
+    headers
+  }
+}
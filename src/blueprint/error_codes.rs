@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use async_graphql::extensions::{Extension, ExtensionContext, ExtensionFactory, NextParseQuery, NextValidation};
+use async_graphql::parser::types::ExecutableDocument;
+use async_graphql::{ServerError, ServerResult, ValidationResult, Value, Variables};
+
+/// The code of the errors of queries that can't be parsed.
+pub const PARSE_FAILED: &str = "GRAPHQL_PARSE_FAILED";
+/// The code of the errors of operations that aren't valid against the schema.
+pub const VALIDATION_FAILED: &str = "GRAPHQL_VALIDATION_FAILED";
+
+/// Sets the `code` extension of the errors that reject a request before it's executed,
+/// so that they can be told apart from the errors of the execution.
+pub struct ErrorCodes;
+
+impl ExtensionFactory for ErrorCodes {
+  fn create(&self) -> Arc<dyn Extension> {
+    Arc::new(ErrorCodesExtension)
+  }
+}
+
+struct ErrorCodesExtension;
+
+#[async_trait::async_trait]
+impl Extension for ErrorCodesExtension {
+  async fn parse_query(
+    &self,
+    ctx: &ExtensionContext<'_>,
+    query: &str,
+    variables: &Variables,
+    next: NextParseQuery<'_>,
+  ) -> ServerResult<ExecutableDocument> {
+    next
+      .run(ctx, query, variables)
+      .await
+      .map_err(|error| with_code(error, PARSE_FAILED))
+  }
+
+  async fn validation(
+    &self,
+    ctx: &ExtensionContext<'_>,
+    next: NextValidation<'_>,
+  ) -> Result<ValidationResult, Vec<ServerError>> {
+    next.run(ctx).await.map_err(|errors| {
+      errors
+        .into_iter()
+        .map(|error| with_code(error, VALIDATION_FAILED))
+        .collect()
+    })
+  }
+}
+
+/// Sets the code of the error, unless it already has one.
+pub fn with_code(mut error: ServerError, code: &str) -> ServerError {
+  let extensions = error.extensions.get_or_insert_with(Default::default);
+  if extensions.get("code").is_none() {
+    extensions.set("code", code);
+  }
+  error
+}
+
+/// Whether the error rejected the request before it was executed.
+pub fn is_request_error(error: &ServerError) -> bool {
+  match error.extensions.as_ref().and_then(|extensions| extensions.get("code")) {
+    Some(Value::String(code)) => code == PARSE_FAILED || code == VALIDATION_FAILED,
+    _ => false,
+  }
+}
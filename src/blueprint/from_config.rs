@@ -1,27 +1,34 @@
 #![allow(clippy::too_many_arguments)]
 
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::num::NonZeroU64;
+use std::time::Duration;
 
-use async_graphql::parser::types::ConstDirective;
+use async_graphql::parser::types::{BaseType, ConstDirective};
 #[allow(unused_imports)]
 use async_graphql::InputType;
 use async_graphql_value::ConstValue;
 use hyper::header::{HeaderName, HeaderValue};
 use hyper::HeaderMap;
+use prost_reflect::{Kind, MessageDescriptor};
 use regex::Regex;
 
 use super::UnionTypeDefinition;
 use crate::blueprint::Type::ListType;
 use crate::blueprint::*;
 use crate::config::group_by::GroupBy;
-use crate::config::{Arg, Batch, Config, Field, InlineType};
+use crate::config::{Arg, Batch, Config, Field, InlineType, LinkType};
 use crate::directive::DirectiveCodec;
 use crate::endpoint::Endpoint;
-use crate::http::Method;
+use crate::graphql_request_template::{GraphQLOperationType, GraphqlRequestTemplate};
+use crate::grpc::{GrpcRequestTemplate, ProtobufOperation, ProtobufSet};
+use crate::http::{client_tls, host_and_port, Method};
 use crate::json::JsonSchema;
 use crate::lambda::Expression::Literal;
-use crate::lambda::{Expression, Lambda, Operation};
+use crate::lambda::{Cache, Call, Comparison, Context, Expression, Lambda, MathOperation, Operation, Stream};
+use crate::mustache::{Filter, Mustache, Segment};
 use crate::request_template::RequestTemplate;
+use crate::rest::RestEndpoint;
 use crate::valid::{Valid, ValidationError};
 use crate::{blueprint, config};
 
@@ -29,38 +36,178 @@ pub fn config_blueprint(config: &Config) -> Valid<Blueprint, String> {
   let output_types = config.output_types();
   let input_types = config.input_types();
   let schema = to_schema(config);
-  let definitions = to_definitions(config, output_types, input_types);
+  let definitions =
+    to_definitions(config, output_types, input_types).map(|definitions| link_calls(config, definitions));
   let server = Server::try_from(config.server.clone()).into();
   let upstream = to_upstream(config.upstream.clone());
+  let upstreams = to_upstreams(config);
+  let rest = to_rest_endpoints(&config.links);
+  let tls_hosts = to_tls_hosts(config);
 
   schema
     .zip(definitions)
     .zip(server)
     .zip(upstream)
-    .map(|(((schema, definitions), server), upstream)| Blueprint { schema, definitions, server, upstream })
+    .zip(upstreams)
+    .zip(rest)
+    .zip(tls_hosts)
+    .map(
+      |((((((schema, definitions), server), upstream), upstreams), rest), tls_hosts)| Blueprint {
+        schema,
+        definitions,
+        server,
+        upstream,
+        upstreams,
+        rest,
+        tls_hosts,
+      },
+    )
     .map(apply_batching)
     .map(super::compress::compress)
 }
 
 fn to_upstream(upstream: config::Upstream) -> Valid<config::Upstream, String> {
-  if let Some(ref base_url) = upstream.base_url {
-    Valid::from(reqwest::Url::parse(base_url).map_err(|e| ValidationError::new(e.to_string()))).map_to(upstream)
+  let base_url = if let Some(ref base_url) = upstream.base_url {
+    Valid::from(reqwest::Url::parse(base_url).map_err(|e| ValidationError::new(e.to_string()))).unit()
   } else {
-    Valid::succeed(upstream)
-  }
+    Valid::succeed(())
+  };
+
+  let retry = Valid::<(), String>::fail("maxAttempts must be at least 1".to_string())
+    .when(|| upstream.retry.as_ref().is_some_and(|retry| retry.max_attempts == 0))
+    .trace("retry");
+  let circuit_breaker = Valid::<(), String>::fail("failureThreshold must be at least 1".to_string())
+    .when(|| {
+      upstream
+        .circuit_breaker
+        .as_ref()
+        .is_some_and(|circuit_breaker| circuit_breaker.failure_threshold == 0)
+    })
+    .trace("circuitBreaker");
+  let tls = match upstream.tls.as_ref() {
+    Some(tls) => to_tls(tls).trace("tls"),
+    None => Valid::succeed(()),
+  };
+
+  base_url
+    .and(retry.and(circuit_breaker).and(tls).trace("@upstream"))
+    .map_to(upstream)
+}
+
+/// Validates the named profiles of `@upstream`, and merges them on top of the unnamed one.
+fn to_upstreams(config: &Config) -> Valid<BTreeMap<String, config::Upstream>, String> {
+  Valid::from_iter(config.upstreams.iter().enumerate(), |(index, upstream)| {
+    let name = upstream.name.clone().unwrap_or_default();
+    Valid::<(), String>::fail(format!("Upstream profile '{name}' is defined more than once"))
+      .when(|| {
+        config.upstreams[..index]
+          .iter()
+          .any(|other| other.name == upstream.name)
+      })
+      .and(
+        // The allowed headers of the request are forwarded to every upstream
+        Valid::<(), String>::fail("allowedHeaders can only be set on the unnamed @upstream".to_string())
+          .when(|| upstream.allowed_headers.is_some())
+          .trace("allowedHeaders"),
+      )
+      .and(to_upstream(upstream.clone()))
+      .map(|_| (name.clone(), config.upstream.clone().merge_right(upstream.clone())))
+      .trace(&name)
+  })
+  .map(BTreeMap::from_iter)
+}
+
+fn to_tls(tls: &config::UpstreamTls) -> Valid<(), String> {
+  Valid::from(
+    client_tls(reqwest::Client::builder(), tls)
+      .map(|_| ())
+      .map_err(|e| ValidationError::new(e.to_string())),
+  )
+}
+
+/// Collects the TLS settings of `@http` fields, by the `host:port` of their base URL.
+fn to_tls_hosts(config: &Config) -> Valid<BTreeMap<String, config::UpstreamTls>, String> {
+  let fields = config.graphql.types.iter().flat_map(|(type_name, type_of)| {
+    type_of.fields.iter().filter_map(move |(field_name, field)| {
+      let tls = field.http.as_ref()?.tls.as_ref()?;
+      Some((type_name, field_name, field.http.as_ref()?, tls))
+    })
+  });
+  Valid::from_iter(fields, |(type_name, field_name, http, tls)| {
+    let upstream = match &http.upstream {
+      Some(name) => config.find_upstream(name).unwrap_or_else(|| config.upstream.clone()),
+      None => config.upstream.clone(),
+    };
+    let base_url = http.base_url.as_ref().or(upstream.base_url.as_ref());
+    let host = match base_url.map(|base_url| reqwest::Url::parse(base_url)) {
+      Some(Ok(url)) => Valid::succeed(host_and_port(&url)),
+      _ => Valid::fail("TLS settings require a valid base URL".to_string()),
+    };
+    to_tls(tls)
+      .and(host)
+      .map(|host| (host, tls.clone()))
+      .trace("tls")
+      .trace("@http")
+      .trace(field_name)
+      .trace(type_name)
+  })
+  .and_then(|tls_hosts| {
+    let conflicts = Valid::from_iter(tls_hosts.iter().enumerate(), |(index, (host, tls))| {
+      Valid::<(), String>::fail(format!("Conflicting TLS settings for {host}")).when(|| {
+        tls_hosts[..index]
+          .iter()
+          .any(|(other_host, other_tls)| other_host == host && other_tls != tls)
+      })
+    });
+    conflicts.map_to(tls_hosts.into_iter().collect())
+  })
+}
+
+/// Reads the linked operation documents and creates an endpoint for each of their `@rest` operations.
+fn to_rest_endpoints(links: &[config::Link]) -> Valid<Vec<RestEndpoint>, String> {
+  Valid::from_iter(
+    links.iter().filter(|link| link.type_of == LinkType::Operation),
+    |link| {
+      Valid::from(
+        std::fs::read_to_string(&link.src)
+          .map_err(anyhow::Error::from)
+          .and_then(|document| RestEndpoint::try_from_document(&document))
+          .map_err(|e| ValidationError::new(e.to_string())),
+      )
+      .trace(&link.src)
+      .trace("@link")
+      .trace("schema")
+    },
+  )
+  .map(|endpoints| endpoints.into_iter().flatten().collect())
 }
 
 pub fn apply_batching(mut blueprint: Blueprint) -> Blueprint {
+  let mut upstreams = BTreeSet::new();
   for def in blueprint.definitions.iter() {
     if let Definition::ObjectTypeDefinition(object_type_definition) = def {
       for field in object_type_definition.fields.iter() {
-        if let Some(Expression::Unsafe(Operation::Endpoint(_request_template, Some(_), _dl))) = field.resolver.clone() {
-          blueprint.upstream.batch = blueprint.upstream.batch.or(Some(Batch::default()));
-          return blueprint;
+        match field.resolver.as_ref().and_then(Expression::operation) {
+          Some(Operation::Endpoint(req_template, Some(_), _)) => {
+            upstreams.insert(req_template.endpoint.upstream.clone());
+          }
+          Some(Operation::Grpc(_, Some(_), _)) => {
+            upstreams.insert(None);
+          }
+          _ => {}
         }
       }
     }
   }
+  for name in upstreams {
+    let upstream = match name {
+      Some(name) => blueprint.upstreams.get_mut(&name),
+      None => Some(&mut blueprint.upstream),
+    };
+    if let Some(upstream) = upstream {
+      upstream.batch = upstream.batch.take().or(Some(Batch::default()));
+    }
+  }
   blueprint
 }
 
@@ -84,6 +231,7 @@ fn to_directive(const_directive: ConstDirective) -> Valid<Directive, String> {
 fn to_schema(config: &Config) -> Valid<SchemaDefinition, String> {
   validate_query(config)
     .and(validate_mutation(config))
+    .and(validate_subscription(config))
     .and(Valid::from_option(
       config.graphql.schema.query.as_ref(),
       "Query root is missing".to_owned(),
@@ -92,6 +240,7 @@ fn to_schema(config: &Config) -> Valid<SchemaDefinition, String> {
     .map(|(query_type_name, directive)| SchemaDefinition {
       query: query_type_name.to_owned(),
       mutation: config.graphql.schema.mutation.clone(),
+      subscription: config.graphql.schema.subscription.clone(),
       directives: vec![directive],
     })
 }
@@ -175,14 +324,20 @@ fn to_enum_type_definition(
   Valid::succeed(enum_type_definition)
 }
 fn to_object_type_definition(name: &str, type_of: &config::Type, config: &Config) -> Valid<Definition, String> {
-  to_fields(type_of, config).map(|fields| {
-    Definition::ObjectTypeDefinition(ObjectTypeDefinition {
-      name: name.to_string(),
-      description: type_of.doc.clone(),
-      fields,
-      implements: type_of.implements.clone(),
+  validate_auth_provider(config)
+    .when(|| type_of.protected)
+    .trace("@protected")
+    .and(to_fields(name, type_of, config))
+    .zip(to_entity_key(name, type_of, config))
+    .map(|(fields, key)| {
+      Definition::ObjectTypeDefinition(ObjectTypeDefinition {
+        name: name.to_string(),
+        description: type_of.doc.clone(),
+        fields,
+        implements: type_of.implements.clone(),
+        key,
+      })
     })
-  })
 }
 fn to_input_object_type_definition(definition: ObjectTypeDefinition) -> Valid<Definition, String> {
   Valid::succeed(Definition::InputObjectTypeDefinition(InputObjectTypeDefinition {
@@ -207,26 +362,90 @@ fn to_interface_type_definition(definition: ObjectTypeDefinition) -> Valid<Defin
     description: definition.description,
   }))
 }
-fn to_fields(type_of: &config::Type, config: &Config) -> Valid<Vec<blueprint::FieldDefinition>, String> {
+fn to_fields(
+  object_name: &str,
+  type_of: &config::Type,
+  config: &Config,
+) -> Valid<Vec<blueprint::FieldDefinition>, String> {
+  let operation_type = if config.graphql.schema.mutation.as_deref() == Some(object_name) {
+    GraphQLOperationType::Mutation
+  } else {
+    GraphQLOperationType::Query
+  };
+  let is_subscription = config.graphql.schema.subscription.as_deref() == Some(object_name);
   Valid::from_iter(type_of.fields.iter(), |(name, field)| {
     validate_field_type_exist(config, field)
-      .and(to_field(type_of, config, name, field))
+      .and(to_field(type_of, config, name, field, &operation_type, is_subscription))
       .trace(name)
   })
   .map(|fields| fields.into_iter().flatten().collect())
 }
 
-fn get_value_type(type_of: &config::Type, value: &str) -> Option<Type> {
-  if let Some(field) = type_of.fields.get(value) {
-    return Some(to_type(
-      &field.type_of,
-      field.list,
-      field.required,
-      field.list_type_required,
-    ));
+/// The type of the value at the path, where numeric parts are indices of lists, along with the
+/// number of parts that lead to it. The walk stops at fields that have their own resolver, since
+/// their values aren't part of the parent value.
+fn get_value_type(config: &Config, type_of: &config::Type, path: &[String]) -> Option<(Type, usize)> {
+  let (name, mut rest) = path.split_first()?;
+  let field = type_of.fields.get(name)?;
+  let mut of_type = to_type(&field.type_of, field.list, field.required, field.list_type_required);
+  if let (Type::ListType { of_type: item, .. }, Some(index)) = (&of_type, rest.first()) {
+    if index.parse::<usize>().is_ok() {
+      of_type = *item.clone();
+      rest = &rest[1..];
+    }
+  }
+  let len = path.len() - rest.len();
+
+  match of_type {
+    _ if rest.is_empty() => Some((of_type, len)),
+    Type::NamedType { ref name, .. } if field.has_resolver() => {
+      config.find_type(name)?;
+      Some((of_type, len))
+    }
+    // The nested value is missing whenever its parent is
+    Type::NamedType { name, non_null } => {
+      let (nested, nested_len) = get_value_type(config, config.find_type(&name)?, rest)?;
+      let nested = match nested {
+        Type::NamedType { name, .. } if !non_null => Type::NamedType { name, non_null },
+        Type::ListType { of_type, .. } if !non_null => Type::ListType { of_type, non_null },
+        nested => nested,
+      };
+      Some((nested, len + nested_len))
+    }
+    Type::ListType { .. } => None,
   }
+}
+
+/// The shape of the value of a template, as it goes through the filters.
+#[derive(Clone, Copy, PartialEq)]
+enum Shape {
+  Scalar,
+  List,
+  Object,
+}
+
+impl Shape {
+  fn of(type_of: &Type) -> Shape {
+    match type_of {
+      Type::ListType { .. } => Shape::List,
+      Type::NamedType { name, .. } if is_scalar(name) => Shape::Scalar,
+      Type::NamedType { .. } => Shape::Object,
+    }
+  }
+}
 
-  None
+fn apply_filters(mut shape: Shape, filters: &[Filter]) -> Result<Shape, String> {
+  for filter in filters {
+    shape = match (filter, shape) {
+      (Filter::Default(_), shape) => shape,
+      (Filter::Json, _) => Shape::Scalar,
+      (Filter::Join(_), Shape::List) => Shape::Scalar,
+      (Filter::Join(_), _) => return Err("filter 'join' requires a list".to_string()),
+      (Filter::UrlEncode | Filter::Trim, Shape::Scalar) => Shape::Scalar,
+      (filter, _) => return Err(format!("filter '{}' requires a scalar", filter.name())),
+    };
+  }
+  Ok(shape)
 }
 
 fn validate_mustache_parts(
@@ -234,6 +453,7 @@ fn validate_mustache_parts(
   config: &Config,
   is_query: bool,
   parts: &[String],
+  filters: &[Filter],
   args: &[InputFieldDefinition],
 ) -> Valid<(), String> {
   if parts.len() < 2 {
@@ -242,20 +462,35 @@ fn validate_mustache_parts(
 
   let head = parts[0].as_str();
   let tail = parts[1].as_str();
+  // Queries can use optional values, and so can the templates that have a default value
+  let allows_null = is_query || filters.iter().any(|filter| matches!(filter, Filter::Default(_)));
 
   match head {
-    "value" => {
-      if let Some(val_type) = get_value_type(type_of, tail) {
-        if !is_scalar(val_type.name()) {
+    // `input` reads better than `value` in the steps of a pipeline, where it's the output of the previous step
+    "value" | "input" => {
+      if let Some((val_type, len)) = get_value_type(config, type_of, &parts[1..]) {
+        let tail = parts[1..=len].join(".");
+        // The path goes through a field that's resolved on its own
+        if len + 1 < parts.len() {
+          return Valid::fail(format!("value '{tail}' is not of a scalar type"));
+        }
+        let is_scalar = if filters.is_empty() {
+          is_scalar(val_type.name())
+        } else {
+          match apply_filters(Shape::of(&val_type), filters) {
+            Ok(shape) => shape == Shape::Scalar,
+            Err(e) => return Valid::fail(e),
+          }
+        };
+        if !is_scalar {
           return Valid::fail(format!("value '{tail}' is not of a scalar type"));
         }
 
-        // Queries can use optional values
-        if !is_query && val_type.is_nullable() {
+        if !allows_null && val_type.is_nullable() {
           return Valid::fail(format!("value '{tail}' is a nullable type"));
         }
       } else {
-        return Valid::fail(format!("no value '{tail}' found"));
+        return Valid::fail(format!("no value '{}' found", parts[1..].join(".")));
       }
     }
     "args" => {
@@ -263,13 +498,14 @@ fn validate_mustache_parts(
       // constructing a HashMap since we'd have 3-4 arguments at max in
       // most cases
       if let Some(arg) = args.iter().find(|arg| arg.name == tail) {
-        if let Type::ListType { .. } = arg.of_type {
-          return Valid::fail(format!("can't use list type '{tail}' here"));
-        }
-
         // we can use non-scalar types in args
+        match apply_filters(Shape::of(&arg.of_type), filters) {
+          Ok(Shape::List) => return Valid::fail(format!("can't use list type '{tail}' here")),
+          Ok(_) => {}
+          Err(e) => return Valid::fail(e),
+        }
 
-        if !is_query && arg.default_value.is_none() && arg.of_type.is_nullable() {
+        if !allows_null && arg.default_value.is_none() && arg.of_type.is_nullable() {
           return Valid::fail(format!("argument '{tail}' is a nullable type"));
         }
       } else {
@@ -280,10 +516,16 @@ fn validate_mustache_parts(
       if config.server.vars.get(tail).is_none() {
         return Valid::fail(format!("var '{tail}' is not set in the server config"));
       }
+      if let Err(e) = apply_filters(Shape::Scalar, filters) {
+        return Valid::fail(e);
+      }
     }
     "headers" => {
       // "headers" refers to the header values known at runtime, which we can't
       // validate here
+      if let Err(e) = apply_filters(Shape::Scalar, filters) {
+        return Valid::fail(e);
+      }
     }
     _ => {
       return Valid::fail(format!("unknown template directive '{head}'"));
@@ -301,14 +543,14 @@ fn validate_field(type_of: &config::Type, config: &Config, field: &FieldDefiniti
   // context from that method alone
   // So we must duplicate some of that logic here :(
   if let Some(Expression::Unsafe(Operation::Endpoint(req_template, _, _))) = &field.resolver {
-    Valid::from_iter(req_template.root_url.expression_segments(), |parts| {
-      validate_mustache_parts(type_of, config, false, parts, &field.args).trace("path")
+    Valid::from_iter(req_template.root_url.expression_segments(), |(parts, filters)| {
+      validate_mustache_parts(type_of, config, false, parts, filters, &field.args).trace("path")
     })
     .and(Valid::from_iter(req_template.query.clone(), |query| {
       let (_, mustache) = query;
 
-      Valid::from_iter(mustache.expression_segments(), |parts| {
-        validate_mustache_parts(type_of, config, true, parts, &field.args).trace("query")
+      Valid::from_iter(mustache.expression_segments(), |(parts, filters)| {
+        validate_mustache_parts(type_of, config, true, parts, filters, &field.args).trace("query")
       })
     }))
     .unit()
@@ -322,6 +564,8 @@ fn to_field(
   config: &Config,
   name: &str,
   field: &Field,
+  operation_type: &GraphQLOperationType,
+  is_subscription: bool,
 ) -> Valid<Option<blueprint::FieldDefinition>, String> {
   let directives = field.resolvable_directives();
   if directives.len() > 1 {
@@ -337,14 +581,31 @@ fn to_field(
       of_type: to_type(field_type, field.list, field.required, field.list_type_required),
       directives: Vec::new(),
       resolver: None,
+      stream: None,
+      cost: field.http.as_ref().and_then(|http| http.cost).unwrap_or(1),
     };
 
     update_http(field, field_definition, type_of, config)
       .trace("@http")
       .map(|field_definition| update_unsafe(field.clone(), field_definition))
       .and_then(|field_definition| update_const_field(field, field_definition, config).trace("@const"))
+      .and_then(|field_definition| {
+        update_graphql(field, field_definition, type_of, config, operation_type).trace("@graphQL")
+      })
+      .and_then(|field_definition| update_grpc(field, field_definition, type_of, config).trace("@grpc"))
+      .and_then(|field_definition| update_expr(field, field_definition, type_of, config).trace("@expr"))
+      .and_then(|field_definition| update_call(field, field_definition, type_of, config).trace("@call"))
+      .and_then(|field_definition| update_pipeline(field, field_definition, type_of, config).trace("@pipeline"))
       .and_then(|field_definition| update_inline_field(type_of, field, field_definition, config).trace("@inline"))
       .and_then(|field_definition| update_modify(field, field_definition, type_of, config).trace("@modify"))
+      .and_then(|field_definition| match field_definition {
+        Some(field_definition) => update_stream(field, field_definition, is_subscription)
+          .trace("@http")
+          .map(|field_definition| update_cache(field, field_definition, type_of))
+          .and_then(|field_definition| update_protected(field, field_definition, type_of, config).trace("@protected"))
+          .map(Some),
+        None => Valid::succeed(None),
+      })
   })
 }
 
@@ -387,6 +648,26 @@ fn validate_mutation(config: &Config) -> Valid<(), String> {
   }
 }
 
+fn validate_subscription(config: &Config) -> Valid<(), String> {
+  let subscription_type_name = config.graphql.schema.subscription.as_ref();
+
+  if let Some(subscription_type_name) = subscription_type_name {
+    let Some(subscription) = config.find_type(subscription_type_name) else {
+      return Valid::fail("Subscription type is not defined".to_owned()).trace(subscription_type_name);
+    };
+
+    Valid::from_iter(subscription.fields.iter(), |(name, field)| {
+      Valid::<(), String>::fail("Subscription fields can only be resolved with @http".to_owned())
+        .when(|| field.http.is_none())
+        .trace(name)
+    })
+    .trace(subscription_type_name)
+    .unit()
+  } else {
+    Valid::succeed(())
+  }
+}
+
 fn validate_field_has_resolver((name, field): (&String, &Field)) -> Valid<(), String> {
   Valid::<(), String>::fail("No resolver has been found in the schema".to_owned())
     .when(|| !field.has_resolver())
@@ -418,63 +699,444 @@ fn update_http(
   config: &Config,
 ) -> Valid<FieldDefinition, String> {
   match field.http.as_ref() {
-    Some(http) => match http
+    Some(http) => {
+      let upstream = match &http.upstream {
+        Some(name) => match config.find_upstream(name) {
+          Some(upstream) => upstream,
+          None => return Valid::fail(format!("Upstream profile '{name}' is not defined")).trace("upstream"),
+        },
+        None => config.upstream.clone(),
+      };
+      match http.base_url.as_ref().or(upstream.base_url.as_ref()) {
+        Some(base_url) => {
+          let mut base_url = base_url.clone();
+          if base_url.ends_with('/') {
+            base_url.pop();
+          }
+          base_url.push_str(http.path.clone().as_str());
+          let query = http.query.clone().iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+          let output_schema = to_json_schema_for_field(field, config);
+          let input_schema = to_json_schema_for_args(&field.args, config);
+
+          Valid::<(), String>::fail("GroupBy is only supported for GET requests".to_string())
+            .when(|| !http.group_by.is_empty() && http.method != Method::GET)
+            .and(Valid::from_iter(http.headers.iter(), |(k, v)| {
+              let name =
+                Valid::from(HeaderName::from_bytes(k.as_bytes()).map_err(|e| ValidationError::new(e.to_string())));
+
+              let value =
+                Valid::from(HeaderValue::from_str(v.as_str()).map_err(|e| ValidationError::new(e.to_string())));
+
+              name.zip(value).map(|(name, value)| (name, value))
+            }))
+            .map(HeaderMap::from_iter)
+            .and_then(|header_map| {
+              RequestTemplate::try_from(
+                Endpoint::new(base_url.to_string())
+                  .method(http.method.clone())
+                  .query(query)
+                  .output(output_schema)
+                  .input(input_schema)
+                  .body(http.body.clone())
+                  .headers(header_map)
+                  .idempotent(http.idempotent)
+                  .upstream(http.upstream.clone()),
+              )
+              .map_err(|e| ValidationError::new(e.to_string()))
+              .into()
+            })
+            .map(|req_template| {
+              if !http.group_by.is_empty() && http.method == Method::GET {
+                b_field.resolver(Some(Expression::Unsafe(Operation::Endpoint(
+                  req_template,
+                  Some(GroupBy::new(http.group_by.clone())),
+                  None,
+                ))))
+              } else {
+                b_field.resolver(Some(Lambda::from_request_template(req_template).expression))
+              }
+            })
+            .and_then(|b_field| validate_field(type_of, config, &b_field).map_to(b_field))
+        }
+        None => Valid::fail("No base URL defined".to_string()),
+      }
+    }
+    None => Valid::succeed(b_field),
+  }
+}
+/// Turns the resolver of a subscription field into a stream of values.
+/// Fields with a `pollInterval` evaluate their resolver periodically,
+/// the others listen to the `text/event-stream` returned by the upstream.
+fn update_stream(
+  field: &config::Field,
+  b_field: FieldDefinition,
+  is_subscription: bool,
+) -> Valid<FieldDefinition, String> {
+  let Some(http) = field.http.as_ref() else {
+    return Valid::succeed(b_field);
+  };
+  if !is_subscription {
+    return Valid::<(), String>::fail("pollInterval is only supported on subscription fields".to_string())
+      .when(|| http.poll_interval.is_some())
+      .map_to(b_field);
+  }
+
+  match (http.poll_interval, b_field.resolver.clone()) {
+    (Some(0), _) => Valid::fail("pollInterval must be greater than 0".to_string()),
+    (Some(interval), Some(expr)) => Valid::succeed(
+      b_field
+        .stream(Some(Stream::Poll(expr, Duration::from_millis(interval))))
+        .resolver(None),
+    ),
+    (None, Some(Expression::Unsafe(Operation::Endpoint(req_template, _, _)))) => {
+      Valid::succeed(b_field.stream(Some(Stream::EventStream(req_template))).resolver(None))
+    }
+    _ => Valid::fail("Subscription fields that transform the upstream response require a pollInterval".to_string()),
+  }
+}
+
+/// Caches the IO operations of the resolver for the `maxAge` of the field,
+/// falling back to the one of its type.
+fn update_cache(field: &config::Field, b_field: FieldDefinition, type_of: &config::Type) -> FieldDefinition {
+  match field.cache.as_ref().or(type_of.cache.as_ref()) {
+    Some(cache) => {
+      let resolver = b_field.resolver.clone().map(|expr| Cache::wrap(cache.max_age, expr));
+      b_field.resolver(resolver)
+    }
+    None => b_field,
+  }
+}
+
+/// Finds the field of the query type that resolves the entity by its key fields.
+/// It must be an `@http` field returning a single entity, with an argument for every key field.
+fn to_entity_key(name: &str, type_of: &config::Type, config: &Config) -> Valid<Option<EntityKey>, String> {
+  let Some(key) = &type_of.key else {
+    return Valid::succeed(None);
+  };
+  if !config.server.enable_federation() {
+    return Valid::fail("@key requires federation to be enabled in @server".to_string()).trace("@key");
+  }
+
+  let fields = key.fields.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+  if fields.is_empty() || key.fields.contains(['{', '}']) {
+    return Valid::fail("Key fields must be a space separated list of field names".to_string())
+      .trace("fields")
+      .trace("@key");
+  }
+
+  Valid::from_iter(fields.iter(), |field| match type_of.fields.get(field) {
+    Some(_) => Valid::succeed(()),
+    None => Valid::fail(format!("Key field '{field}' is not a field of {name}")),
+  })
+  .and_then(|_| {
+    let query = config
+      .graphql
+      .schema
+      .query
+      .as_deref()
+      .and_then(|query| config.find_type(query));
+    let resolver = query
+      .into_iter()
+      .flat_map(|query| query.fields.iter())
+      .find(|(_, field)| {
+        field.type_of == name
+          && !field.list
+          && field.http.is_some()
+          && !field.modify.as_ref().is_some_and(|modify| modify.omit)
+          && fields.iter().all(|key| field.args.contains_key(key))
+          && field
+            .args
+            .iter()
+            .all(|(arg_name, arg)| fields.contains(arg_name) || !arg.required)
+      });
+
+    match resolver {
+      Some((field_name, field)) => Valid::succeed(Some(EntityKey {
+        fields: fields.clone(),
+        resolver: field
+          .modify
+          .as_ref()
+          .and_then(|modify| modify.name.clone())
+          .unwrap_or(field_name.clone()),
+      })),
+      None => Valid::fail(format!(
+        "No @http field of the query type resolves {name} with the arguments {}",
+        fields.join(", ")
+      )),
+    }
+  })
+  .trace("@key")
+}
+
+fn validate_auth_provider(config: &Config) -> Valid<(), String> {
+  match config.server.auth {
+    Some(_) => Valid::succeed(()),
+    None => Valid::fail("@protected requires an auth provider to be configured in @server".to_string()),
+  }
+}
+
+/// Fails the resolution of the field for unauthenticated requests when the field or its type is protected.
+fn update_protected(
+  field: &config::Field,
+  b_field: FieldDefinition,
+  type_of: &config::Type,
+  config: &Config,
+) -> Valid<FieldDefinition, String> {
+  if !field.protected && !type_of.protected {
+    return Valid::succeed(b_field);
+  }
+
+  // A protected type without an auth provider is reported on the type itself
+  validate_auth_provider(config)
+    .when(|| field.protected)
+    .map_to(b_field)
+    .map(|b_field| match b_field.stream.clone() {
+      Some(stream) => b_field.stream(Some(Stream::Protected(Box::new(stream)))),
+      None => {
+        let resolver = b_field
+          .resolver
+          .clone()
+          .unwrap_or_else(|| Lambda::context_field(b_field.name.clone()).expression);
+        b_field.resolver(Some(Expression::Protected(Box::new(resolver))))
+      }
+    })
+}
+
+fn update_graphql(
+  field: &config::Field,
+  b_field: FieldDefinition,
+  type_of: &config::Type,
+  config: &Config,
+  operation_type: &GraphQLOperationType,
+) -> Valid<FieldDefinition, String> {
+  match field.graphql.as_ref() {
+    Some(graphql) => match graphql
       .base_url
       .as_ref()
       .map_or_else(|| config.upstream.base_url.as_ref(), Some)
     {
       Some(base_url) => {
-        let mut base_url = base_url.clone();
-        if base_url.ends_with('/') {
-          base_url.pop();
-        }
-        base_url.push_str(http.path.clone().as_str());
-        let query = http.query.clone().iter().map(|(k, v)| (k.clone(), v.clone())).collect();
-        let output_schema = to_json_schema_for_field(field, config);
-        let input_schema = to_json_schema_for_args(&field.args, config);
-
-        Valid::<(), String>::fail("GroupBy is only supported for GET requests".to_string())
-          .when(|| !http.group_by.is_empty() && http.method != Method::GET)
-          .and(Valid::from_iter(http.headers.iter(), |(k, v)| {
-            let name =
-              Valid::from(HeaderName::from_bytes(k.as_bytes()).map_err(|e| ValidationError::new(e.to_string())));
-
-            let value = Valid::from(HeaderValue::from_str(v.as_str()).map_err(|e| ValidationError::new(e.to_string())));
-
-            name.zip(value).map(|(name, value)| (name, value))
-          }))
-          .map(HeaderMap::from_iter)
-          .and_then(|header_map| {
-            RequestTemplate::try_from(
-              Endpoint::new(base_url.to_string())
-                .method(http.method.clone())
-                .query(query)
-                .output(output_schema)
-                .input(input_schema)
-                .body(http.body.clone())
-                .headers(header_map),
-            )
-            .map_err(|e| ValidationError::new(e.to_string()))
-            .into()
-          })
-          .map(|req_template| {
-            if !http.group_by.is_empty() && http.method == Method::GET {
-              b_field.resolver(Some(Expression::Unsafe(Operation::Endpoint(
-                req_template,
-                Some(GroupBy::new(http.group_by.clone())),
-                None,
-              ))))
-            } else {
-              b_field.resolver(Some(Lambda::from_request_template(req_template).expression))
-            }
+        let to_mustache = |(k, v): (&String, &String)| {
+          Valid::from(Mustache::parse(v.as_str()).map_err(|e| ValidationError::new(e.to_string())))
+            .map(|mustache| (k.clone(), mustache))
+        };
+        let headers = Valid::from_iter(graphql.headers.iter(), |(k, v)| {
+          Valid::from(HeaderName::from_bytes(k.as_bytes()).map_err(|e| ValidationError::new(e.to_string())))
+            .and(to_mustache((k, v)))
+        })
+        .trace("headers");
+        let args = Valid::from_iter(graphql.args.iter(), to_mustache)
+          .and_then(|args| {
+            Valid::from_iter(args.iter(), |(_, mustache)| {
+              Valid::from_iter(mustache.expression_segments(), |(parts, filters)| {
+                validate_mustache_parts(type_of, config, true, parts, filters, &b_field.args)
+              })
+            })
+            .map_to(args)
           })
-          .and_then(|b_field| validate_field(type_of, config, &b_field).map_to(b_field))
+          .trace("args");
+
+        headers.zip(args).map(|(headers, args)| {
+          let req_template = GraphqlRequestTemplate::new(
+            base_url.to_owned(),
+            operation_type.clone(),
+            &graphql.name,
+            Some(args),
+            headers,
+          );
+          b_field.resolver(Some(Expression::Unsafe(Operation::GraphQLEndpoint(
+            req_template,
+            graphql.batch,
+            None,
+          ))))
+        })
+      }
+      None => Valid::fail("No base URL defined".to_string()),
+    },
+    None => Valid::succeed(b_field),
+  }
+}
+fn update_grpc(
+  field: &config::Field,
+  b_field: FieldDefinition,
+  type_of: &config::Type,
+  config: &Config,
+) -> Valid<FieldDefinition, String> {
+  match field.grpc.as_ref() {
+    Some(grpc) => match grpc
+      .base_url
+      .as_ref()
+      .map_or_else(|| config.upstream.base_url.as_ref(), Some)
+    {
+      Some(base_url) => {
+        let operation =
+          Valid::from(ProtobufSet::from_proto_file(&grpc.proto_path).map_err(|e| ValidationError::new(e.to_string())))
+            .trace("protoPath")
+            .and_then(|set| {
+              Valid::from(
+                set
+                  .find_operation(&grpc.service, &grpc.method)
+                  .map_err(|e| ValidationError::new(e.to_string())),
+              )
+              .trace("method")
+            })
+            .and_then(|operation| validate_grpc_output(field, grpc, &operation, config).map_to(operation));
+        let headers = Valid::from_iter(grpc.headers.iter(), |(k, v)| {
+          Valid::from(HeaderName::from_bytes(k.as_bytes()).map_err(|e| ValidationError::new(e.to_string())))
+            .and(Valid::from(
+              Mustache::parse(v.as_str()).map_err(|e| ValidationError::new(e.to_string())),
+            ))
+            .map(|mustache| (k.clone(), mustache))
+        })
+        .trace("headers");
+        let body = match grpc.body.as_ref() {
+          Some(body) => {
+            Valid::from(Mustache::parse(body).map_err(|e| ValidationError::new(e.to_string()))).and_then(|mustache| {
+              Valid::from_iter(mustache.expression_segments(), |(parts, filters)| {
+                validate_mustache_parts(type_of, config, true, parts, filters, &b_field.args)
+              })
+              .map_to(Some(mustache))
+            })
+          }
+          None => Valid::succeed(None),
+        }
+        .trace("body");
+
+        operation.zip(headers).zip(body).map(|((operation, headers), body)| {
+          let mut base_url = base_url.clone();
+          if base_url.ends_with('/') {
+            base_url.pop();
+          }
+          let url = format!("{}{}", base_url, operation.path);
+          let group_by = (!grpc.group_by.is_empty()).then(|| GroupBy::new(grpc.group_by.clone()));
+          let req_template = GrpcRequestTemplate::new(url, operation)
+            .headers(headers)
+            .body(body)
+            .list(field.list);
+          b_field.resolver(Some(Expression::Unsafe(Operation::Grpc(req_template, group_by, None))))
+        })
       }
       None => Valid::fail("No base URL defined".to_string()),
     },
     None => Valid::succeed(b_field),
   }
 }
+
+/// Checks that the value returned by a gRPC method fits the GraphQL type of the field.
+/// With `groupBy`, the field resolves to the message found at that path of the response.
+fn validate_grpc_output(
+  field: &config::Field,
+  grpc: &config::Grpc,
+  operation: &ProtobufOperation,
+  config: &Config,
+) -> Valid<(), String> {
+  let group_by = grpc.group_by.split_last().map(|(_, path)| path).unwrap_or_default();
+  let message = group_by
+    .iter()
+    .try_fold(operation.output_type.clone(), |message, name| {
+      match message.get_field_by_json_name(name).map(|field| field.kind()) {
+        Some(Kind::Message(message)) => Ok(message),
+        _ => Err(format!(
+          "groupBy path '{}' doesn't point to a message in '{}'",
+          grpc.group_by.join("."),
+          operation.output_type.full_name()
+        )),
+      }
+    });
+
+  let batchable = Valid::<(), String>::fail(format!(
+    "groupBy requires '{}' to have a single repeated scalar field",
+    operation.input_type.full_name()
+  ))
+  .when(|| !grpc.group_by.is_empty() && operation.batch_field().is_none())
+  .trace("groupBy");
+
+  let output = Valid::from(message.map_err(ValidationError::new))
+    .trace("groupBy")
+    .and_then(|message| {
+      if field.type_of == "JSON" {
+        Valid::succeed(())
+      } else if field.list && grpc.group_by.is_empty() {
+        Valid::fail(format!(
+          "list type '{}' can't be resolved from message '{}'",
+          field.type_of,
+          message.full_name()
+        ))
+      } else {
+        validate_message_type(&field.type_of, &message, config, &[])
+      }
+    });
+
+  batchable.and(output)
+}
+
+fn validate_message_type(
+  type_name: &str,
+  message: &MessageDescriptor,
+  config: &Config,
+  ancestors: &[&str],
+) -> Valid<(), String> {
+  let Some(type_of) = config.find_type(type_name) else {
+    return Valid::fail(format!(
+      "type '{type_name}' can't be resolved from message '{}'",
+      message.full_name()
+    ));
+  };
+  // Recursive messages were already checked further up
+  if ancestors.contains(&type_name) {
+    return Valid::succeed(());
+  }
+  let ancestors = [ancestors, &[type_name]].concat();
+
+  Valid::from_iter(type_of.fields.iter(), |(name, field)| {
+    if field.has_resolver() || field.inline.is_some() {
+      return Valid::succeed(());
+    }
+    let Some(proto_field) = message
+      .get_field_by_json_name(name)
+      .or_else(|| message.get_field_by_name(name))
+    else {
+      return Valid::fail(format!("field is not defined in message '{}'", message.full_name())).trace(name);
+    };
+
+    if field.list != proto_field.is_list() {
+      return Valid::fail(format!(
+        "list type mismatch with field '{}' of message '{}'",
+        proto_field.name(),
+        message.full_name()
+      ))
+      .trace(name);
+    }
+
+    let is_compatible = match proto_field.kind() {
+      _ if field.type_of == "JSON" => true,
+      Kind::Message(message) => {
+        return validate_message_type(&field.type_of, &message, config, &ancestors).trace(name);
+      }
+      _ if proto_field.is_map() => false,
+      Kind::Enum(_) => {
+        field.type_of == "String"
+          || config
+            .find_type(&field.type_of)
+            .is_some_and(|type_| type_.variants.is_some())
+      }
+      Kind::String | Kind::Bytes => ["String", "ID"].contains(&field.type_of.as_str()),
+      Kind::Bool => field.type_of == "Boolean",
+      Kind::Float | Kind::Double => field.type_of == "Float",
+      _ => ["Int", "Float", "ID"].contains(&field.type_of.as_str()),
+    };
+
+    Valid::<(), String>::fail(format!(
+      "type '{}' doesn't match the type of field '{}' in message '{}'",
+      field.type_of,
+      proto_field.name(),
+      message.full_name()
+    ))
+    .when(|| !is_compatible)
+    .trace(name)
+  })
+  .unit()
+}
 fn update_modify(
   field: &config::Field,
   mut b_field: FieldDefinition,
@@ -528,9 +1190,472 @@ fn update_const_field(
     None => Valid::succeed(b_field),
   }
 }
+fn update_expr(
+  field: &config::Field,
+  b_field: FieldDefinition,
+  type_of: &config::Type,
+  config: &Config,
+) -> Valid<FieldDefinition, String> {
+  match field.expr.as_ref() {
+    Some(expr) => {
+      compile_expr(&expr.body, type_of, config, &b_field.args, false).map(|expr| b_field.resolver(Some(expr)))
+    }
+    None => Valid::succeed(b_field),
+  }
+}
+
+/// Compiles the body of `@expr`. Objects are operators, strings are templates and the other values are literals.
+/// Within `map` and `filter`, `{{value}}` is an item of the list, so its paths are only checked outside of them.
+fn compile_expr(
+  body: &serde_json::Value,
+  type_of: &config::Type,
+  config: &Config,
+  args: &[InputFieldDefinition],
+  in_item: bool,
+) -> Valid<Expression, String> {
+  match body {
+    serde_json::Value::String(template) => compile_template(template, type_of, config, args, in_item),
+    serde_json::Value::Object(operator) => match operator.iter().collect::<Vec<_>>().as_slice() {
+      [(name, operands)] => compile_operator(name, operands, type_of, config, args, in_item).trace(name),
+      _ => Valid::fail("an operator must be an object with a single key".to_string()),
+    },
+    value => Valid::succeed(Expression::Literal(value.clone())),
+  }
+}
+
+fn compile_operator(
+  name: &str,
+  operands: &serde_json::Value,
+  type_of: &config::Type,
+  config: &Config,
+  args: &[InputFieldDefinition],
+  in_item: bool,
+) -> Valid<Expression, String> {
+  let compile = |body: &serde_json::Value| compile_expr(body, type_of, config, args, in_item);
+  let compile_list = || match operands.as_array() {
+    Some(operands) => Valid::from_iter(operands.iter(), compile),
+    None => Valid::fail(format!("'{name}' expects a list of operands")),
+  };
+  let compile_pair = || {
+    compile_list().and_then(|operands| match <[Expression; 2]>::try_from(operands) {
+      Ok([left, right]) => Valid::succeed((Box::new(left), Box::new(right))),
+      Err(_) => Valid::fail(format!("'{name}' expects 2 operands")),
+    })
+  };
+  let compile_key = |key: &str, in_item: bool| match operands.get(key) {
+    Some(body) => compile_expr(body, type_of, config, args, in_item).trace(key),
+    None => Valid::fail(format!("'{name}' expects a '{key}'")),
+  };
+  let check_keys = |keys: &[&str]| match operands.as_object() {
+    Some(operands) => Valid::from_iter(operands.keys(), |key| match keys.contains(&key.as_str()) {
+      true => Valid::succeed(()),
+      false => Valid::fail(format!("unknown key '{key}'")),
+    })
+    .unit(),
+    None => Valid::fail(format!("'{name}' expects an object with the keys {}", keys.join(", "))),
+  };
+
+  match name {
+    "if" => check_keys(&["cond", "then", "else"]).and_then(|_| {
+      let otherwise = match operands.get("else") {
+        Some(body) => compile(body).trace("else"),
+        None => Valid::succeed(Expression::Literal(serde_json::Value::Null)),
+      };
+      compile_key("cond", in_item)
+        .zip(compile_key("then", in_item))
+        .zip(otherwise)
+        .map(|((cond, then), otherwise)| Expression::If(Box::new(cond), Box::new(then), Box::new(otherwise)))
+    }),
+    "and" => compile_list().map(Expression::And),
+    "or" => compile_list().map(Expression::Or),
+    "concat" => compile_list().map(Expression::Concat),
+    "coalesce" => compile_list().map(Expression::Coalesce),
+    "not" => compile(operands).map(|expr| Expression::Not(Box::new(expr))),
+    "eq" => compile_pair().map(|(left, right)| Expression::EqualTo(left, right)),
+    "neq" => compile_pair().map(|(left, right)| Expression::Not(Box::new(Expression::EqualTo(left, right)))),
+    "lt" => compile_pair().map(|(left, right)| Expression::Compare(Comparison::Lt, left, right)),
+    "lte" => compile_pair().map(|(left, right)| Expression::Compare(Comparison::Lte, left, right)),
+    "gt" => compile_pair().map(|(left, right)| Expression::Compare(Comparison::Gt, left, right)),
+    "gte" => compile_pair().map(|(left, right)| Expression::Compare(Comparison::Gte, left, right)),
+    "add" => compile_pair().map(|(left, right)| Expression::Math(MathOperation::Add, left, right)),
+    "sub" => compile_pair().map(|(left, right)| Expression::Math(MathOperation::Sub, left, right)),
+    "mul" => compile_pair().map(|(left, right)| Expression::Math(MathOperation::Mul, left, right)),
+    "div" => compile_pair().map(|(left, right)| Expression::Math(MathOperation::Div, left, right)),
+    "mod" => compile_pair().map(|(left, right)| Expression::Math(MathOperation::Mod, left, right)),
+    "map" | "filter" => check_keys(&["list", "expr"]).and_then(|_| {
+      compile_key("list", in_item)
+        .zip(compile_key("expr", true))
+        .map(|(list, expr)| match name {
+          "map" => Expression::Map(Box::new(list), Box::new(expr)),
+          _ => Expression::Filter(Box::new(list), Box::new(expr)),
+        })
+    }),
+    _ => Valid::fail(format!("unknown operator '{name}'")),
+  }
+}
+
+/// Resolves the field with the resolver of the called query field, that the server context links to the data loaders of
+/// the query field. The arguments that aren't passed take their default value.
+fn update_call(
+  field: &config::Field,
+  b_field: FieldDefinition,
+  type_of: &config::Type,
+  config: &Config,
+) -> Valid<FieldDefinition, String> {
+  let Some(call) = field.call.as_ref() else {
+    return Valid::succeed(b_field);
+  };
+  let query_name = config.graphql.schema.query.as_deref().unwrap_or_default();
+  let Some(query) = config.find_type(query_name) else {
+    return Valid::fail("Query type is not defined".to_string());
+  };
+  let Some(query_field) = query.fields.get(&call.query) else {
+    return Valid::fail(format!("query '{}' is not defined", call.query));
+  };
+  if calls_itself(query, &call.query) {
+    return Valid::fail(format!("query '{}' calls itself", call.query));
+  }
+
+  // The called field is compiled, and its errors are reported, with the other fields of the query type
+  if query_field.resolvable_directives().is_empty() {
+    return Valid::fail(format!("query '{}' has no resolver", call.query));
+  }
+  let target_type = to_type(
+    &query_field.type_of,
+    query_field.list,
+    query_field.required,
+    query_field.list_type_required,
+  );
+  if !is_compatible(&target_type, &b_field.of_type) {
+    return Valid::fail(format!(
+      "query '{}' returns '{}', expected '{}'",
+      call.query, target_type, b_field.of_type
+    ));
+  }
+  let target_args = to_args(query_field).to_result().unwrap_or_default();
+
+  let passed = Valid::from_iter(call.args.iter(), |(name, value)| {
+    let expected = target_args.iter().find(|arg| &arg.name == name).map(|arg| &arg.of_type);
+    match (expected, call_arg_type(value, type_of, config, &b_field.args)) {
+      (None, _) => Valid::fail(format!("no argument '{name}' on query '{}'", call.query)),
+      (Some(expected), Some(of_type)) if !is_compatible(&of_type, expected) => Valid::fail(format!(
+        "argument '{name}' of query '{}' is of type '{expected}', found '{of_type}'",
+        call.query
+      )),
+      _ => compile_expr(value, type_of, config, &b_field.args, false).map(|expr| (name.clone(), expr)),
+    }
+    .trace(name)
+  });
+  let defaults = Valid::from_iter(
+    target_args.iter().filter(|arg| !call.args.contains_key(&arg.name)),
+    |arg| match &arg.default_value {
+      Some(value) => Valid::succeed(Some((arg.name.clone(), Expression::Literal(value.clone())))),
+      None if arg.of_type.is_nullable() => Valid::succeed(None),
+      None => Valid::fail(format!("argument '{}' of query '{}' is required", arg.name, call.query)),
+    },
+  );
+
+  passed.zip(defaults).map(|(mut args, defaults)| {
+    args.extend(defaults.into_iter().flatten());
+    b_field.resolver(Some(Expression::Call(Call::new(call.query.clone(), args))))
+  })
+}
+
+/// Links every `@call` to the resolver of the query field it calls, once all of them are compiled.
+/// The called resolver is cached like the other resolvers of the calling field.
+fn link_calls(config: &Config, mut definitions: Vec<Definition>) -> Vec<Definition> {
+  let query_name = config.graphql.schema.query.as_deref().unwrap_or_default();
+  let Some(query) = config.find_type(query_name) else {
+    return definitions;
+  };
+  let compiled: HashMap<String, Expression> = definitions
+    .iter()
+    .filter_map(|def| match def {
+      Definition::ObjectTypeDefinition(def) if def.name == query_name => Some(def),
+      _ => None,
+    })
+    .flat_map(|def| def.fields.iter())
+    .filter_map(|field| Some((field.name.clone(), field.resolver.clone()?)))
+    .collect();
+  let mut linked = HashMap::new();
+
+  for def in definitions.iter_mut() {
+    let Definition::ObjectTypeDefinition(def) = def else {
+      continue;
+    };
+    let Some(type_of) = config.find_type(&def.name) else {
+      continue;
+    };
+    for field in def.fields.iter_mut() {
+      if let Some(resolver) = field.resolver.as_mut() {
+        let max_age = cache_max_age(type_of, &field.name);
+        link_calls_of(resolver, max_age, query, &compiled, &mut linked);
+      }
+    }
+  }
+  definitions
+}
+
+fn link_calls_of(
+  expr: &mut Expression,
+  max_age: Option<NonZeroU64>,
+  query: &config::Type,
+  compiled: &HashMap<String, Expression>,
+  linked: &mut HashMap<String, Expression>,
+) {
+  expr.walk_mut(&mut |expr| {
+    let Expression::Call(call) = expr else {
+      return;
+    };
+    // The calls nested in a linked resolver are already linked
+    if call.expression().is_some() {
+      return;
+    }
+    let Some(target) = linked_resolver(call.query(), query, compiled, linked) else {
+      return;
+    };
+    call.link(match max_age {
+      Some(max_age) => Cache::wrap(max_age, target),
+      None => target,
+    });
+  });
+}
+
+/// The resolver of the query field, with its own calls linked. Calls can't be recursive.
+fn linked_resolver(
+  name: &str,
+  query: &config::Type,
+  compiled: &HashMap<String, Expression>,
+  linked: &mut HashMap<String, Expression>,
+) -> Option<Expression> {
+  if let Some(expr) = linked.get(name) {
+    return Some(expr.clone());
+  }
+  let mut expr = compiled.get(name)?.clone();
+  link_calls_of(&mut expr, cache_max_age(query, name), query, compiled, linked);
+  linked.insert(name.to_string(), expr.clone());
+  Some(expr)
+}
+
+fn cache_max_age(type_of: &config::Type, field_name: &str) -> Option<NonZeroU64> {
+  let field = type_of.fields.get(field_name);
+  field
+    .and_then(|field| field.cache.as_ref())
+    .or(type_of.cache.as_ref())
+    .map(|cache| cache.max_age)
+}
+
+/// Resolves the field with the steps of its pipeline, nesting every step in a `Pipe` with the previous ones.
+/// The templates of a step are validated against the type of the output of the previous step.
+fn update_pipeline(
+  field: &config::Field,
+  b_field: FieldDefinition,
+  type_of: &config::Type,
+  config: &Config,
+) -> Valid<FieldDefinition, String> {
+  let Some(pipeline) = field.pipeline.as_ref() else {
+    return Valid::succeed(b_field);
+  };
+  if pipeline.steps.is_empty() {
+    return Valid::fail("a pipeline requires at least one step".to_string());
+  }
+  let last = pipeline.steps.len() - 1;
+
+  Valid::from_iter(pipeline.steps.iter().enumerate(), |(i, step)| {
+    let output = match (&step.type_of, i == last) {
+      (None, false) => Valid::fail("the type of the output is required on every step but the last one".to_string()),
+      (None, true) => Valid::succeed(b_field.of_type.clone()),
+      (Some(text), _) => match step_type(text) {
+        None => Valid::fail(format!("'{text}' is not a valid type")),
+        Some(of_type) if i == last && of_type.to_string() != b_field.of_type.to_string() => Valid::fail(format!(
+          "the last step returns '{of_type}', expected '{}'",
+          b_field.of_type
+        )),
+        Some(Type::ListType { .. }) if i < last => {
+          Valid::fail("only the last step can return a list, the next step has no way to read its items".to_string())
+        }
+        Some(of_type) if !is_scalar(of_type.name()) && config.find_type(of_type.name()).is_none() => {
+          Valid::fail(format!("type '{}' is not defined", of_type.name()))
+        }
+        Some(of_type) => Valid::succeed(of_type),
+      },
+    };
+    let input = match i {
+      0 => type_of.clone(),
+      _ => pipeline.steps[i - 1]
+        .type_of
+        .as_deref()
+        .and_then(step_type)
+        .and_then(|of_type| config.find_type(of_type.name()))
+        .cloned()
+        .unwrap_or_default(),
+    };
+
+    output
+      .and_then(|of_type| {
+        let step_field = config::Field {
+          type_of: of_type.name().to_string(),
+          list: matches!(of_type, Type::ListType { .. }),
+          required: !of_type.is_nullable(),
+          list_type_required: matches!(&of_type, Type::ListType { of_type, .. } if !of_type.is_nullable()),
+          args: field.args.clone(),
+          http: step.http.clone(),
+          call: step.call.clone(),
+          expr: step.expr.clone(),
+          ..Default::default()
+        };
+        let step_b_field = b_field.clone().resolver(None).of_type(of_type);
+        match step_field.resolvable_directives().as_slice() {
+          [_] => update_http(&step_field, step_b_field, &input, config)
+            .trace("http")
+            .and_then(|b_field| update_call(&step_field, b_field, &input, config).trace("call"))
+            .and_then(|b_field| update_expr(&step_field, b_field, &input, config).trace("expr")),
+          [] => Valid::fail("a step requires one of http, call and expr".to_string()),
+          directives => Valid::fail(format!("Multiple resolvers detected [{}]", directives.join(", "))),
+        }
+      })
+      .and_then(|b_field| Valid::from_option(b_field.resolver, "the step has no resolver".to_string()))
+      .trace(&i.to_string())
+  })
+  .map(|steps| {
+    let resolver = steps
+      .into_iter()
+      .reduce(|input, step| Expression::Pipe(Box::new(input), Box::new(step)));
+    b_field.resolver(resolver)
+  })
+}
+
+/// Parses the `type` of a pipeline step, written the way the type of a field is, e.g. `[Post!]!`.
+fn step_type(text: &str) -> Option<Type> {
+  fn convert(ty: async_graphql::parser::types::Type) -> Option<Type> {
+    match ty.base {
+      BaseType::Named(name) if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => {
+        Some(Type::NamedType { name: name.to_string(), non_null: !ty.nullable })
+      }
+      BaseType::Named(_) => None,
+      BaseType::List(of_type) => Some(Type::ListType { of_type: Box::new(convert(*of_type)?), non_null: !ty.nullable }),
+    }
+  }
+  async_graphql::parser::types::Type::new(text).and_then(convert)
+}
+
+/// Whether the query field ends up calling itself, through the fields it calls.
+/// Every field on such a cycle is rejected, so that the resolvers of the called fields can be built recursively.
+fn calls_itself(query: &config::Type, name: &str) -> bool {
+  let mut visited = HashSet::new();
+  let mut pending = vec![name];
+  while let Some(current) = pending.pop() {
+    let Some(field) = query.fields.get(current) else {
+      continue;
+    };
+    for called in field.called_queries() {
+      if called == name {
+        return true;
+      }
+      if visited.insert(called) {
+        pending.push(called);
+      }
+    }
+  }
+  false
+}
+
+/// The type of the value of an argument of `@call`, when it's known before the value is evaluated.
+fn call_arg_type(
+  value: &serde_json::Value,
+  type_of: &config::Type,
+  config: &Config,
+  args: &[InputFieldDefinition],
+) -> Option<Type> {
+  let named = |name: &str| Some(Type::NamedType { name: name.to_string(), non_null: true });
+  match value {
+    serde_json::Value::String(template) => match Mustache::parse(template).ok()?.segments() {
+      [Segment::Expression(parts, _)] => match parts.split_first() {
+        Some((head, path)) if head == "value" || head == "input" => match get_value_type(config, type_of, path)? {
+          (of_type, len) if len == path.len() => Some(of_type),
+          _ => None,
+        },
+        Some((head, [name])) if head == "args" => {
+          args.iter().find(|arg| &arg.name == name).map(|arg| arg.of_type.clone())
+        }
+        _ => None,
+      },
+      _ => named("String"),
+    },
+    serde_json::Value::Number(number) if number.is_f64() => named("Float"),
+    serde_json::Value::Number(_) => named("Int"),
+    serde_json::Value::Bool(_) => named("Boolean"),
+    _ => None,
+  }
+}
+
+/// Whether a value of the source type can be used where the target type is expected, regardless of nullability.
+fn is_compatible(source: &Type, target: &Type) -> bool {
+  match (source, target) {
+    (Type::ListType { of_type: source, .. }, Type::ListType { of_type: target, .. }) => is_compatible(source, target),
+    (Type::NamedType { name: source, .. }, Type::NamedType { name: target, .. }) => {
+      source == target
+        || target == "JSON"
+        || (target == "ID" && matches!(source.as_str(), "String" | "Int"))
+        || (target == "Float" && source == "Int")
+    }
+    _ => false,
+  }
+}
+
+/// A template that is a single expression keeps the type of its value, any other template is a string.
+fn compile_template(
+  template: &str,
+  type_of: &config::Type,
+  config: &Config,
+  args: &[InputFieldDefinition],
+  in_item: bool,
+) -> Valid<Expression, String> {
+  let mustache = match Mustache::parse(template) {
+    Ok(mustache) => mustache,
+    Err(e) => return Valid::fail(e.to_string()),
+  };
+  let compile_segment = |segment: &Segment| match segment {
+    Segment::Literal(literal) => Valid::succeed(Expression::Literal(serde_json::Value::from(literal.clone()))),
+    Segment::Expression(parts, filters) => {
+      if !filters.is_empty() {
+        return Valid::fail(format!("filters aren't supported in '{template}'"));
+      }
+      match parts.split_first().map(|(head, tail)| (head.as_str(), tail)) {
+        Some(("value" | "input", [])) => Valid::succeed(Expression::Context(Context::Value)),
+        Some(("value" | "input", path))
+          if in_item || get_value_type(config, type_of, path).is_some_and(|(_, len)| len == path.len()) =>
+        {
+          Valid::succeed(Expression::Context(Context::Path(path.to_vec())))
+        }
+        Some(("value" | "input", path)) => Valid::fail(format!("no value '{}' found", path.join("."))),
+        Some(("args", [name, ..])) => match args.iter().any(|arg| &arg.name == name) {
+          true => Valid::succeed(Expression::Context(Context::Args(parts[1..].to_vec()))),
+          false => Valid::fail(format!("no argument '{name}' found")),
+        },
+        Some(("args", [])) => Valid::fail("too few parts in template".to_string()),
+        Some((head, _)) => Valid::fail(format!("unknown template directive '{head}'")),
+        None => Valid::fail("too few parts in template".to_string()),
+      }
+    }
+  };
+
+  Valid::from_iter(mustache.segments(), compile_segment).map(|mut exprs| {
+    if matches!(mustache.segments(), [Segment::Expression(..)]) {
+      exprs.remove(0)
+    } else if mustache.is_const() {
+      Expression::Literal(serde_json::Value::from(template))
+    } else {
+      Expression::Concat(exprs)
+    }
+  })
+}
+
 fn is_scalar(type_name: &str) -> bool {
   ["String", "Int", "Float", "Boolean", "ID", "JSON"].contains(&type_name)
 }
+type InvalidPathHandler = dyn Fn(&str, &[String]) -> Valid<Type, String>;
+
 // Helper function to recursively process the path and return the corresponding type
 fn process_path(
   path: &[String],
@@ -538,7 +1663,7 @@ fn process_path(
   type_info: &config::Type,
   is_required: bool,
   config: &Config,
-  invalid_path_handler: &dyn Fn(&str, &[String]) -> Valid<Type, String>,
+  invalid_path_handler: &InvalidPathHandler,
 ) -> Valid<Type, String> {
   if let Some((field_name, remaining_path)) = path.split_first() {
     if field_name.parse::<usize>().is_ok() {
@@ -588,7 +1713,7 @@ fn process_field_within_type(
   type_info: &config::Type,
   is_required: bool,
   config: &Config,
-  invalid_path_handler: &dyn Fn(&str, &[String]) -> Valid<Type, String>,
+  invalid_path_handler: &InvalidPathHandler,
 ) -> Valid<Type, String> {
   if let Some(next_field) = type_info.fields.get(field_name) {
     if next_field.has_resolver() {
@@ -597,7 +1722,13 @@ fn process_field_within_type(
         {
           let next_dir_http = next_field.http.as_ref().map(|_| "http");
           let next_dir_const = next_field.const_field.as_ref().map(|_| "const");
-          next_dir_http.or(next_dir_const).unwrap_or("unsafe")
+          let next_dir_graphql = next_field.graphql.as_ref().map(|_| "graphQL");
+          let next_dir_grpc = next_field.grpc.as_ref().map(|_| "grpc");
+          next_dir_http
+            .or(next_dir_const)
+            .or(next_dir_graphql)
+            .or(next_dir_grpc)
+            .unwrap_or("unsafe")
         },
         field.type_of,
         field_name
@@ -711,7 +1842,7 @@ pub fn to_json_schema(type_of: &str, required: bool, list: bool, config: &Config
     Some(type_) => {
       let mut schema_fields = HashMap::new();
       for (name, field) in type_.fields.iter() {
-        if field.unsafe_operation.is_none() && field.http.is_none() {
+        if field.unsafe_operation.is_none() && field.http.is_none() && field.graphql.is_none() && field.grpc.is_none() {
           schema_fields.insert(name.clone(), to_json_schema_for_field(field, config));
         }
       }
@@ -1,14 +1,19 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_graphql::dynamic::{
-  FieldFuture, FieldValue, SchemaBuilder, {self},
+  FieldFuture, FieldValue, ResolverContext, SchemaBuilder, SubscriptionFieldFuture, {self},
 };
-use async_graphql_value::ConstValue;
+use async_graphql::futures_util::{self, StreamExt};
+use async_graphql_value::{ConstValue, Name};
+use opentelemetry::trace::SpanKind;
+use opentelemetry::Context;
 
-use crate::blueprint::{Blueprint, Definition, Type};
+use crate::blueprint::{Blueprint, Definition, ObjectTypeDefinition, Type};
 use crate::http::RequestContext;
-use crate::lambda::EvaluationContext;
+use crate::lambda::{ArgsContext, EvaluationContext};
+use crate::telemetry;
 
 fn to_type_ref(type_of: &Type) -> dynamic::TypeRef {
   match type_of {
@@ -38,10 +43,12 @@ fn to_type(def: &Definition) -> dynamic::Type {
         let field = field.clone();
         let type_ref = to_type_ref(&field.of_type);
         let field_name = &field.name.clone();
+        let span_name = format!("{}.{}", def.name, field.name);
         let mut dyn_schema_field = dynamic::Field::new(field_name, type_ref, move |ctx| {
           let req_ctx = ctx.ctx.data::<Arc<RequestContext>>().unwrap();
           let field_name = field.name.clone();
           let resolver = field.resolver.clone();
+          let span_name = span_name.clone();
           FieldFuture::new(async move {
             match resolver {
               None => {
@@ -50,7 +57,8 @@ fn to_type(def: &Definition) -> dynamic::Type {
               }
               Some(expr) => {
                 let ctx = EvaluationContext::new(req_ctx, &ctx);
-                let const_value = expr.eval(&ctx).await?;
+                let cx = telemetry::start_span(&Context::current(), span_name, SpanKind::Internal, vec![]);
+                let const_value = telemetry::in_span(cx, expr.eval(&ctx)).await?;
                 let p = match const_value {
                   ConstValue::List(a) => FieldValue::list(a),
                   a => FieldValue::from(a),
@@ -72,6 +80,9 @@ fn to_type(def: &Definition) -> dynamic::Type {
       for interface in def.implements.iter() {
         object = object.implement(interface.clone());
       }
+      if let Some(key) = &def.key {
+        object = object.key(key.fields.join(" "));
+      }
 
       dynamic::Type::Object(object)
     }
@@ -121,18 +132,128 @@ fn to_type(def: &Definition) -> dynamic::Type {
   }
 }
 
+fn to_subscription(def: &ObjectTypeDefinition) -> dynamic::Subscription {
+  let mut subscription = dynamic::Subscription::new(def.name.clone());
+  for field in def.fields.iter() {
+    let stream = field.stream.clone();
+    let mut dyn_schema_field =
+      dynamic::SubscriptionField::new(field.name.clone(), to_type_ref(&field.of_type), move |ctx| {
+        let stream = stream.clone();
+        SubscriptionFieldFuture::new(async move {
+          let req_ctx = ctx.ctx.data::<Arc<RequestContext>>()?.clone();
+          let graphql_ctx = ArgsContext::new(ctx.args.as_index_map().clone());
+          let values = match stream {
+            Some(stream) => stream.subscribe(req_ctx, graphql_ctx),
+            None => Box::pin(futures_util::stream::empty()),
+          };
+          Ok(values.map(|value| {
+            let value = match value? {
+              ConstValue::List(a) => FieldValue::list(a),
+              a => FieldValue::from(a),
+            };
+            Ok(value)
+          }))
+        })
+      });
+    if let Some(description) = &field.description {
+      dyn_schema_field = dyn_schema_field.description(description);
+    }
+    for arg in field.args.iter() {
+      dyn_schema_field =
+        dyn_schema_field.argument(dynamic::InputValue::new(arg.name.clone(), to_type_ref(&arg.of_type)));
+    }
+    subscription = subscription.field(dyn_schema_field);
+  }
+
+  subscription
+}
+
 fn create(blueprint: &Blueprint) -> SchemaBuilder {
   let query = blueprint.query();
   let mutation = blueprint.mutation();
-  let mut schema = dynamic::Schema::build(query.as_str(), mutation.as_deref(), None);
+  let subscription = blueprint.subscription();
+  let mut schema = dynamic::Schema::build(query.as_str(), mutation.as_deref(), subscription.as_deref());
 
   for def in blueprint.definitions.iter() {
-    schema = schema.register(to_type(def));
+    match def {
+      Definition::ObjectTypeDefinition(def) if subscription.as_deref() == Some(def.name.as_str()) => {
+        schema = schema.register(to_subscription(def));
+      }
+      def => schema = schema.register(to_type(def)),
+    }
+  }
+
+  if blueprint.server.enable_federation {
+    schema = schema
+      .enable_federation()
+      .entity_resolver(to_entity_resolver(blueprint));
   }
 
   schema
 }
 
+/// Resolves the `_entities` field of a federated schema.
+/// Every representation is resolved by the query field of its key,
+/// with the key fields as arguments, so that the requests can be batched by its data loader.
+fn to_entity_resolver(blueprint: &Blueprint) -> impl for<'a> Fn(ResolverContext<'a>) -> FieldFuture<'a> {
+  let query = blueprint.query();
+  let query_fields = blueprint
+    .definitions
+    .iter()
+    .find_map(|def| match def {
+      Definition::ObjectTypeDefinition(def) if def.name == query => Some(&def.fields),
+      _ => None,
+    })
+    .cloned()
+    .unwrap_or_default();
+  let entities = blueprint
+    .definitions
+    .iter()
+    .filter_map(|def| match def {
+      Definition::ObjectTypeDefinition(def) => def.key.as_ref().map(|key| (def.name.clone(), key.clone())),
+      _ => None,
+    })
+    .filter_map(|(name, key)| {
+      let field = query_fields.iter().find(|field| field.name == key.resolver)?;
+      Some((name, (key.fields, field.resolver.clone()?)))
+    })
+    .collect::<HashMap<_, _>>();
+  let entities = Arc::new(entities);
+
+  move |ctx| {
+    let entities = entities.clone();
+    FieldFuture::new(async move {
+      let req_ctx = ctx.ctx.data::<Arc<RequestContext>>()?;
+      let mut futures = Vec::new();
+      for representation in ctx.args.try_get("representations")?.list()?.iter() {
+        let representation = representation.object()?;
+        let type_name = representation.try_get("__typename")?.string()?.to_string();
+        let (fields, expr) = entities
+          .get(&type_name)
+          .ok_or_else(|| async_graphql::Error::new(format!("{type_name} is not an entity")))?;
+        let args = fields
+          .iter()
+          .map(|field| {
+            let value = representation.get(field).map(|value| value.as_value().clone());
+            (Name::new(field), value.unwrap_or_default())
+          })
+          .collect();
+        futures.push(async move {
+          let graphql_ctx = ArgsContext::new(args);
+          let value = expr.eval(&EvaluationContext::new(req_ctx, &graphql_ctx)).await?;
+          Ok::<_, async_graphql::Error>(match value {
+            ConstValue::Null => FieldValue::NULL,
+            value => FieldValue::from(value).with_type(type_name),
+          })
+        });
+      }
+
+      let values = futures_util::future::try_join_all(futures).await?;
+      Ok(Some(FieldValue::list(values)))
+    })
+  }
+}
+
 impl From<&Blueprint> for SchemaBuilder {
   fn from(blueprint: &Blueprint) -> Self {
     create(blueprint)
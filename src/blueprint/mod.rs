@@ -1,12 +1,22 @@
 mod blueprint;
 mod compress;
+mod cors;
+mod error_codes;
 mod from_config;
 mod into_schema;
+mod operation_metrics;
+mod operation_tracing;
+mod query_limits;
 mod timeout;
 
 // TODO: make it private
 mod server;
 
 pub use blueprint::*;
+pub use cors::Cors;
+pub use error_codes::{is_request_error, ErrorCodes};
+pub use operation_metrics::OperationMetrics;
+pub use operation_tracing::OperationTracing;
+pub use query_limits::QueryLimiter;
 pub use server::*;
 pub use timeout::GlobalTimeout;
@@ -0,0 +1,28 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use async_graphql::extensions::{Extension, ExtensionContext, ExtensionFactory, NextExecute};
+use async_graphql::Response;
+
+use crate::metrics::metrics;
+
+/// Counts and times the executed operations by name.
+pub struct OperationMetrics;
+
+impl ExtensionFactory for OperationMetrics {
+  fn create(&self) -> Arc<dyn Extension> {
+    Arc::new(OperationMetricsExtension)
+  }
+}
+
+struct OperationMetricsExtension;
+
+#[async_trait::async_trait]
+impl Extension for OperationMetricsExtension {
+  async fn execute(&self, ctx: &ExtensionContext<'_>, operation_name: Option<&str>, next: NextExecute<'_>) -> Response {
+    let start = Instant::now();
+    let response = next.run(ctx, operation_name).await;
+    metrics().observe_operation(operation_name, start.elapsed());
+    response
+  }
+}
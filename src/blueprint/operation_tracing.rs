@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use async_graphql::extensions::{Extension, ExtensionContext, ExtensionFactory, NextExecute};
+use async_graphql::Response;
+use opentelemetry::trace::{FutureExt, SpanKind, Status, TraceContextExt};
+use opentelemetry::{Context, KeyValue};
+
+use crate::telemetry;
+
+/// Creates a span for every executed operation, as a child of the trace of the incoming request.
+pub struct OperationTracing;
+
+impl ExtensionFactory for OperationTracing {
+  fn create(&self) -> Arc<dyn Extension> {
+    Arc::new(OperationTracingExtension)
+  }
+}
+
+struct OperationTracingExtension;
+
+#[async_trait::async_trait]
+impl Extension for OperationTracingExtension {
+  async fn execute(&self, ctx: &ExtensionContext<'_>, operation_name: Option<&str>, next: NextExecute<'_>) -> Response {
+    let operation = operation_name.unwrap_or("anonymous").to_string();
+    let cx = telemetry::start_span(
+      &Context::current(),
+      format!("graphql {operation}"),
+      SpanKind::Server,
+      vec![KeyValue::new("graphql.operation.name", operation)],
+    );
+    let response = next.run(ctx, operation_name).with_context(cx.clone()).await;
+    if let Some(error) = response.errors.first() {
+      cx.span().set_status(Status::error(error.message.clone()));
+    }
+    cx.span().end();
+    response
+  }
+}
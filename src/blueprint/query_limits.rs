@@ -0,0 +1,380 @@
+#![allow(clippy::too_many_arguments)]
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+
+use async_graphql::extensions::{Extension, ExtensionContext, ExtensionFactory, NextParseQuery};
+use async_graphql::parser::types::{ExecutableDocument, OperationType, Selection, SelectionSet};
+use async_graphql::{Name, Positioned, ServerError, ServerResult, Variables};
+
+use super::error_codes::{with_code, VALIDATION_FAILED};
+use crate::blueprint::{Blueprint, Definition, FieldDefinition};
+use crate::config::QueryLimits;
+
+/// Rejects the queries that exceed the `queryLimits` of `@server`, before they are executed.
+pub struct QueryLimiter {
+  limits: QueryLimits,
+  costs: Arc<QueryCosts>,
+}
+
+impl QueryLimiter {
+  pub fn new(blueprint: &Blueprint, limits: QueryLimits) -> Self {
+    Self { limits, costs: Arc::new(QueryCosts::from(blueprint)) }
+  }
+}
+
+impl ExtensionFactory for QueryLimiter {
+  fn create(&self) -> Arc<dyn Extension> {
+    Arc::new(QueryLimiterExtension { limits: self.limits.clone(), costs: self.costs.clone() })
+  }
+}
+
+struct QueryLimiterExtension {
+  limits: QueryLimits,
+  costs: Arc<QueryCosts>,
+}
+
+#[async_trait::async_trait]
+impl Extension for QueryLimiterExtension {
+  async fn parse_query(
+    &self,
+    ctx: &ExtensionContext<'_>,
+    query: &str,
+    variables: &Variables,
+    next: NextParseQuery<'_>,
+  ) -> ServerResult<ExecutableDocument> {
+    let document = next.run(ctx, query, variables).await?;
+    let mut measurer = Measurer::new(&self.costs, &self.limits, &document);
+    for (_, operation) in document.operations.iter() {
+      let root = self.costs.root(operation.node.ty);
+      measurer
+        .measure_operation(root, &operation.node.selection_set)
+        .map_err(|message| with_code(ServerError::new(message, Some(operation.pos)), VALIDATION_FAILED))?;
+    }
+    Ok(document)
+  }
+}
+
+fn exceeds(limit: &str, value: u64, max: Option<u64>) -> Result<(), String> {
+  match max {
+    Some(max) if value > max => Err(format!("The query exceeds the maximum {limit} of {max}")),
+    _ => Ok(()),
+  }
+}
+
+/// The shape of an operation, as compared against the query limits.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+struct Measure {
+  depth: usize,
+  complexity: u64,
+  aliases: usize,
+  root_fields: usize,
+}
+
+/// The cost and output type of the fields of every object and interface, and the members of every union.
+#[derive(Debug)]
+struct QueryCosts {
+  query: String,
+  mutation: Option<String>,
+  subscription: Option<String>,
+  fields: HashMap<String, HashMap<String, (u64, String)>>,
+  unions: HashMap<String, BTreeSet<String>>,
+}
+
+impl Measure {
+  /// Adds the measure of a selection to the measure of its selection set.
+  fn add(&mut self, other: Measure) {
+    self.depth = self.depth.max(other.depth);
+    self.complexity = self.complexity.saturating_add(other.complexity);
+    self.aliases = self.aliases.saturating_add(other.aliases);
+    self.root_fields = self.root_fields.saturating_add(other.root_fields);
+  }
+}
+
+impl From<&Blueprint> for QueryCosts {
+  fn from(blueprint: &Blueprint) -> Self {
+    let to_costs = |fields: &Vec<FieldDefinition>| {
+      fields
+        .iter()
+        .map(|field| (field.name.clone(), (field.cost, field.of_type.name().to_string())))
+        .collect::<HashMap<_, _>>()
+    };
+    let mut fields = HashMap::new();
+    let mut unions = HashMap::new();
+    for definition in blueprint.definitions.iter() {
+      match definition {
+        Definition::ObjectTypeDefinition(def) => {
+          fields.insert(def.name.clone(), to_costs(&def.fields));
+        }
+        Definition::InterfaceTypeDefinition(def) => {
+          fields.insert(def.name.clone(), to_costs(&def.fields));
+        }
+        Definition::UnionTypeDefinition(def) => {
+          unions.insert(def.name.clone(), def.types.clone());
+        }
+        _ => {}
+      }
+    }
+    QueryCosts {
+      query: blueprint.query(),
+      mutation: blueprint.mutation(),
+      subscription: blueprint.subscription(),
+      fields,
+      unions,
+    }
+  }
+}
+
+impl QueryCosts {
+  fn root(&self, operation_type: OperationType) -> Option<&str> {
+    match operation_type {
+      OperationType::Query => Some(self.query.as_str()),
+      OperationType::Mutation => self.mutation.as_deref(),
+      OperationType::Subscription => self.subscription.as_deref(),
+    }
+  }
+
+  fn field(&self, type_name: Option<&str>, field_name: &str) -> Option<&(u64, String)> {
+    self.fields.get(type_name?)?.get(field_name)
+  }
+
+  /// The complexity of the most expensive operation that selects every field once, without aliases.
+  /// Returns `None` when the schema is recursive and the depth of the operations isn't limited.
+  fn worst_case(&self, max_depth: Option<usize>) -> Option<u64> {
+    let mut memo = HashMap::new();
+    [
+      Some(self.query.as_str()),
+      self.mutation.as_deref(),
+      self.subscription.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|root| self.worst_case_of(root, max_depth, &mut Vec::new(), &mut memo))
+    .try_fold(0, |max, cost| cost.map(|cost| max.max(cost)))
+  }
+
+  fn worst_case_of(
+    &self,
+    type_name: &str,
+    depth: Option<usize>,
+    stack: &mut Vec<(String, Option<usize>)>,
+    memo: &mut HashMap<(String, Option<usize>), Option<u64>>,
+  ) -> Option<u64> {
+    if depth == Some(0) {
+      return Some(0);
+    }
+    let key = (type_name.to_string(), depth);
+    if let Some(cost) = memo.get(&key) {
+      return *cost;
+    }
+    if stack.contains(&key) {
+      // Only reachable without a maximum depth, as the depth decreases with every nested field
+      return None;
+    }
+    stack.push(key.clone());
+
+    let nested = depth.map(|depth| depth - 1);
+    let cost = if let Some(members) = self.unions.get(type_name) {
+      members.iter().try_fold(0u64, |sum, member| {
+        Some(sum.saturating_add(self.worst_case_of(member, depth, stack, memo)?))
+      })
+    } else {
+      self.fields.get(type_name).map_or(Some(0), |fields| {
+        fields.values().try_fold(0u64, |sum, (cost, of_type)| {
+          let nested = self.worst_case_of(of_type, nested, stack, memo)?;
+          Some(sum.saturating_add(*cost).saturating_add(nested))
+        })
+      })
+    };
+
+    stack.pop();
+    memo.insert(key, cost);
+    cost
+  }
+}
+
+/// Measures the operations of a document against the query limits.
+/// The measure of every fragment is computed once, and measuring stops as soon as a limit is exceeded.
+struct Measurer<'a> {
+  costs: &'a QueryCosts,
+  limits: &'a QueryLimits,
+  document: &'a ExecutableDocument,
+  fragments: HashMap<&'a Name, Measure>,
+  spread: HashSet<&'a Name>,
+}
+
+impl<'a> Measurer<'a> {
+  fn new(costs: &'a QueryCosts, limits: &'a QueryLimits, document: &'a ExecutableDocument) -> Self {
+    Self { costs, limits, document, fragments: HashMap::new(), spread: HashSet::new() }
+  }
+
+  fn measure_operation(
+    &mut self,
+    root: Option<&'a str>,
+    selection_set: &'a Positioned<SelectionSet>,
+  ) -> Result<Measure, String> {
+    let measure = self.measure(root, selection_set)?;
+    exceeds(
+      "number of root fields",
+      measure.root_fields as u64,
+      self.limits.max_root_fields.map(|max| max as u64),
+    )?;
+    Ok(measure)
+  }
+
+  /// Measures the selection set on its own: its fields are at depth 1, and counted as root fields.
+  /// The measure of a part of the operation never exceeds the measure of the whole,
+  /// so the depth, complexity and aliases are checked after every selection.
+  fn measure(
+    &mut self,
+    type_name: Option<&'a str>,
+    selection_set: &'a Positioned<SelectionSet>,
+  ) -> Result<Measure, String> {
+    let mut measure = Measure::default();
+    for selection in selection_set.node.items.iter() {
+      let selection = match &selection.node {
+        Selection::Field(field) => {
+          let field = &field.node;
+          let (cost, of_type) = match self.costs.field(type_name, &field.name.node) {
+            Some((cost, of_type)) => (*cost, Some(of_type.as_str())),
+            None => (1, None),
+          };
+          let nested = self.measure(of_type, &field.selection_set)?;
+          Measure {
+            depth: nested.depth + 1,
+            complexity: nested.complexity.saturating_add(cost),
+            aliases: nested.aliases + usize::from(field.alias.is_some()),
+            root_fields: 1,
+          }
+        }
+        Selection::FragmentSpread(fragment_spread) => self.fragment(&fragment_spread.node.fragment_name.node)?,
+        Selection::InlineFragment(inline_fragment) => {
+          let type_condition = inline_fragment
+            .node
+            .type_condition
+            .as_ref()
+            .map_or(type_name, |condition| Some(condition.node.on.node.as_str()));
+          self.measure(type_condition, &inline_fragment.node.selection_set)?
+        }
+      };
+      measure.add(selection);
+      self.check(&measure)?;
+    }
+    Ok(measure)
+  }
+
+  /// Measures a fragment once, and reuses its measure wherever it is spread.
+  fn fragment(&mut self, name: &'a Name) -> Result<Measure, String> {
+    if let Some(measure) = self.fragments.get(name) {
+      return Ok(*measure);
+    }
+    let Some(fragment) = self.document.fragments.get(name) else {
+      return Ok(Measure::default());
+    };
+    // Cycles of fragments are only rejected once the document is validated, after it is parsed
+    if !self.spread.insert(name) {
+      return Ok(Measure::default());
+    }
+    let type_condition = fragment.node.type_condition.node.on.node.as_str();
+    let measure = self.measure(Some(type_condition), &fragment.node.selection_set)?;
+    self.spread.remove(name);
+    self.fragments.insert(name, measure);
+    Ok(measure)
+  }
+
+  fn check(&self, measure: &Measure) -> Result<(), String> {
+    exceeds(
+      "depth",
+      measure.depth as u64,
+      self.limits.max_depth.map(|max| max as u64),
+    )?;
+    exceeds("complexity", measure.complexity, self.limits.max_complexity)?;
+    exceeds(
+      "number of aliases",
+      measure.aliases as u64,
+      self.limits.max_aliases.map(|max| max as u64),
+    )
+  }
+}
+
+impl Blueprint {
+  /// The complexity of the most expensive operation that the schema accepts, when every field is selected once.
+  /// Returns `None` when the schema is recursive and `queryLimits.maxDepth` isn't set.
+  pub fn worst_case_cost(&self) -> Option<u64> {
+    let max_depth = self.server.query_limits.as_ref().and_then(|limits| limits.max_depth);
+    QueryCosts::from(self).worst_case(max_depth)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use async_graphql::parser::parse_query;
+
+  use super::{Measurer, QueryCosts};
+  use crate::blueprint::Blueprint;
+  use crate::config::{Config, QueryLimits};
+
+  fn blueprint(limits: &str) -> Blueprint {
+    let sdl = format!(
+      r#"
+      schema @server(queryLimits: {limits}) @upstream(baseURL: "http://localhost:3000") {{
+        query: Query
+      }}
+
+      type Query {{
+        posts: [Post] @http(path: "/posts", cost: 10)
+        user(id: Int!): User @http(path: "/users/{{{{args.id}}}}")
+      }}
+
+      type Post {{
+        id: Int!
+        title: String
+        userId: Int!
+        user: User @http(path: "/users/{{{{value.userId}}}}", cost: 5)
+      }}
+
+      type User {{
+        id: Int!
+        name: String
+        posts: [Post] @http(path: "/users/{{{{value.id}}}}/posts", cost: 10)
+      }}
+      "#
+    );
+    Blueprint::try_from(&Config::from_sdl(&sdl).to_result().unwrap()).unwrap()
+  }
+
+  #[test]
+  fn test_worst_case_cost() {
+    // posts: 10 + Post { id, title, userId: 3 + user: 5 + User { id, name: 2 + posts: 10 } } = 30
+    // user: 1 + User { id, name: 2 + posts: 10 + Post { id, title, userId: 3 + user: 5 } } = 21
+    assert_eq!(blueprint("{maxDepth: 3}").worst_case_cost(), Some(51));
+    // Users have posts, that have a user, so the cost is only bounded by the depth of the query
+    assert_eq!(blueprint("{maxComplexity: 100}").worst_case_cost(), None);
+  }
+
+  #[test]
+  fn test_nested_fragments() {
+    // Every fragment spreads the previous one twice, so the query selects `name` 2^40 times
+    let mut query = "query { user(id: 1) { ...F40 } } fragment F0 on User { name }".to_string();
+    for i in 1..=40 {
+      query.push_str(&format!(" fragment F{i} on User {{ ...F{0} ...F{0} }}", i - 1));
+    }
+    let document = parse_query(&query).unwrap();
+    let (_, operation) = document.operations.iter().next().unwrap();
+    let costs = QueryCosts::from(&blueprint("{}"));
+
+    let limits = QueryLimits::default();
+    let mut measurer = Measurer::new(&costs, &limits, &document);
+    let measure = measurer
+      .measure_operation(Some("Query"), &operation.node.selection_set)
+      .unwrap();
+    assert_eq!(measure.complexity, 1 + (1 << 40));
+
+    let limits = QueryLimits { max_complexity: Some(100), ..Default::default() };
+    let mut measurer = Measurer::new(&costs, &limits, &document);
+    let error = measurer
+      .measure_operation(Some("Query"), &operation.node.selection_set)
+      .unwrap_err();
+    assert_eq!(error, "The query exceeds the maximum complexity of 100");
+  }
+}
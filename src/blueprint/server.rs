@@ -1,11 +1,16 @@
 use std::collections::BTreeMap;
 use std::net::{AddrParseError, IpAddr};
+use std::sync::Arc;
 
 use derive_setters::Setters;
 use hyper::header::{HeaderName, HeaderValue};
 use hyper::HeaderMap;
 
+use super::Cors;
+use crate::auth::{Auth, Htpasswd, Jwks};
 use crate::config;
+use crate::http::server_config;
+use crate::persisted_queries::PersistedQueries;
 use crate::valid::{Valid, ValidationError};
 
 #[derive(Clone, Debug, Setters)]
@@ -16,11 +21,21 @@ pub struct Server {
   pub enable_introspection: bool,
   pub enable_query_validation: bool,
   pub enable_response_validation: bool,
+  pub enable_federation: bool,
   pub global_response_timeout: i64,
   pub port: u16,
   pub hostname: IpAddr,
   pub vars: BTreeMap<String, String>,
   pub response_headers: HeaderMap,
+  pub auth: Option<Auth>,
+  /// Path of the Prometheus metrics endpoint, if enabled.
+  pub metrics_path: Option<String>,
+  pub telemetry: Option<config::Telemetry>,
+  pub query_limits: Option<config::QueryLimits>,
+  pub persisted_queries: Option<PersistedQueries>,
+  pub cors: Option<Cors>,
+  pub tls: Option<Arc<rustls::ServerConfig>>,
+  pub enable_h2c: bool,
 }
 
 impl Server {
@@ -80,20 +95,201 @@ fn handle_response_headers(resp_headers: BTreeMap<String, String>) -> Valid<Head
   .trace("schema")
 }
 
+fn to_auth(auth: Option<&config::Auth>) -> Valid<Option<Auth>, String> {
+  let Some(auth) = auth else {
+    return Valid::succeed(None);
+  };
+  let jwks = match &auth.jwks {
+    Some(jwks) => Valid::from(Jwks::from_file(&jwks.file).map_err(|e| ValidationError::new(e.to_string())))
+      .map(|provider| Some(provider.issuer(jwks.issuer.clone()).audiences(jwks.audiences.clone())))
+      .trace("jwks"),
+    None => Valid::succeed(None),
+  };
+  let htpasswd = match &auth.htpasswd {
+    Some(htpasswd) => Valid::from(Htpasswd::from_file(&htpasswd.file).map_err(|e| ValidationError::new(e.to_string())))
+      .map(Some)
+      .trace("htpasswd"),
+    None => Valid::succeed(None),
+  };
+
+  jwks
+    .zip(htpasswd)
+    .and_then(|(jwks, htpasswd)| {
+      if jwks.is_none() && htpasswd.is_none() {
+        Valid::fail("At least one auth provider must be configured".to_string())
+      } else {
+        Valid::succeed(Some(Auth { jwks, htpasswd }))
+      }
+    })
+    .trace("auth")
+    .trace("@server")
+    .trace("schema")
+}
+
+fn to_metrics_path(metrics: Option<&config::Metrics>) -> Valid<Option<String>, String> {
+  let Some(metrics) = metrics else {
+    return Valid::succeed(None);
+  };
+
+  if !metrics.path.starts_with('/') {
+    Valid::fail(format!("Path '{}' must start with '/'", metrics.path))
+  } else if metrics.path == "/graphql" {
+    Valid::fail("Path '/graphql' is reserved for GraphQL requests".to_string())
+  } else {
+    Valid::succeed(Some(metrics.path.clone()))
+  }
+  .trace("path")
+  .trace("metrics")
+  .trace("@server")
+  .trace("schema")
+}
+
+fn to_telemetry(telemetry: Option<&config::Telemetry>) -> Valid<Option<config::Telemetry>, String> {
+  let Some(telemetry) = telemetry else {
+    return Valid::succeed(None);
+  };
+
+  Valid::from(url::Url::parse(&telemetry.otlp.url).map_err(|e| ValidationError::new(e.to_string())))
+    .map_to(Some(telemetry.clone()))
+    .trace("url")
+    .trace("otlp")
+    .trace("telemetry")
+    .trace("@server")
+    .trace("schema")
+}
+
+fn to_cors(cors: Option<&config::Cors>) -> Valid<Option<Cors>, String> {
+  let Some(cors) = cors else {
+    return Valid::succeed(None);
+  };
+
+  let any_origin = cors.allow_origins.iter().any(|origin| origin == "*");
+  let allow_origins = if any_origin {
+    Valid::<(), String>::fail("allowOrigins can't contain '*' when allowCredentials is true".to_string())
+      .when(|| cors.allow_credentials)
+      .map_to(None)
+  } else {
+    Valid::<(), String>::fail("At least one origin must be allowed".to_string())
+      .when(|| cors.allow_origins.is_empty())
+      .and(Valid::from_iter(cors.allow_origins.iter(), |origin| {
+        to_header_value(origin)
+      }))
+      .map(Some)
+  }
+  .trace("allowOrigins");
+  let allow_methods = Valid::from_iter(cors.allow_methods.iter(), |method| {
+    Valid::from(
+      hyper::Method::from_bytes(method.as_bytes())
+        .map_err(|e| ValidationError::new(format!("Parsing failed because of {}", e))),
+    )
+  })
+  .and_then(|_| to_header_list(&cors.allow_methods))
+  .trace("allowMethods");
+  let allow_headers = to_header_names(&cors.allow_headers).trace("allowHeaders");
+  let expose_headers = if cors.expose_headers.is_empty() {
+    Valid::succeed(None)
+  } else {
+    to_header_names(&cors.expose_headers).map(Some)
+  }
+  .trace("exposeHeaders");
+
+  allow_origins
+    .zip(allow_methods)
+    .zip(allow_headers)
+    .zip(expose_headers)
+    .map(|(((allow_origins, allow_methods), allow_headers), expose_headers)| {
+      Some(Cors {
+        allow_origins,
+        allow_methods,
+        allow_headers,
+        expose_headers,
+        max_age: cors.max_age.map(HeaderValue::from),
+        allow_credentials: cors.allow_credentials,
+      })
+    })
+    .trace("cors")
+    .trace("@server")
+    .trace("schema")
+}
+
+fn to_header_value(value: &str) -> Valid<HeaderValue, String> {
+  Valid::from(
+    HeaderValue::from_str(value).map_err(|e| ValidationError::new(format!("Parsing failed because of {}", e))),
+  )
+}
+
+/// Validates the names of headers, and joins them into the value of a CORS header.
+fn to_header_names(names: &[String]) -> Valid<HeaderValue, String> {
+  Valid::from_iter(names.iter(), |name| {
+    Valid::from(
+      HeaderName::from_bytes(name.as_bytes())
+        .map_err(|e| ValidationError::new(format!("Parsing failed because of {}", e))),
+    )
+  })
+  .and_then(|_| to_header_list(names))
+}
+
+fn to_header_list(values: &[String]) -> Valid<HeaderValue, String> {
+  to_header_value(&values.join(", "))
+}
+
+fn to_tls(tls: Option<&config::Tls>) -> Valid<Option<Arc<rustls::ServerConfig>>, String> {
+  let Some(tls) = tls else {
+    return Valid::succeed(None);
+  };
+
+  Valid::from(server_config(&tls.cert, &tls.key).map_err(|e| ValidationError::new(e.to_string())))
+    .map(|config| Some(Arc::new(config)))
+    .trace("tls")
+    .trace("@server")
+    .trace("schema")
+}
+
+fn to_persisted_queries(
+  persisted_queries: Option<&config::PersistedQueries>,
+) -> Valid<Option<PersistedQueries>, String> {
+  let Some(persisted_queries) = persisted_queries else {
+    return Valid::succeed(None);
+  };
+
+  Valid::from(PersistedQueries::from_config(persisted_queries).map_err(|e| ValidationError::new(e.to_string())))
+    .map(Some)
+    .trace("persistedQueries")
+    .trace("@server")
+    .trace("schema")
+}
+
 fn configure_server(config_config: &config::Server) -> Valid<Server, String> {
   validate_hostname(config_config.get_hostname().to_lowercase())
     .zip(handle_response_headers(config_config.get_response_headers().0))
-    .map(|(hostname, response_headers)| Server {
-      enable_apollo_tracing: config_config.enable_apollo_tracing(),
-      enable_cache_control_header: config_config.enable_cache_control(),
-      enable_graphiql: config_config.enable_graphiql(),
-      enable_introspection: config_config.enable_introspection(),
-      enable_query_validation: config_config.enable_query_validation(),
-      enable_response_validation: config_config.enable_http_validation(),
-      global_response_timeout: config_config.get_global_response_timeout(),
-      port: config_config.get_port(),
-      hostname,
-      vars: config_config.get_vars(),
-      response_headers,
-    })
+    .zip(to_auth(config_config.auth.as_ref()))
+    .zip(to_metrics_path(config_config.metrics.as_ref()))
+    .zip(to_telemetry(config_config.telemetry.as_ref()))
+    .zip(to_persisted_queries(config_config.persisted_queries.as_ref()))
+    .zip(to_cors(config_config.cors.as_ref()))
+    .zip(to_tls(config_config.tls.as_ref()))
+    .map(
+      |(((((((hostname, response_headers), auth), metrics_path), telemetry), persisted_queries), cors), tls)| Server {
+        enable_apollo_tracing: config_config.enable_apollo_tracing(),
+        enable_cache_control_header: config_config.enable_cache_control(),
+        enable_graphiql: config_config.enable_graphiql(),
+        enable_introspection: config_config.enable_introspection(),
+        enable_query_validation: config_config.enable_query_validation(),
+        enable_response_validation: config_config.enable_http_validation(),
+        enable_federation: config_config.enable_federation(),
+        global_response_timeout: config_config.get_global_response_timeout(),
+        port: config_config.get_port(),
+        hostname,
+        vars: config_config.get_vars(),
+        response_headers,
+        auth,
+        metrics_path,
+        telemetry,
+        query_limits: config_config.query_limits.clone(),
+        persisted_queries,
+        cors,
+        tls,
+        enable_h2c: config_config.enable_h2c(),
+      },
+    )
 }
@@ -6,6 +6,8 @@ use async_graphql::{Response, ServerError};
 use async_graphql_value::ConstValue;
 use tokio::time::timeout;
 
+use crate::metrics::metrics;
+
 pub struct GlobalTimeout;
 
 impl ExtensionFactory for GlobalTimeout {
@@ -27,6 +29,7 @@ impl Extension for GlobalTimeoutExtension {
         match result {
           Ok(result) => result,
           Err(_) => {
+            metrics().global_timeouts.inc();
             let mut response = Response::new(ConstValue::Null);
             response.errors = vec![ServerError::new("Global timeout".to_string(), None)];
             response
@@ -1,5 +1,9 @@
 use std::collections::HashMap;
+use std::hash::Hash;
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use moka::Expiry;
 
 pub struct Cache<K, V>(Mutex<HashMap<K, V>>);
 
@@ -22,3 +26,96 @@ where
     Self(Mutex::new(HashMap::new()))
   }
 }
+
+/// Upper bound on the number of entries kept by a [TtlCache].
+const TTL_CACHE_CAPACITY: u64 = 10_000;
+
+/// TtlCache is an in-memory store where every entry expires after its own time to live.
+/// Clones share the same store.
+#[derive(Clone)]
+pub struct TtlCache<K, V>
+where
+  K: Hash + Eq + Send + Sync + 'static,
+  V: Clone + Send + Sync + 'static,
+{
+  store: moka::future::Cache<K, Entry<V>>,
+}
+
+#[derive(Clone)]
+struct Entry<V> {
+  value: V,
+  expires_at: Instant,
+}
+
+struct ExpiresAt;
+
+impl<K, V> Expiry<K, Entry<V>> for ExpiresAt {
+  fn expire_after_create(&self, _: &K, entry: &Entry<V>, created_at: Instant) -> Option<Duration> {
+    Some(entry.expires_at.saturating_duration_since(created_at))
+  }
+
+  fn expire_after_update(&self, _: &K, entry: &Entry<V>, updated_at: Instant, _: Option<Duration>) -> Option<Duration> {
+    Some(entry.expires_at.saturating_duration_since(updated_at))
+  }
+}
+
+impl<K, V> Default for TtlCache<K, V>
+where
+  K: Hash + Eq + Send + Sync + 'static,
+  V: Clone + Send + Sync + 'static,
+{
+  fn default() -> Self {
+    let store = moka::future::Cache::builder()
+      .max_capacity(TTL_CACHE_CAPACITY)
+      .expire_after(ExpiresAt)
+      .build();
+    Self { store }
+  }
+}
+
+impl<K, V> TtlCache<K, V>
+where
+  K: Hash + Eq + Send + Sync + 'static,
+  V: Clone + Send + Sync + 'static,
+{
+  /// Returns the value for the key along with the time it has left to live.
+  pub async fn get(&self, key: &K) -> Option<(V, Duration)> {
+    let entry = self.store.get(key).await?;
+    let ttl = entry.expires_at.checked_duration_since(Instant::now())?;
+    Some((entry.value, ttl))
+  }
+
+  /// Stores the value for the key until its time to live runs out.
+  pub async fn insert(&self, key: K, (value, ttl): (V, Duration)) {
+    self
+      .store
+      .insert(key, Entry { value, expires_at: Instant::now() + ttl })
+      .await;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use super::TtlCache;
+
+  #[tokio::test]
+  async fn test_ttl_cache_hit() {
+    let cache = TtlCache::default();
+    cache.insert(1, ("a", Duration::from_secs(60))).await;
+
+    let (value, ttl) = cache.get(&1).await.unwrap();
+    assert_eq!(value, "a");
+    assert!(ttl > Duration::from_secs(59));
+  }
+
+  #[tokio::test]
+  async fn test_ttl_cache_expired() {
+    let cache = TtlCache::default();
+    cache.insert(1, ("a", Duration::from_millis(10))).await;
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    assert_eq!(cache.get(&1).await, None);
+  }
+}
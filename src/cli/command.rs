@@ -1,5 +1,7 @@
 use clap::{Parser, Subcommand};
 
+use crate::config::Source;
+
 const VERSION: &str = match option_env!("APP_VERSION") {
   Some(version) => version,
   _ => "0.1.0-dev",
@@ -27,6 +29,9 @@ pub enum Command {
     file_path: Vec<String>,
     #[arg(long)]
     log_level: Option<log::Level>,
+    /// Reload the configuration when one of the files changes
+    #[arg(long)]
+    watch: bool,
   },
 
   /// Validate a composition spec
@@ -45,4 +50,38 @@ pub enum Command {
   },
   /// Initialize a new project
   Init { file_path: String },
+
+  /// Generate a configuration from a description of an upstream
+  Gen {
+    #[command(subcommand)]
+    source: GenSource,
+  },
+}
+
+#[derive(Subcommand)]
+pub enum GenSource {
+  /// Generate types and @http fields from an OpenAPI 3 document
+  Openapi {
+    /// Path of the OpenAPI document, in YAML or JSON
+    file_path: String,
+
+    /// Format of the generated configuration: graphql, yml or json
+    #[arg(short, long, default_value = "graphql", value_parser = Source::from_ext)]
+    format: Source,
+  },
+
+  /// Generate types and an @http field from sample JSON responses of an endpoint
+  Json {
+    /// Path of the endpoint, or its full URL to also set the base URL of the upstream
+    #[arg(long)]
+    url_path: String,
+
+    /// Paths of sample responses of the endpoint
+    #[arg(long, required = true)]
+    sample: Vec<String>,
+
+    /// Format of the generated configuration: graphql, yml or json
+    #[arg(short, long, default_value = "graphql", value_parser = Source::from_ext)]
+    format: Source,
+  },
 }
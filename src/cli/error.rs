@@ -159,6 +159,19 @@ impl From<hyper::Error> for CLIError {
   }
 }
 
+impl From<std::io::Error> for CLIError {
+  fn from(error: std::io::Error) -> Self {
+    let cli_error = CLIError::new("Server Failed");
+    if error.kind() == std::io::ErrorKind::AddrInUse {
+      cli_error
+        .description("The port is already in use".to_string())
+        .caused_by(vec![CLIError::new(error.to_string().as_str())])
+    } else {
+      cli_error.description(error.to_string())
+    }
+  }
+}
+
 impl<'a> From<ValidationError<&'a str>> for CLIError {
   fn from(error: ValidationError<&'a str>) -> Self {
     CLIError::new("Invalid Configuration").caused_by(
@@ -189,6 +202,27 @@ impl From<ValidationError<String>> for CLIError {
   }
 }
 
+impl From<anyhow::Error> for CLIError {
+  fn from(error: anyhow::Error) -> Self {
+    // Ensure all errors are converted to CLIErrors before being printed.
+    let error = match error.downcast::<CLIError>() {
+      Ok(cli_error) => return cli_error,
+      Err(error) => error,
+    };
+    match error.downcast::<ValidationError<String>>() {
+      Ok(validation_error) => CLIError::from(validation_error),
+      Err(error) => {
+        let sources = error
+          .source()
+          .map(|error| vec![CLIError::new(error.to_string().as_str())])
+          .unwrap_or_default();
+
+        CLIError::new(&error.to_string()).caused_by(sources)
+      }
+    }
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use std::collections::VecDeque;
@@ -1,19 +1,20 @@
 use colored::*;
 
+use crate::blueprint::Blueprint;
 use crate::config::Config;
 
 pub struct Fmt {}
 
 impl Fmt {
-  pub fn heading(heading: &String) -> String {
+  pub fn heading(heading: &str) -> String {
     format!("{}", heading.bold())
   }
 
-  pub fn meta(meta: &String) -> String {
+  pub fn meta(meta: &str) -> String {
     format!("{}", meta.yellow())
   }
 
-  pub fn success(success: &String) -> String {
+  pub fn success(success: &str) -> String {
     format!("{}", success.green())
   }
 
@@ -92,4 +93,12 @@ impl Fmt {
       ("N + 1".to_string(), n_plus_one_info.len().to_string())
     }
   }
+
+  pub fn worst_case_cost_data(blueprint: &Blueprint) -> (String, String) {
+    let cost = match blueprint.worst_case_cost() {
+      Some(cost) => cost.to_string(),
+      None => "unbounded (the schema is recursive, set queryLimits.maxDepth to bound it)".to_string(),
+    };
+    ("Worst-case query cost".to_string(), cost)
+  }
 }
@@ -2,6 +2,8 @@ mod command;
 mod error;
 mod fmt;
 mod tc;
+mod watch;
 
 pub use error::CLIError;
 pub use tc::run;
+pub use watch::watch;
@@ -9,23 +9,28 @@ use log::Level;
 use resource::resource_str;
 use stripmargin::StripMargin;
 
-use super::command::{Cli, Command};
+use super::command::{Cli, Command, GenSource};
 use crate::blueprint::Blueprint;
 use crate::cli::fmt::Fmt;
 use crate::config::Config;
-use crate::http::start_server;
-use crate::print_schema;
+use crate::http::{start_server, start_server_with_reload};
+use crate::{generator, print_schema};
 
 pub async fn run() -> Result<()> {
   let cli = Cli::parse();
 
   match cli.command {
-    Command::Start { file_path, log_level } => {
+    Command::Start { file_path, log_level, watch } => {
       env_logger::Builder::new()
         .filter_level(log_level.unwrap_or(Level::Info).to_level_filter())
         .init();
       let config = Config::from_file_paths(file_path.iter()).await?;
-      start_server(config).await?;
+      if watch {
+        let reloads = super::watch(file_path, &config)?;
+        start_server_with_reload(config, reloads).await?;
+      } else {
+        start_server(config).await?;
+      }
       Ok(())
     }
     Command::Check { file_path, n_plus_one_queries, schema } => {
@@ -40,6 +45,20 @@ pub async fn run() -> Result<()> {
       }
     }
     Command::Init { file_path } => Ok(init(&file_path).await?),
+    Command::Gen { source } => {
+      let (config, format) = match source {
+        GenSource::Openapi { file_path, format } => (generator::from_openapi(&fs::read_to_string(file_path)?)?, format),
+        GenSource::Json { url_path, sample, format } => {
+          let samples = sample
+            .iter()
+            .map(|path| Ok(serde_json::from_str(&fs::read_to_string(path)?)?))
+            .collect::<Result<Vec<_>>>()?;
+          (generator::from_json(&samples, &url_path)?, format)
+        }
+      };
+      println!("{}", config.to_source(format)?);
+      Ok(())
+    }
   }
 }
 
@@ -93,12 +112,15 @@ pub async fn init(file_path: &str) -> Result<()> {
 }
 
 pub fn display_details(config: &Config, blueprint: Blueprint, n_plus_one_queries: &bool, schema: &bool) -> Result<()> {
-  Fmt::display(Fmt::success(&"No errors found".to_string()));
-  let seq = vec![Fmt::n_plus_one_data(*n_plus_one_queries, config)];
+  Fmt::display(Fmt::success("No errors found"));
+  let seq = vec![
+    Fmt::n_plus_one_data(*n_plus_one_queries, config),
+    Fmt::worst_case_cost_data(&blueprint),
+  ];
   Fmt::display(Fmt::table(seq));
 
   if *schema {
-    Fmt::display(Fmt::heading(&"GraphQL Schema:\n".to_string()));
+    Fmt::display(Fmt::heading("GraphQL Schema:\n"));
     let sdl = blueprint.to_schema();
     Fmt::display(print_schema::print_schema(sdl));
   }
@@ -0,0 +1,123 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use notify::{RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+
+use super::CLIError;
+use crate::config::{Config, UpstreamTls};
+
+/// Watches the config files, and the files they link to, and sends the merged config every time one of them changes.
+/// Configs that can't be read are reported instead of being sent.
+pub fn watch(file_paths: Vec<String>, config: &Config) -> Result<mpsc::Receiver<Config>> {
+  let files = file_paths
+    .iter()
+    .map(|path| Ok(Path::new(path).canonicalize()?))
+    .collect::<Result<HashSet<PathBuf>>>()?;
+  let watched = Arc::new(Mutex::new(HashSet::new()));
+  let (change_tx, mut change_rx) = mpsc::unbounded_channel();
+  let mut watcher = notify::recommended_watcher({
+    let watched = watched.clone();
+    move |event: notify::Result<notify::Event>| {
+      let Ok(event) = event else {
+        return;
+      };
+      let watched = watched.lock().unwrap();
+      if !event.kind.is_access() && event.paths.iter().any(|path| watched.contains(path)) {
+        let _ = change_tx.send(());
+      }
+    }
+  })?;
+
+  // Editors often replace a file instead of writing to it, so the directories of the files are watched
+  let mut directories = HashSet::new();
+  let mut watch_files = move |watcher: &mut notify::RecommendedWatcher, paths: HashSet<PathBuf>| -> Result<()> {
+    for directory in paths.iter().filter_map(|file| file.parent()) {
+      if directories.insert(directory.to_path_buf()) {
+        watcher.watch(directory, RecursiveMode::NonRecursive)?;
+      }
+    }
+    watched.lock().unwrap().extend(paths);
+    Ok(())
+  };
+  watch_files(&mut watcher, files)?;
+  watch_files(&mut watcher, linked_files(config))?;
+
+  let (config_tx, config_rx) = mpsc::channel(1);
+  tokio::spawn(async move {
+    // The watcher stops as soon as it's dropped
+    let mut watcher = watcher;
+    while change_rx.recv().await.is_some() {
+      // A single save usually emits several events, that are handled as one change
+      tokio::time::sleep(Duration::from_millis(100)).await;
+      while change_rx.try_recv().is_ok() {}
+
+      match Config::from_file_paths(file_paths.iter()).await {
+        Ok(config) => {
+          // The config can link to other files than it used to
+          if let Err(error) = watch_files(&mut watcher, linked_files(&config)) {
+            log::warn!("Failed to watch the files linked to the config: {error}");
+          }
+          if config_tx.send(config).await.is_err() {
+            break;
+          }
+        }
+        Err(error) => eprintln!("{}", CLIError::from(error).color(true)),
+      }
+    }
+  });
+
+  Ok(config_rx)
+}
+
+/// The files that are read along with the config: links, certificates, keys, manifests and protobuf definitions.
+/// Files that don't exist can't be watched, and are left out.
+fn linked_files(config: &Config) -> HashSet<PathBuf> {
+  let server = &config.server;
+  let fields = config
+    .graphql
+    .types
+    .values()
+    .flat_map(|type_of| type_of.fields.values());
+
+  config
+    .links
+    .iter()
+    .map(|link| &link.src)
+    .chain(server.tls.iter().flat_map(|tls| [&tls.cert, &tls.key]))
+    .chain(server.persisted_queries.iter().flat_map(|pq| &pq.manifest))
+    .chain(server.auth.iter().flat_map(|auth| &auth.jwks).map(|jwks| &jwks.file))
+    .chain(
+      server
+        .auth
+        .iter()
+        .flat_map(|auth| &auth.htpasswd)
+        .map(|htpasswd| &htpasswd.file),
+    )
+    .chain(
+      std::iter::once(&config.upstream)
+        .chain(&config.upstreams)
+        .filter_map(|upstream| upstream.tls.as_ref())
+        .flat_map(tls_files),
+    )
+    .chain(
+      fields
+        .clone()
+        .filter_map(|field| field.http.as_ref()?.tls.as_ref())
+        .flat_map(tls_files),
+    )
+    .chain(
+      fields
+        .filter_map(|field| field.grpc.as_ref())
+        .map(|grpc| &grpc.proto_path),
+    )
+    .filter_map(|path| Path::new(path).canonicalize().ok())
+    .collect()
+}
+
+fn tls_files(tls: &UpstreamTls) -> impl Iterator<Item = &String> {
+  [&tls.ca_cert, &tls.client_cert, &tls.client_key].into_iter().flatten()
+}
@@ -1,4 +1,6 @@
 use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::num::NonZeroU64;
+use std::path::Path;
 
 use anyhow::Result;
 use async_graphql::futures_util::future::join_all;
@@ -9,7 +11,7 @@ use serde_json::Value;
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
 
-use super::{Server, Upstream};
+use super::{Server, Upstream, UpstreamTls};
 use crate::config::source::Source;
 use crate::config::{is_default, KeyValues};
 use crate::http::Method;
@@ -21,9 +23,29 @@ use crate::valid::Valid;
 pub struct Config {
   pub server: Server,
   pub upstream: Upstream,
+  /// Named upstream profiles, that are applied on top of `upstream`.
+  #[serde(default, skip_serializing_if = "is_default")]
+  pub upstreams: Vec<Upstream>,
+  #[serde(default, skip_serializing_if = "is_default")]
+  pub links: Vec<Link>,
   pub graphql: GraphQL,
 }
 
+/// Link points to a file that's loaded along with the config.
+/// A relative `src` is relative to the directory of the config file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Link {
+  pub src: String,
+  #[serde(rename = "type")]
+  pub type_of: LinkType,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkType {
+  /// A document of GraphQL operations, that can be exposed as REST endpoints with `@rest`.
+  Operation,
+}
+
 impl Config {
   pub fn port(&self) -> u16 {
     self.server.port.unwrap_or(8000)
@@ -68,6 +90,15 @@ impl Config {
     self.graphql.types.get(name)
   }
 
+  /// The settings of the upstream profile, on top of the unnamed `@upstream`.
+  pub fn find_upstream(&self, name: &str) -> Option<Upstream> {
+    self
+      .upstreams
+      .iter()
+      .find(|upstream| upstream.name.as_deref() == Some(name))
+      .map(|upstream| self.upstream.clone().merge_right(upstream.clone()))
+  }
+
   pub fn find_union(&self, name: &str) -> Option<&Union> {
     self.graphql.unions.get(name)
   }
@@ -111,7 +142,20 @@ impl Config {
     let server = self.server.merge_right(other.server.clone());
     let graphql = self.graphql.merge_right(other.graphql.clone());
     let upstream = self.upstream.merge_right(other.upstream.clone());
-    Self { server, upstream, graphql }
+    let mut upstreams = self.upstreams;
+    for other in other.upstreams.iter() {
+      match upstreams.iter_mut().find(|upstream| upstream.name == other.name) {
+        Some(upstream) => *upstream = upstream.clone().merge_right(other.clone()),
+        None => upstreams.push(other.clone()),
+      }
+    }
+    let mut links = self.links;
+    for link in other.links.iter() {
+      if !links.contains(link) {
+        links.push(link.clone());
+      }
+    }
+    Self { server, upstream, upstreams, links, graphql }
   }
 }
 
@@ -127,6 +171,14 @@ pub struct Type {
   pub variants: Option<BTreeSet<String>>,
   #[serde(default)]
   pub scalar: bool,
+  /// Caches the resolved value of every field of the type.
+  pub cache: Option<Cache>,
+  /// Requires an authenticated request to resolve any field of the type.
+  #[serde(default, skip_serializing_if = "is_default")]
+  pub protected: bool,
+  /// Marks the type as an entity of a federated schema, identified by the given fields.
+  #[serde(skip_serializing_if = "is_default")]
+  pub key: Option<Key>,
 }
 
 impl Type {
@@ -149,6 +201,9 @@ impl Type {
     } else {
       self.variants = other.variants.clone();
     }
+    self.cache = other.cache.clone().or(self.cache);
+    self.protected = self.protected || other.protected;
+    self.key = other.key.clone().or(self.key);
     Self { fields, ..self.clone() }
   }
 }
@@ -221,14 +276,29 @@ pub struct Field {
   #[serde(rename = "unsafe")]
   pub unsafe_operation: Option<Unsafe>,
   pub const_field: Option<ConstField>,
+  pub graphql: Option<GraphQLOperation>,
+  pub grpc: Option<Grpc>,
+  pub cache: Option<Cache>,
+  #[serde(default, skip_serializing_if = "is_default")]
+  pub protected: bool,
+  pub expr: Option<Expr>,
+  pub call: Option<Call>,
+  pub pipeline: Option<Pipeline>,
 }
 
 impl Field {
   pub fn has_resolver(&self) -> bool {
-    self.http.is_some() || self.unsafe_operation.is_some() || self.const_field.is_some()
+    self.http.is_some()
+      || self.unsafe_operation.is_some()
+      || self.const_field.is_some()
+      || self.graphql.is_some()
+      || self.grpc.is_some()
+      || self.expr.is_some()
+      || self.call.is_some()
+      || self.pipeline.is_some()
   }
   pub fn resolvable_directives(&self) -> Vec<&str> {
-    let mut directives = Vec::with_capacity(3);
+    let mut directives = Vec::with_capacity(4);
     if self.http.is_some() {
       directives.push("@http")
     }
@@ -238,10 +308,37 @@ impl Field {
     if self.const_field.is_some() {
       directives.push("@const")
     }
+    if self.graphql.is_some() {
+      directives.push("@graphQL")
+    }
+    if self.grpc.is_some() {
+      directives.push("@grpc")
+    }
+    if self.expr.is_some() {
+      directives.push("@expr")
+    }
+    if self.call.is_some() {
+      directives.push("@call")
+    }
+    if self.pipeline.is_some() {
+      directives.push("@pipeline")
+    }
     directives
   }
   pub fn has_batched_resolver(&self) -> bool {
     self.http.as_ref().is_some_and(|http| !http.group_by.is_empty())
+      || self.graphql.as_ref().is_some_and(|graphql| graphql.batch)
+      || self.grpc.as_ref().is_some_and(|grpc| !grpc.group_by.is_empty())
+  }
+  /// The names of the query fields that the resolver of the field calls.
+  pub fn called_queries(&self) -> Vec<&str> {
+    let steps = self.pipeline.iter().flat_map(|pipeline| pipeline.steps.iter());
+    self
+      .call
+      .iter()
+      .chain(steps.filter_map(|step| step.call.as_ref()))
+      .map(|call| call.query.as_str())
+      .collect()
   }
   pub fn to_list(mut self) -> Self {
     self.list = true;
@@ -249,6 +346,19 @@ impl Field {
   }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Cache {
+  /// Number of seconds a resolved value is kept in the cache.
+  #[serde(rename = "maxAge")]
+  pub max_age: NonZeroU64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Key {
+  /// Space separated list of the fields that identify an entity.
+  pub fields: String,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Unsafe {
   pub script: String,
@@ -312,6 +422,56 @@ pub struct Http {
   #[serde(default)]
   #[serde(rename = "groupBy", skip_serializing_if = "is_default")]
   pub group_by: Vec<String>,
+  /// Interval in milliseconds at which a subscription field polls the upstream. The subscription ends on the first
+  /// upstream error.
+  /// Without it, subscription fields listen to an upstream `text/event-stream`.
+  #[serde(rename = "pollInterval", skip_serializing_if = "is_default")]
+  pub poll_interval: Option<u64>,
+  /// Allows the upstream retry policy to retry requests whose method isn't idempotent, such as POST.
+  #[serde(default, skip_serializing_if = "is_default")]
+  pub idempotent: bool,
+  /// Weight of the field in the complexity of a query, checked against `queryLimits.maxComplexity`.
+  #[serde(skip_serializing_if = "is_default")]
+  pub cost: Option<u64>,
+  /// Overrides the TLS settings of `@upstream` for the requests to the host of the base URL.
+  #[serde(skip_serializing_if = "is_default")]
+  pub tls: Option<UpstreamTls>,
+  /// Name of the upstream profile of the requests, instead of the unnamed `@upstream`.
+  #[serde(skip_serializing_if = "is_default")]
+  pub upstream: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct GraphQLOperation {
+  pub name: String,
+  #[serde(default)]
+  #[serde(skip_serializing_if = "is_default")]
+  pub args: KeyValues,
+  #[serde(rename = "baseURL")]
+  pub base_url: Option<String>,
+  #[serde(default)]
+  #[serde(skip_serializing_if = "is_default")]
+  pub headers: KeyValues,
+  #[serde(default)]
+  #[serde(skip_serializing_if = "is_default")]
+  pub batch: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Grpc {
+  pub service: String,
+  pub method: String,
+  pub body: Option<String>,
+  #[serde(rename = "protoPath")]
+  pub proto_path: String,
+  #[serde(rename = "baseURL")]
+  pub base_url: Option<String>,
+  #[serde(default)]
+  #[serde(skip_serializing_if = "is_default")]
+  pub headers: KeyValues,
+  #[serde(default)]
+  #[serde(rename = "groupBy", skip_serializing_if = "is_default")]
+  pub group_by: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -319,6 +479,56 @@ pub struct ConstField {
   pub data: Value,
 }
 
+/// Expr computes the value of a field from its parent value and arguments.
+/// The body is a tree of operators, such as `{gte: ["{{value.age}}", 18]}`,
+/// whose strings are Mustache templates and whose other values are literals.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Expr {
+  pub body: Value,
+}
+
+/// Call resolves a field with the resolver of a field of the query type.
+/// The values of the arguments are Mustache templates or literals, as in `@expr`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Call {
+  pub query: String,
+  #[serde(default, skip_serializing_if = "is_default")]
+  pub args: BTreeMap<String, Value>,
+}
+
+/// Pipeline resolves a field with steps that are evaluated in order.
+/// The output of a step is the `{{value}}`, or `{{input}}`, of the next one,
+/// and the output of the last step is the value of the field.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Pipeline {
+  pub steps: Vec<Step>,
+}
+
+/// Step resolves a value with one of `http`, `call` and `expr`.
+/// The type of its output is written like the type of a field, e.g. `[Post!]!`.
+/// It's required to validate the templates of the next step, so only the last step can omit it,
+/// and only the last step can return a list since templates have no way to read its items.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Step {
+  #[serde(rename = "type", skip_serializing_if = "is_default")]
+  pub type_of: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none", serialize_with = "serialize_step_http")]
+  pub http: Option<Http>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub call: Option<Call>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub expr: Option<Expr>,
+}
+
+/// Leaves out the unset arguments of a step's `http`, the way they are left out of the `@http` directive.
+fn serialize_step_http<S: serde::Serializer>(http: &Option<Http>, serializer: S) -> Result<S::Ok, S::Error> {
+  let mut value = serde_json::to_value(http).map_err(serde::ser::Error::custom)?;
+  if let Value::Object(map) = &mut value {
+    map.retain(|_, value| !value.is_null());
+  }
+  value.serialize(serializer)
+}
+
 impl Config {
   pub fn from_json(json: &str) -> Result<Self> {
     Ok(serde_json::from_str(json)?)
@@ -344,10 +554,29 @@ impl Config {
     }
   }
 
+  pub fn to_source(&self, source: Source) -> Result<String> {
+    match source {
+      Source::GraphQL => Ok(self.to_sdl()),
+      Source::Json => self.to_json(),
+      Source::Yml => self.to_yaml(),
+    }
+  }
+
   pub fn n_plus_one(&self) -> Vec<Vec<(String, String)>> {
     super::n_plus_one::n_plus_one(self)
   }
 
+  /// Makes the relative paths of the links relative to the directory of the config file, instead of the current one.
+  fn resolve_links(mut self, file_path: &str) -> Self {
+    let directory = Path::new(file_path).parent().unwrap_or(Path::new(""));
+    for link in self.links.iter_mut() {
+      if Path::new(&link.src).is_relative() {
+        link.src = directory.join(&link.src).to_string_lossy().to_string();
+      }
+    }
+    self
+  }
+
   pub async fn from_file_paths(file_paths: std::slice::Iter<'_, String>) -> Result<Config> {
     let mut config = Config::default();
     let futures: Vec<_> = file_paths
@@ -358,7 +587,7 @@ impl Config {
         f.read_to_end(&mut buffer).await?;
 
         let server_sdl = String::from_utf8(buffer)?;
-        Config::from_source(source, &server_sdl)
+        Ok(Config::from_source(source, &server_sdl)?.resolve_links(file_path))
       })
       .collect();
 
@@ -377,6 +606,19 @@ impl Config {
 mod tests {
   use super::*;
 
+  #[test]
+  fn test_resolve_links() {
+    let link = |src: &str| Link { src: src.to_string(), type_of: LinkType::Operation };
+    let config =
+      Config { links: vec![link("operations.graphql"), link("/etc/operations.graphql")], ..Default::default() };
+    let config = config.resolve_links("examples/server.graphql");
+
+    assert_eq!(
+      config.links,
+      vec![link("examples/operations.graphql"), link("/etc/operations.graphql")]
+    );
+  }
+
   #[test]
   fn test_field_has_or_not_batch_resolver() {
     let f1 = Field { ..Default::default() };
@@ -390,4 +632,47 @@ mod tests {
     assert!(f2.has_batched_resolver());
     assert!(!f3.has_batched_resolver());
   }
+
+  #[test]
+  fn test_field_has_or_not_batch_graphql_resolver() {
+    let f1 = Field { graphql: Some(GraphQLOperation { batch: true, ..Default::default() }), ..Default::default() };
+    let f2 = Field { graphql: Some(GraphQLOperation::default()), ..Default::default() };
+
+    assert!(f1.has_batched_resolver());
+    assert!(!f2.has_batched_resolver());
+  }
+
+  #[test]
+  fn test_field_has_or_not_batch_grpc_resolver() {
+    let f1 =
+      Field { grpc: Some(Grpc { group_by: vec!["id".to_string()], ..Default::default() }), ..Default::default() };
+    let f2 = Field { grpc: Some(Grpc::default()), ..Default::default() };
+
+    assert!(f1.has_batched_resolver());
+    assert!(!f2.has_batched_resolver());
+  }
+
+  #[test]
+  fn test_merge_upstream_profiles() {
+    let profile = |name: &str| Upstream::default().name(Some(name.to_string()));
+    let config = Config::default()
+      .upstream(
+        Upstream::default()
+          .timeout(Some(10))
+          .user_agent(Some("Tailcall".to_string())),
+      )
+      .upstreams(vec![profile("users").timeout(Some(5))]);
+    let other = Config::default().upstreams(vec![
+      profile("users").base_url(Some("http://users".to_string())),
+      profile("posts"),
+    ]);
+
+    let config = config.merge_right(&other);
+    let users = config.find_upstream("users").unwrap();
+    assert_eq!(users.timeout, Some(5));
+    assert_eq!(users.user_agent.as_deref(), Some("Tailcall"));
+    assert_eq!(users.base_url.as_deref(), Some("http://users"));
+    assert_eq!(config.find_upstream("posts").unwrap().timeout, Some(10));
+    assert!(config.find_upstream("comments").is_none());
+  }
 }
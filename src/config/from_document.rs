@@ -9,14 +9,14 @@ use async_graphql::parser::types::{
 use async_graphql::parser::Positioned;
 use async_graphql::Name;
 
-use crate::config::{self, Config, GraphQL, Http, RootSchema, Server, Union, Upstream};
+use crate::config::{self, Config, GraphQL, GraphQLOperation, Grpc, Http, Link, RootSchema, Server, Union, Upstream};
 use crate::directive::DirectiveCodec;
 use crate::valid::{Valid, ValidationError};
 
 fn from_document(doc: ServiceDocument) -> Valid<Config, String> {
   schema_definition(&doc)
-    .and_then(|sd| server(sd).zip(upstream(sd)).zip(graphql(&doc, sd)))
-    .map(|((server, upstream), graphql)| Config { server, upstream, graphql })
+    .and_then(|sd| server(sd).zip(upstreams(sd)).zip(links(sd)).zip(graphql(&doc, sd)))
+    .map(|(((server, (upstream, upstreams)), links), graphql)| Config { server, upstream, upstreams, links, graphql })
 }
 
 fn graphql(doc: &ServiceDocument, sd: &SchemaDefinition) -> Valid<GraphQL, String> {
@@ -29,8 +29,11 @@ fn graphql(doc: &ServiceDocument, sd: &SchemaDefinition) -> Valid<GraphQL, Strin
     })
     .collect();
 
-  to_types(&type_definitions)
-    .map(|types| (GraphQL { schema: to_root_schema(sd), types, unions: to_union_types(&type_definitions) }))
+  to_types(&type_definitions).map(|types| GraphQL {
+    schema: to_root_schema(sd),
+    types,
+    unions: to_union_types(&type_definitions),
+  })
 }
 
 fn schema_definition(doc: &ServiceDocument) -> Valid<&SchemaDefinition, String> {
@@ -60,8 +63,30 @@ fn process_schema_directives<'a, T: DirectiveCodec<'a, T> + Default>(
 fn server(schema_definition: &SchemaDefinition) -> Valid<Server, String> {
   process_schema_directives(schema_definition, "server")
 }
-fn upstream(schema_definition: &SchemaDefinition) -> Valid<Upstream, String> {
-  process_schema_directives(schema_definition, "upstream")
+/// Splits the `@upstream` directives into the unnamed one and the named profiles.
+fn upstreams(schema_definition: &SchemaDefinition) -> Valid<(Upstream, Vec<Upstream>), String> {
+  Valid::from_iter(
+    schema_definition
+      .directives
+      .iter()
+      .filter(|directive| directive.node.name.node == "upstream"),
+    |directive| Upstream::from_directive(&directive.node),
+  )
+  .and_then(|upstreams| {
+    let (upstreams, unnamed): (Vec<_>, Vec<_>) = upstreams.into_iter().partition(|upstream| upstream.name.is_some());
+    Valid::<(), String>::fail("@upstream without a name is defined more than once".to_string())
+      .when(|| unnamed.len() > 1)
+      .map(|_| (unnamed.into_iter().next().unwrap_or_default(), upstreams))
+  })
+}
+fn links(schema_definition: &SchemaDefinition) -> Valid<Vec<Link>, String> {
+  Valid::from_iter(
+    schema_definition
+      .directives
+      .iter()
+      .filter(|directive| directive.node.name.node == "link"),
+    |directive| Link::from_directive(&directive.node),
+  )
 }
 fn to_root_schema(schema_definition: &SchemaDefinition) -> RootSchema {
   let query = schema_definition.query.as_ref().map(pos_name_to_string);
@@ -82,6 +107,7 @@ fn to_types(type_definitions: &Vec<&Positioned<TypeDefinition>>) -> Valid<BTreeM
         &type_definition.node.description,
         false,
         &object_type.implements,
+        &type_definition.node.directives,
       )
       .some(),
       TypeKind::Interface(interface_type) => to_object_type(
@@ -89,6 +115,7 @@ fn to_types(type_definitions: &Vec<&Positioned<TypeDefinition>>) -> Valid<BTreeM
         &type_definition.node.description,
         true,
         &interface_type.implements,
+        &type_definition.node.directives,
       )
       .some(),
       TypeKind::Enum(enum_type) => Valid::succeed(Some(to_enum(enum_type))),
@@ -129,12 +156,17 @@ fn to_object_type(
   description: &Option<Positioned<String>>,
   interface: bool,
   implements: &[Positioned<Name>],
+  directives: &[Positioned<ConstDirective>],
 ) -> Valid<config::Type, String> {
-  to_fields(fields).map(|fields| {
-    let doc = description.as_ref().map(|pos| pos.node.clone());
-    let implements = implements.iter().map(|pos| pos.node.to_string()).collect();
-    config::Type { fields, doc, interface, implements, ..Default::default() }
-  })
+  to_fields(fields)
+    .zip(to_cache(directives))
+    .zip(to_key(directives))
+    .map(|((fields, cache), key)| {
+      let doc = description.as_ref().map(|pos| pos.node.clone());
+      let implements = implements.iter().map(|pos| pos.node.to_string()).collect();
+      let protected = to_protected(directives);
+      config::Type { fields, doc, interface, implements, cache, protected, key, ..Default::default() }
+    })
 }
 fn to_enum(enum_type: EnumType) -> config::Type {
   let variants = enum_type
@@ -201,23 +233,38 @@ fn to_common_field(
   let doc = description.as_ref().map(|pos| pos.node.clone());
   let modify = to_modify(directives);
   let inline = to_inline(directives);
-  to_http(directives).map(|http| {
-    let unsafe_operation = to_unsafe_operation(directives);
-    let const_field = to_const_field(directives);
-    config::Field {
-      type_of,
-      list,
-      required: !nullable,
-      list_type_required,
-      args,
-      doc,
-      modify,
-      inline,
-      http,
-      unsafe_operation,
-      const_field,
-    }
-  })
+  to_http(directives)
+    .zip(to_graphql(directives))
+    .zip(to_grpc(directives))
+    .zip(to_cache(directives))
+    .map(|(((http, graphql), grpc), cache)| {
+      let unsafe_operation = to_unsafe_operation(directives);
+      let const_field = to_const_field(directives);
+      let protected = to_protected(directives);
+      let expr = to_expr(directives);
+      let call = to_call(directives);
+      let pipeline = to_pipeline(directives);
+      config::Field {
+        type_of,
+        list,
+        required: !nullable,
+        list_type_required,
+        args,
+        doc,
+        modify,
+        inline,
+        http,
+        unsafe_operation,
+        const_field,
+        graphql,
+        grpc,
+        cache,
+        protected,
+        expr,
+        call,
+        pipeline,
+      }
+    })
 }
 fn to_unsafe_operation(directives: &[Positioned<ConstDirective>]) -> Option<config::Unsafe> {
   directives.iter().find_map(|directive| {
@@ -288,6 +335,43 @@ fn to_http(directives: &[Positioned<ConstDirective>]) -> Valid<Option<config::Ht
   }
   Valid::succeed(None)
 }
+fn to_cache(directives: &[Positioned<ConstDirective>]) -> Valid<Option<config::Cache>, String> {
+  for directive in directives {
+    if directive.node.name.node == "cache" {
+      return config::Cache::from_directive(&directive.node).map(Some);
+    }
+  }
+  Valid::succeed(None)
+}
+fn to_key(directives: &[Positioned<ConstDirective>]) -> Valid<Option<config::Key>, String> {
+  for directive in directives {
+    if directive.node.name.node == "key" {
+      return config::Key::from_directive(&directive.node).map(Some);
+    }
+  }
+  Valid::succeed(None)
+}
+fn to_protected(directives: &[Positioned<ConstDirective>]) -> bool {
+  directives
+    .iter()
+    .any(|directive| directive.node.name.node == "protected")
+}
+fn to_graphql(directives: &[Positioned<ConstDirective>]) -> Valid<Option<config::GraphQLOperation>, String> {
+  for directive in directives {
+    if directive.node.name.node == "graphQL" {
+      return GraphQLOperation::from_directive(&directive.node).map(Some);
+    }
+  }
+  Valid::succeed(None)
+}
+fn to_grpc(directives: &[Positioned<ConstDirective>]) -> Valid<Option<config::Grpc>, String> {
+  for directive in directives {
+    if directive.node.name.node == "grpc" {
+      return Grpc::from_directive(&directive.node).map(Some);
+    }
+  }
+  Valid::succeed(None)
+}
 fn to_union(union_type: UnionType, doc: &Option<String>) -> Union {
   let types = union_type
     .members
@@ -305,6 +389,33 @@ fn to_const_field(directives: &[Positioned<ConstDirective>]) -> Option<config::C
     }
   })
 }
+fn to_expr(directives: &[Positioned<ConstDirective>]) -> Option<config::Expr> {
+  directives.iter().find_map(|directive| {
+    if directive.node.name.node == "expr" {
+      config::Expr::from_directive(&directive.node).to_result().ok()
+    } else {
+      None
+    }
+  })
+}
+fn to_call(directives: &[Positioned<ConstDirective>]) -> Option<config::Call> {
+  directives.iter().find_map(|directive| {
+    if directive.node.name.node == "call" {
+      config::Call::from_directive(&directive.node).to_result().ok()
+    } else {
+      None
+    }
+  })
+}
+fn to_pipeline(directives: &[Positioned<ConstDirective>]) -> Option<config::Pipeline> {
+  directives.iter().find_map(|directive| {
+    if directive.node.name.node == "pipeline" {
+      config::Pipeline::from_directive(&directive.node).to_result().ok()
+    } else {
+      None
+    }
+  })
+}
 
 trait HasName {
   fn name(&self) -> &Positioned<Name>;
@@ -15,7 +15,21 @@ fn config_document(config: &Config) -> ServiceDocument {
     directives: vec![
       pos(config.server.to_directive("server".to_string())),
       pos(config.upstream.to_directive("upstream".to_string())),
-    ],
+    ]
+    .into_iter()
+    .chain(
+      config
+        .upstreams
+        .iter()
+        .map(|upstream| pos(upstream.to_directive("upstream".to_string()))),
+    )
+    .chain(
+      config
+        .links
+        .iter()
+        .map(|link| pos(link.to_directive("link".to_string()))),
+    )
+    .collect(),
     query: config.graphql.schema.query.clone().map(|name| pos(Name::new(name))),
     mutation: config.graphql.schema.mutation.clone().map(|name| pos(Name::new(name))),
     subscription: config
@@ -162,7 +176,13 @@ fn config_document(config: &Config) -> ServiceDocument {
       extend: false,
       description: None,
       name: pos(Name::new(type_name.clone())),
-      directives: Vec::new(),
+      directives: type_def
+        .cache
+        .iter()
+        .map(|cache| pos(cache.to_directive("cache".to_string())))
+        .chain(type_def.protected.then(|| pos(protected_directive())))
+        .chain(type_def.key.iter().map(|key| pos(key.to_directive("key".to_string()))))
+        .collect(),
       kind,
     })));
   }
@@ -195,6 +215,26 @@ fn get_directives(field: &crate::config::Field) -> Vec<Positioned<ConstDirective
     let us_dir = const_field.to_directive("const".to_string());
     directives.push(pos(us_dir));
   }
+  if let Some(expr) = field.clone().expr {
+    let expr_dir = expr.to_directive("expr".to_string());
+    directives.push(pos(expr_dir));
+  }
+  if let Some(call) = field.clone().call {
+    let call_dir = call.to_directive("call".to_string());
+    directives.push(pos(call_dir));
+  }
+  if let Some(pipeline) = field.clone().pipeline {
+    let pipeline_dir = pipeline.to_directive("pipeline".to_string());
+    directives.push(pos(pipeline_dir));
+  }
+  if let Some(graphql) = field.clone().graphql {
+    let graphql_dir = graphql.to_directive("graphQL".to_string());
+    directives.push(pos(graphql_dir));
+  }
+  if let Some(grpc) = field.clone().grpc {
+    let grpc_dir = grpc.to_directive("grpc".to_string());
+    directives.push(pos(grpc_dir));
+  }
   if let Some(inline) = field.clone().inline {
     let il_dir = inline.to_directive("inline".to_string());
     directives.push(pos(il_dir));
@@ -203,9 +243,20 @@ fn get_directives(field: &crate::config::Field) -> Vec<Positioned<ConstDirective
     let dir = modify.to_directive("modify".to_string());
     directives.push(pos(dir));
   }
+  if let Some(cache) = field.clone().cache {
+    let dir = cache.to_directive("cache".to_string());
+    directives.push(pos(dir));
+  }
+  if field.protected {
+    directives.push(pos(protected_directive()));
+  }
   directives
 }
 
+fn protected_directive() -> ConstDirective {
+  ConstDirective { name: pos(Name::new("protected")), arguments: Vec::new() }
+}
+
 impl From<Config> for ServiceDocument {
   fn from(value: Config) -> Self {
     config_document(&value)
@@ -14,6 +14,9 @@ pub struct Server {
   pub enable_introspection: Option<bool>,
   pub enable_query_validation: Option<bool>,
   pub enable_response_validation: Option<bool>,
+  /// Exposes the schema as an Apollo Federation subgraph.
+  #[serde(skip_serializing_if = "is_default")]
+  pub federation: Option<bool>,
   pub global_response_timeout: Option<i64>,
   #[serde(skip_serializing_if = "is_default")]
   pub hostname: Option<String>,
@@ -22,6 +25,157 @@ pub struct Server {
   pub vars: KeyValues,
   #[serde(skip_serializing_if = "is_default", default)]
   pub response_headers: KeyValues,
+  #[serde(skip_serializing_if = "is_default")]
+  pub auth: Option<Auth>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub metrics: Option<Metrics>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub telemetry: Option<Telemetry>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub query_limits: Option<QueryLimits>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub persisted_queries: Option<PersistedQueries>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub cors: Option<Cors>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub tls: Option<Tls>,
+  /// Accepts HTTP/2 without TLS, from clients that start the connection with HTTP/2. Enabled by default.
+  #[serde(skip_serializing_if = "is_default")]
+  pub h2c: Option<bool>,
+}
+
+/// Terminates TLS with the certificate chain and private key of the PEM files.
+/// HTTP/2 is negotiated with the clients that support it.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug, Default)]
+pub struct Tls {
+  pub cert: String,
+  pub key: String,
+}
+
+/// Allows browsers to call the server from the listed origins.
+/// `OPTIONS` preflight requests are answered by the server, and the other responses carry the CORS headers.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Cors {
+  /// Origins that are allowed to call the server, `*` allows any origin.
+  pub allow_origins: Vec<String>,
+  pub allow_methods: Vec<String>,
+  pub allow_headers: Vec<String>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub expose_headers: Vec<String>,
+  /// Seconds for which the browser can cache the response to a preflight request.
+  #[serde(skip_serializing_if = "is_default")]
+  pub max_age: Option<u64>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub allow_credentials: bool,
+}
+impl Default for Cors {
+  fn default() -> Self {
+    Cors {
+      allow_origins: vec!["*".to_string()],
+      allow_methods: vec!["GET".to_string(), "POST".to_string(), "OPTIONS".to_string()],
+      allow_headers: vec!["content-type".to_string()],
+      expose_headers: Vec::new(),
+      max_age: None,
+      allow_credentials: false,
+    }
+  }
+}
+
+/// Accepts Automatic Persisted Queries, that are sent as the SHA-256 hash of their query.
+/// In `strict` mode, only the operations of the `manifest` are accepted.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PersistedQueries {
+  /// Maximum number of queries that are kept in memory.
+  pub max_size: u64,
+  /// Path of an Apollo persisted query manifest.
+  #[serde(skip_serializing_if = "is_default")]
+  pub manifest: Option<String>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub strict: bool,
+}
+impl Default for PersistedQueries {
+  fn default() -> Self {
+    PersistedQueries { max_size: 1000, manifest: None, strict: false }
+  }
+}
+
+/// Limits on the shape of incoming queries, that are checked before the queries are executed.
+/// Fields weigh 1 in the complexity of a query, unless they set a `cost` with `@http`.
+/// Introspection fields are measured like any other field.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryLimits {
+  #[serde(skip_serializing_if = "is_default")]
+  pub max_depth: Option<usize>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub max_complexity: Option<u64>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub max_aliases: Option<usize>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub max_root_fields: Option<usize>,
+}
+
+/// Serves the Prometheus metrics of the server at `path`.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+#[serde(default)]
+pub struct Metrics {
+  pub path: String,
+}
+impl Default for Metrics {
+  fn default() -> Self {
+    Metrics { path: "/metrics".to_string() }
+  }
+}
+
+/// Exports the traces of operations, resolvers and upstream requests to an OpenTelemetry collector.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Telemetry {
+  pub otlp: Otlp,
+  #[serde(skip_serializing_if = "is_default")]
+  pub service_name: Option<String>,
+}
+
+impl Telemetry {
+  pub fn get_service_name(&self) -> String {
+    self.service_name.clone().unwrap_or("tailcall".to_string())
+  }
+}
+
+/// Sends spans over OTLP/HTTP to `{url}/v1/traces`.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug, Default)]
+pub struct Otlp {
+  pub url: String,
+  #[serde(default, skip_serializing_if = "is_default")]
+  pub headers: KeyValues,
+}
+
+/// Auth lists the providers that can authenticate an incoming request.
+/// A request is authenticated as soon as one of them accepts its credentials.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug, Default)]
+pub struct Auth {
+  #[serde(skip_serializing_if = "is_default")]
+  pub jwks: Option<Jwks>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub htpasswd: Option<Htpasswd>,
+}
+
+/// Validates `Bearer` tokens against the keys of a JSON Web Key Set.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug, Default)]
+pub struct Jwks {
+  pub file: String,
+  #[serde(skip_serializing_if = "is_default")]
+  pub issuer: Option<String>,
+  #[serde(default, skip_serializing_if = "is_default")]
+  pub audiences: BTreeSet<String>,
+}
+
+/// Validates `Basic` credentials against the users of an htpasswd file.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug, Default)]
+pub struct Htpasswd {
+  pub file: String,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug, Setters)]
@@ -37,6 +191,58 @@ impl Default for Batch {
   }
 }
 
+/// Retries failed upstream requests with an exponential backoff, starting at `backoffMs` and capped at 30 seconds.
+/// Only requests with an idempotent method are retried, unless the field is marked as idempotent.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug, Setters)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Retry {
+  pub max_attempts: usize,
+  pub backoff_ms: u64,
+  pub retry_on: BTreeSet<RetryOn>,
+}
+impl Default for Retry {
+  fn default() -> Self {
+    Retry {
+      max_attempts: 3,
+      backoff_ms: 100,
+      retry_on: BTreeSet::from([
+        RetryOn::Status(502),
+        RetryOn::Status(503),
+        RetryOn::Status(504),
+        RetryOn::Error(RetryError::Timeout),
+      ]),
+    }
+  }
+}
+
+/// A response status, or a kind of error, that causes a request to be retried.
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+#[serde(untagged)]
+pub enum RetryOn {
+  Status(u16),
+  Error(RetryError),
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum RetryError {
+  Timeout,
+  Connect,
+}
+
+/// Stops sending requests to a host for `openMs` after `failureThreshold` consecutive failures.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug, Setters)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CircuitBreaker {
+  pub failure_threshold: usize,
+  pub open_ms: u64,
+}
+impl Default for CircuitBreaker {
+  fn default() -> Self {
+    CircuitBreaker { failure_threshold: 5, open_ms: 30000 }
+  }
+}
+
 impl Server {
   pub fn enable_apollo_tracing(&self) -> bool {
     self.enable_apollo_tracing.unwrap_or(false)
@@ -62,6 +268,12 @@ impl Server {
   pub fn enable_query_validation(&self) -> bool {
     self.enable_query_validation.unwrap_or(true)
   }
+  pub fn enable_federation(&self) -> bool {
+    self.federation.unwrap_or(false)
+  }
+  pub fn enable_h2c(&self) -> bool {
+    self.h2c.unwrap_or(true)
+  }
 
   pub fn get_hostname(&self) -> String {
     self.hostname.clone().unwrap_or("127.0.0.1".to_string())
@@ -82,6 +294,7 @@ impl Server {
     self.enable_introspection = other.enable_introspection.or(self.enable_introspection);
     self.enable_query_validation = other.enable_query_validation.or(self.enable_query_validation);
     self.enable_response_validation = other.enable_response_validation.or(self.enable_response_validation);
+    self.federation = other.federation.or(self.federation);
     self.global_response_timeout = other.global_response_timeout.or(self.global_response_timeout);
     self.port = other.port.or(self.port);
     self.hostname = other.hostname.or(self.hostname);
@@ -91,6 +304,14 @@ impl Server {
     let mut response_headers = self.response_headers.0.clone();
     response_headers.extend(other.response_headers.0);
     self.response_headers = KeyValues(response_headers);
+    self.auth = other.auth.or(self.auth);
+    self.metrics = other.metrics.or(self.metrics);
+    self.telemetry = other.telemetry.or(self.telemetry);
+    self.query_limits = other.query_limits.or(self.query_limits);
+    self.persisted_queries = other.persisted_queries.or(self.persisted_queries);
+    self.cors = other.cors.or(self.cors);
+    self.tls = other.tls.or(self.tls);
+    self.h2c = other.h2c.or(self.h2c);
     self
   }
 }
@@ -103,6 +324,9 @@ pub struct Proxy {
 #[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug, Setters, Default)]
 #[serde(rename_all = "camelCase", default)]
 pub struct Upstream {
+  /// Name of the profile, that `@http(upstream:)` refers to. The unnamed `@upstream` applies to the other requests.
+  #[serde(skip_serializing_if = "is_default")]
+  pub name: Option<String>,
   #[serde(skip_serializing_if = "is_default")]
   pub pool_idle_timeout: Option<u64>,
   #[serde(skip_serializing_if = "is_default")]
@@ -132,6 +356,28 @@ pub struct Upstream {
   pub enable_http_cache: Option<bool>,
   #[serde(skip_serializing_if = "is_default")]
   pub batch: Option<Batch>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub retry: Option<Retry>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub circuit_breaker: Option<CircuitBreaker>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub tls: Option<UpstreamTls>,
+}
+
+/// TLS settings of the connections to upstreams, with PEM files for the CA bundle and the client certificate.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpstreamTls {
+  /// CA certificates that are trusted in addition to the built-in roots.
+  #[serde(skip_serializing_if = "is_default")]
+  pub ca_cert: Option<String>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub client_cert: Option<String>,
+  #[serde(skip_serializing_if = "is_default")]
+  pub client_key: Option<String>,
+  /// Accepts any certificate from the upstream. Only meant for development.
+  #[serde(default, skip_serializing_if = "is_default")]
+  pub insecure_skip_verify: bool,
 }
 
 impl Upstream {
@@ -178,6 +424,7 @@ impl Upstream {
         other
       }
     });
+    self.name = other.name.or(self.name);
     self.base_url = other.base_url.or(self.base_url);
     self.connect_timeout = other.connect_timeout.or(self.connect_timeout);
     self.enable_http_cache = other.enable_http_cache.or(self.enable_http_cache);
@@ -197,6 +444,9 @@ impl Upstream {
       batch.headers.extend(other.headers);
       batch
     });
+    self.retry = other.retry.or(self.retry);
+    self.circuit_breaker = other.circuit_breaker.or(self.circuit_breaker);
+    self.tls = other.tls.or(self.tls);
     self
   }
 }
@@ -1,5 +1,6 @@
 use thiserror::Error;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Source {
   Json,
   Yml,
@@ -28,6 +29,13 @@ impl Source {
     file.ends_with(&format!(".{}", self.ext()))
   }
 
+  pub fn from_ext(ext: &str) -> Result<Source, UnsupportedFileFormat> {
+    ALL
+      .into_iter()
+      .find(|format| format.ext() == ext)
+      .ok_or_else(|| UnsupportedFileFormat(ext.to_string()))
+  }
+
   pub fn detect(name: &str) -> Result<Source, UnsupportedFileFormat> {
     ALL
       .into_iter()
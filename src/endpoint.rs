@@ -16,6 +16,10 @@ pub struct Endpoint {
   pub headers: HeaderMap,
   pub body: Option<String>,
   pub description: Option<String>,
+  /// Whether the request can be retried regardless of its method.
+  pub idempotent: bool,
+  /// Name of the upstream profile of the requests, if it isn't the unnamed one.
+  pub upstream: Option<String>,
 }
 
 impl Endpoint {
@@ -29,6 +33,8 @@ impl Endpoint {
       headers: Default::default(),
       body: Default::default(),
       description: Default::default(),
+      idempotent: Default::default(),
+      upstream: Default::default(),
     }
   }
 }
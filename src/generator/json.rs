@@ -0,0 +1,310 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+use url::Url;
+
+use super::{is_name, to_camel_case, to_pascal_case};
+use crate::config::{Config, Field, Http, RootSchema, Type, Upstream};
+
+/// The names of the generated types can't clash with the root type or the built-in scalars.
+const RESERVED_NAMES: [&str; 7] = ["Query", "String", "Int", "Float", "Boolean", "ID", "JSON"];
+
+/// Generates a config with a single `Query` field, resolved with `@http` from `url`,
+/// whose types are inferred from sample responses of the endpoint.
+/// `url` is either a path, or a full URL that also sets the `baseURL` of the upstream.
+pub fn from_json(samples: &[Value], url: &str) -> Result<Config> {
+  let (base_url, path) = match Url::parse(url) {
+    Ok(url) => {
+      let base_url = url[..url::Position::BeforePath].to_string();
+      (Some(base_url), url[url::Position::BeforePath..].to_string())
+    }
+    Err(_) if url.starts_with('/') => (None, url.to_string()),
+    Err(_) => bail!("'{url}' must be a path starting with '/' or a full URL"),
+  };
+
+  let shape = samples
+    .iter()
+    .map(Shape::from_value)
+    .reduce(Shape::merge)
+    .unwrap_or(Shape::Unknown);
+  let field_name = to_field_name(&path);
+
+  let mut generator = JsonGenerator::default();
+  let (type_of, list) = generator.type_of(&shape, &to_pascal_case(&field_name));
+  let http = Http { path, ..Default::default() };
+  let field = Field { type_of, list, http: Some(http), ..Default::default() };
+
+  let mut config = Config::default().upstream(Upstream { base_url, ..Default::default() });
+  config.graphql.schema = RootSchema::default().query("Query".to_string());
+  config.graphql.types = generator.types;
+  config.graphql.types.insert(
+    "Query".to_string(),
+    Type::default().fields(vec![(field_name.as_str(), field)]),
+  );
+  Ok(config)
+}
+
+/// Shape is the structure of JSON values, merged across all the values observed at the same position.
+#[derive(Clone, Debug, PartialEq)]
+enum Shape {
+  /// Only nulls or empty lists were observed.
+  Unknown,
+  Scalar(&'static str),
+  Object(BTreeMap<String, FieldShape>),
+  List(Box<Shape>),
+  /// Values that don't fit a single GraphQL type.
+  Json,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct FieldShape {
+  shape: Shape,
+  /// Whether the field was present and not null in every object.
+  required: bool,
+}
+
+impl Shape {
+  fn from_value(value: &Value) -> Self {
+    match value {
+      Value::Null => Shape::Unknown,
+      Value::Bool(_) => Shape::Scalar("Boolean"),
+      Value::Number(number) if number.is_i64() || number.is_u64() => Shape::Scalar("Int"),
+      Value::Number(_) => Shape::Scalar("Float"),
+      Value::String(_) => Shape::Scalar("String"),
+      Value::Array(items) => Shape::List(Box::new(
+        items
+          .iter()
+          .map(Shape::from_value)
+          .reduce(Shape::merge)
+          .unwrap_or(Shape::Unknown),
+      )),
+      // Keys that aren't valid field names are usually ids, that make the object a map
+      Value::Object(map) if !map.keys().all(|key| is_name(key)) => Shape::Json,
+      Value::Object(map) => Shape::Object(
+        map
+          .iter()
+          .map(|(key, value)| {
+            let field = FieldShape { shape: Shape::from_value(value), required: !value.is_null() };
+            (key.clone(), field)
+          })
+          .collect(),
+      ),
+    }
+  }
+
+  fn merge(self, other: Self) -> Self {
+    match (self, other) {
+      (Shape::Unknown, shape) | (shape, Shape::Unknown) => shape,
+      (Shape::Scalar("Int"), Shape::Scalar("Float")) | (Shape::Scalar("Float"), Shape::Scalar("Int")) => {
+        Shape::Scalar("Float")
+      }
+      (Shape::Scalar(left), Shape::Scalar(right)) if left == right => Shape::Scalar(left),
+      (Shape::List(left), Shape::List(right)) => Shape::List(Box::new(left.merge(*right))),
+      (Shape::Object(left), Shape::Object(mut right)) => {
+        let mut fields = BTreeMap::new();
+        for (key, field) in left {
+          let field = match right.remove(&key) {
+            Some(other) => {
+              FieldShape { shape: field.shape.merge(other.shape), required: field.required && other.required }
+            }
+            // Fields missing from one of the objects are nullable
+            None => FieldShape { shape: field.shape, required: false },
+          };
+          fields.insert(key, field);
+        }
+        for (key, field) in right {
+          fields.insert(key, FieldShape { shape: field.shape, required: false });
+        }
+        Shape::Object(fields)
+      }
+      _ => Shape::Json,
+    }
+  }
+}
+
+/// JsonGenerator creates the types of the object shapes,
+/// reusing the same type for structurally identical objects.
+#[derive(Default)]
+struct JsonGenerator {
+  types: BTreeMap<String, Type>,
+  names: HashMap<Vec<(String, String, bool, bool)>, String>,
+}
+
+impl JsonGenerator {
+  /// Returns the type name of the shape, and whether it's a list.
+  fn type_of(&mut self, shape: &Shape, name_hint: &str) -> (String, bool) {
+    match shape {
+      Shape::Unknown | Shape::Json => ("JSON".to_string(), false),
+      Shape::Scalar(name) => (name.to_string(), false),
+      Shape::List(item) => match self.type_of(item, &to_singular(name_hint)) {
+        (name, false) => (name, true),
+        (_, true) => ("JSON".to_string(), true),
+      },
+      Shape::Object(fields) => {
+        let fields = fields
+          .iter()
+          .map(|(name, field)| {
+            let (type_of, list) = self.type_of(&field.shape, &to_pascal_case(name));
+            let field = Field { type_of, list, required: field.required, ..Default::default() };
+            (name.clone(), field)
+          })
+          .collect::<BTreeMap<_, _>>();
+
+        let signature = fields
+          .iter()
+          .map(|(name, field)| (name.clone(), field.type_of.clone(), field.list, field.required))
+          .collect::<Vec<_>>();
+        if let Some(name) = self.names.get(&signature) {
+          return (name.clone(), false);
+        }
+
+        let name = self.unique_name(name_hint);
+        self.names.insert(signature, name.clone());
+        self.types.insert(name.clone(), Type { fields, ..Default::default() });
+        (name, false)
+      }
+    }
+  }
+
+  fn unique_name(&self, name_hint: &str) -> String {
+    let name_hint = if name_hint.is_empty() { "Data" } else { name_hint };
+    let mut name = name_hint.to_string();
+    let mut suffix = 1;
+    while self.types.contains_key(&name) || RESERVED_NAMES.contains(&name.as_str()) {
+      suffix += 1;
+      name = format!("{name_hint}{suffix}");
+    }
+    name
+  }
+}
+
+/// Names the query field after the last segment of the path that isn't an id, e.g. `users` for `/users/1`.
+fn to_field_name(path: &str) -> String {
+  path
+    .split('?')
+    .next()
+    .unwrap_or_default()
+    .split('/')
+    .rev()
+    .map(to_camel_case)
+    .find(|segment| is_name(segment))
+    .unwrap_or("data".to_string())
+}
+
+/// Turns the plural name of a list into the name of its items, e.g. `addresses` into `address`.
+fn to_singular(name: &str) -> String {
+  if let Some(stem) = name.strip_suffix("ies").filter(|stem| !stem.is_empty()) {
+    return format!("{stem}y");
+  }
+  // Words ending in a sibilant take `es` in the plural
+  for suffix in ["sses", "xes", "zes", "ches", "shes"] {
+    if let Some(stem) = name.strip_suffix(suffix) {
+      return format!("{stem}{}", &suffix[..suffix.len() - 2]);
+    }
+  }
+  if ["ss", "us", "is"].iter().any(|suffix| name.ends_with(suffix)) {
+    return name.to_string();
+  }
+  name.strip_suffix('s').unwrap_or(name).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  use super::*;
+
+  fn users() -> Value {
+    serde_json::from_str(&std::fs::read_to_string("tests/data/users.json").unwrap()).unwrap()
+  }
+
+  #[test]
+  fn test_infer_types() {
+    let config = from_json(&[users()], "http://jsonplaceholder.typicode.com/users").unwrap();
+    let users = &config.find_type("Query").unwrap().fields["users"];
+    let user = config.find_type(&users.type_of).unwrap();
+
+    assert_eq!(users.type_of, "User");
+    assert!(users.list);
+    assert_eq!(users.http.as_ref().unwrap().path, "/users");
+    assert_eq!(
+      config.upstream.base_url.as_deref(),
+      Some("http://jsonplaceholder.typicode.com")
+    );
+    assert_eq!(user.fields["id"].type_of, "Int");
+    assert_eq!(user.fields["address"].type_of, "Address");
+    assert_eq!(config.find_type("Address").unwrap().fields["geo"].type_of, "Geo");
+  }
+
+  #[test]
+  fn test_merge_identical_objects() {
+    let sample = json!({
+      "billing": {"street": "Kulas Light", "city": "Gwenborough"},
+      "shipping": {"street": "Victor Plains", "city": "Wisokyburgh"}
+    });
+    let config = from_json(&[sample], "/addresses").unwrap();
+    let addresses = config.find_type("Addresses").unwrap();
+
+    assert_eq!(addresses.fields["billing"].type_of, "Billing");
+    assert_eq!(addresses.fields["shipping"].type_of, "Billing");
+    assert!(config.find_type("Shipping").is_none());
+  }
+
+  #[test]
+  fn test_nullable_fields() {
+    let sample = json!([
+      {"id": 1, "name": "Leanne", "website": null},
+      {"id": 2, "website": "hildegard.org", "score": 1.5}
+    ]);
+    let config = from_json(&[sample], "/users").unwrap();
+    let user = config.find_type("User").unwrap();
+
+    assert!(user.fields["id"].required);
+    assert!(!user.fields["name"].required);
+    assert!(!user.fields["website"].required);
+    assert_eq!(user.fields["website"].type_of, "String");
+    assert_eq!(user.fields["score"].type_of, "Float");
+  }
+
+  #[test]
+  fn test_map_of_ids() {
+    let sample = serde_json::from_str(&std::fs::read_to_string("tests/data/user-posts.json").unwrap()).unwrap();
+    let config = from_json(&[sample], "/posts").unwrap();
+
+    assert_eq!(config.find_type("Query").unwrap().fields["posts"].type_of, "JSON");
+  }
+
+  #[test]
+  fn test_generated_sdl_is_valid() {
+    let config = from_json(&[users()], "http://jsonplaceholder.typicode.com/users").unwrap();
+    let config = Config::from_sdl(&config.to_sdl()).to_result().unwrap();
+
+    crate::blueprint::Blueprint::try_from(&config).unwrap();
+  }
+
+  #[test]
+  fn test_to_field_name() {
+    assert_eq!(to_field_name("/users/1"), "users");
+    assert_eq!(to_field_name("/user-posts?limit=1"), "userPosts");
+    assert_eq!(to_field_name("/"), "data");
+  }
+
+  #[test]
+  fn test_to_singular() {
+    assert_eq!(to_singular("addresses"), "address");
+    assert_eq!(to_singular("categories"), "category");
+    assert_eq!(to_singular("boxes"), "box");
+    assert_eq!(to_singular("users"), "user");
+    assert_eq!(to_singular("status"), "status");
+  }
+
+  #[test]
+  fn test_reserved_names() {
+    let config = from_json(&[json!({"string": {"id": 1}})], "http://example.com/data").unwrap();
+
+    assert_eq!(config.find_type("Query").unwrap().fields["data"].type_of, "Data");
+    assert_eq!(config.find_type("Data").unwrap().fields["string"].type_of, "String2");
+  }
+}
@@ -0,0 +1,57 @@
+mod json;
+mod openapi;
+
+pub use json::from_json;
+pub use openapi::from_openapi;
+
+/// Checks that the name is a valid GraphQL name.
+fn is_name(name: &str) -> bool {
+  let mut chars = name.chars();
+  chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+    && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Converts a name such as `user_id` or `user-id` into `UserId`.
+fn to_pascal_case(name: &str) -> String {
+  name
+    .split(|c: char| !c.is_ascii_alphanumeric())
+    .filter(|word| !word.is_empty())
+    .map(|word| {
+      let mut chars = word.chars();
+      chars
+        .next()
+        .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+        .unwrap_or_default()
+    })
+    .collect()
+}
+
+/// Converts a name such as `user_id` or `user-id` into `userId`.
+fn to_camel_case(name: &str) -> String {
+  let name = to_pascal_case(name);
+  let mut chars = name.chars();
+  chars
+    .next()
+    .map(|first| first.to_ascii_lowercase().to_string() + chars.as_str())
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_case_conversion() {
+    assert_eq!(to_pascal_case("user_id"), "UserId");
+    assert_eq!(to_pascal_case("/users/{id}"), "UsersId");
+    assert_eq!(to_camel_case("list-pets"), "listPets");
+    assert_eq!(to_camel_case("ListPets"), "listPets");
+  }
+
+  #[test]
+  fn test_is_name() {
+    assert!(is_name("AVAILABLE"));
+    assert!(!is_name("in-stock"));
+    assert!(!is_name("1st"));
+  }
+}
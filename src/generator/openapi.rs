@@ -0,0 +1,467 @@
+#![allow(clippy::too_many_arguments)]
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+use openapiv3::{
+  MediaType, ObjectType, OpenAPI, Operation, Parameter, ParameterSchemaOrContent, ReferenceOr, Schema, SchemaKind,
+  StatusCode, Type as SchemaType,
+};
+
+use super::{is_name, to_camel_case, to_pascal_case};
+use crate::config::{Arg, Config, Field, Http, KeyValues, RootSchema, Type, Upstream};
+use crate::http::Method;
+
+const SCHEMA_REF: &str = "#/components/schemas/";
+
+/// Generates a config from an OpenAPI 3 document in YAML or JSON.
+pub fn from_openapi(spec: &str) -> Result<Config> {
+  let spec: OpenAPI = serde_yaml::from_str(spec)?;
+  Ok(OpenApiGenerator::new(&spec).generate())
+}
+
+/// OpenApiGenerator converts the component schemas of the document into types,
+/// and its operations into `Query` and `Mutation` fields resolved with `@http`.
+struct OpenApiGenerator<'a> {
+  spec: &'a OpenAPI,
+  types: BTreeMap<String, Type>,
+}
+
+/// The GraphQL type of a schema, as it's set on a field or an argument.
+struct TypeRef {
+  name: String,
+  list: bool,
+}
+
+impl<'a> OpenApiGenerator<'a> {
+  fn new(spec: &'a OpenAPI) -> Self {
+    Self { spec, types: BTreeMap::new() }
+  }
+
+  fn generate(mut self) -> Config {
+    let components = self
+      .spec
+      .components
+      .iter()
+      .flat_map(|components| components.schemas.iter());
+    for (name, schema) in components {
+      let type_name = to_pascal_case(name);
+      if !self.types.contains_key(&type_name) {
+        if let ReferenceOr::Item(schema) = schema {
+          self.insert_type(&type_name, schema, false);
+        }
+      }
+    }
+
+    let mut query = BTreeMap::new();
+    let mut mutation = BTreeMap::new();
+    for (path, method, operation) in self.spec.operations() {
+      let Some(method) = to_method(method) else {
+        continue;
+      };
+      // Operation ids aren't necessarily valid GraphQL names, or unique once converted to camel case
+      let name = operation
+        .operation_id
+        .as_deref()
+        .map(to_camel_case)
+        .filter(|name| is_name(name))
+        .unwrap_or_else(|| to_operation_name(&method, path));
+      let fields = match method {
+        Method::GET => &mut query,
+        _ => &mut mutation,
+      };
+      let name = unique_field_name(fields, &name);
+      let field = self.operation_field(path, method.clone(), operation);
+      fields.insert(name, field);
+    }
+
+    let mut schema = RootSchema::default().query("Query".to_string());
+    self
+      .types
+      .insert("Query".to_string(), Type { fields: query, ..Default::default() });
+    if !mutation.is_empty() {
+      schema = schema.mutation("Mutation".to_string());
+      self
+        .types
+        .insert("Mutation".to_string(), Type { fields: mutation, ..Default::default() });
+    }
+
+    let base_url = self.spec.servers.first().map(|server| server.url.clone());
+    let mut config = Config::default().upstream(Upstream { base_url, ..Default::default() });
+    config.graphql.schema = schema;
+    config.graphql.types = self.types;
+    config
+  }
+
+  fn operation_field(&mut self, path: &str, method: Method, operation: &'a Operation) -> Field {
+    let mut args = BTreeMap::new();
+    let mut query = BTreeMap::new();
+    let spec = self.spec;
+    for parameter in operation
+      .parameters
+      .iter()
+      .filter_map(|parameter| resolve_parameter(spec, parameter))
+    {
+      let data = parameter.parameter_data_ref();
+      let type_of = match &data.format {
+        ParameterSchemaOrContent::Schema(schema) => self.type_ref(schema, &data.name, true),
+        ParameterSchemaOrContent::Content(_) => TypeRef { name: "JSON".to_string(), list: false },
+      };
+      match parameter {
+        Parameter::Path { .. } => {}
+        Parameter::Query { .. } => {
+          query.insert(data.name.clone(), format!("{{{{args.{}}}}}", data.name));
+        }
+        _ => continue,
+      }
+      let required = data.required || matches!(parameter, Parameter::Path { .. });
+      args.insert(
+        data.name.clone(),
+        Arg {
+          type_of: type_of.name,
+          list: type_of.list,
+          required,
+          doc: data.description.clone(),
+          modify: None,
+          default_value: None,
+        },
+      );
+    }
+
+    let mut body = None;
+    let request_body = operation.request_body.as_ref().and_then(|body| match body {
+      ReferenceOr::Item(body) => Some(body),
+      ReferenceOr::Reference { .. } => None,
+    });
+    if let Some(schema) = request_body.and_then(|body| to_json_schema(&body.content)) {
+      let type_of = self.type_ref(schema, "body", true);
+      let required = request_body.is_some_and(|body| body.required);
+      args.insert(
+        "body".to_string(),
+        Arg { type_of: type_of.name, list: type_of.list, required, doc: None, modify: None, default_value: None },
+      );
+      body = Some("{{args.body}}".to_string());
+    }
+
+    let type_of = match to_response_schema(operation) {
+      Some(schema) => self.type_ref(schema, &format!("{}Response", to_pascal_case(path)), false),
+      None => TypeRef { name: "JSON".to_string(), list: false },
+    };
+
+    let http = Http { path: to_path_template(path), method, query: KeyValues(query), body, ..Default::default() };
+    let doc = operation.summary.clone().or(operation.description.clone());
+    Field { type_of: type_of.name, list: type_of.list, args, doc, http: Some(http), ..Default::default() }
+  }
+
+  /// Returns the type of the schema, generating a type for the inline objects and enums it contains.
+  /// Input types are generated separately from output types, with an `Input` suffix.
+  fn type_ref(&mut self, schema: &ReferenceOr<Schema>, name_hint: &str, input: bool) -> TypeRef {
+    let schema = match schema {
+      ReferenceOr::Reference { reference } => {
+        let Some(name) = reference.strip_prefix(SCHEMA_REF) else {
+          return TypeRef { name: "JSON".to_string(), list: false };
+        };
+        let Some(schema) = self.find_schema(name) else {
+          return TypeRef { name: "JSON".to_string(), list: false };
+        };
+        if is_scalar(schema) {
+          return self.type_ref(&ReferenceOr::Item(schema.clone()), name, input);
+        }
+        let type_name = to_type_name(name, input && is_object(schema));
+        if !self.types.contains_key(&type_name) {
+          self.insert_type(&type_name, schema, input);
+        }
+        return TypeRef { name: type_name, list: false };
+      }
+      ReferenceOr::Item(schema) => schema,
+    };
+
+    let name = match &schema.schema_kind {
+      SchemaKind::Type(SchemaType::String(string)) if is_enum(&string.enumeration) => {
+        let type_name = to_pascal_case(name_hint);
+        self.insert_type(&type_name, schema, input);
+        type_name
+      }
+      SchemaKind::Type(SchemaType::String(_)) => "String".to_string(),
+      SchemaKind::Type(SchemaType::Integer(_)) => "Int".to_string(),
+      SchemaKind::Type(SchemaType::Number(_)) => "Float".to_string(),
+      SchemaKind::Type(SchemaType::Boolean(_)) => "Boolean".to_string(),
+      SchemaKind::Type(SchemaType::Array(array)) => {
+        let item = array.items.as_ref().map(|items| items.clone().unbox());
+        return match item.map(|item| self.type_ref(&item, name_hint, input)) {
+          Some(item) if !item.list => TypeRef { name: item.name, list: true },
+          _ => TypeRef { name: "JSON".to_string(), list: true },
+        };
+      }
+      SchemaKind::Type(SchemaType::Object(object)) if !object.properties.is_empty() && has_names(object) => {
+        let type_name = to_type_name(name_hint, input);
+        self.insert_type(&type_name, schema, input);
+        type_name
+      }
+      _ => "JSON".to_string(),
+    };
+
+    TypeRef { name, list: false }
+  }
+
+  /// Inserts the type generated from an object or enum schema.
+  fn insert_type(&mut self, type_name: &str, schema: &Schema, input: bool) {
+    // Inserted first, so that recursive schemas refer to the type instead of generating it again
+    self.types.insert(type_name.to_string(), Type::default());
+    let type_of = match &schema.schema_kind {
+      SchemaKind::Type(SchemaType::String(string)) if is_enum(&string.enumeration) => Type {
+        variants: Some(string.enumeration.iter().flatten().cloned().collect::<BTreeSet<_>>()),
+        ..Default::default()
+      },
+      SchemaKind::Type(SchemaType::Object(object)) if has_names(object) => {
+        let mut fields = BTreeMap::new();
+        for (name, property) in object.properties.iter() {
+          let type_of = self.type_ref(
+            &property.clone().unbox(),
+            &format!("{type_name}{}", to_pascal_case(name)),
+            input,
+          );
+          let doc = property
+            .as_item()
+            .and_then(|property| property.schema_data.description.clone());
+          let field = Field {
+            type_of: type_of.name,
+            list: type_of.list,
+            required: object.required.contains(name),
+            doc,
+            ..Default::default()
+          };
+          fields.insert(name.clone(), field);
+        }
+        Type { fields, doc: schema.schema_data.description.clone(), ..Default::default() }
+      }
+      _ => {
+        self.types.remove(type_name);
+        return;
+      }
+    };
+    self.types.insert(type_name.to_string(), type_of);
+  }
+
+  fn find_schema(&self, name: &str) -> Option<&'a Schema> {
+    let components = self.spec.components.as_ref()?;
+    components.schemas.get(name)?.as_item()
+  }
+}
+
+fn resolve_parameter<'a>(spec: &'a OpenAPI, parameter: &'a ReferenceOr<Parameter>) -> Option<&'a Parameter> {
+  match parameter {
+    ReferenceOr::Item(parameter) => Some(parameter),
+    ReferenceOr::Reference { reference } => {
+      let name = reference.strip_prefix("#/components/parameters/")?;
+      spec.components.as_ref()?.parameters.get(name)?.as_item()
+    }
+  }
+}
+
+fn to_method(method: &str) -> Option<Method> {
+  match method {
+    "get" => Some(Method::GET),
+    "post" => Some(Method::POST),
+    "put" => Some(Method::PUT),
+    "patch" => Some(Method::PATCH),
+    "delete" => Some(Method::DELETE),
+    _ => None,
+  }
+}
+
+/// Names an operation without an `operationId` after its method and path, e.g. `getUsersById`.
+fn to_operation_name(method: &Method, path: &str) -> String {
+  let mut name = format!("{method:?}").to_lowercase();
+  for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+    match segment.strip_prefix('{').and_then(|param| param.strip_suffix('}')) {
+      Some(param) => name.push_str(&format!("By{}", to_pascal_case(param))),
+      None => name.push_str(&to_pascal_case(segment)),
+    }
+  }
+  name
+}
+
+/// Numbers the name of a field that's already taken, e.g. `getUser2`.
+fn unique_field_name(fields: &BTreeMap<String, Field>, name: &str) -> String {
+  let mut unique_name = name.to_string();
+  let mut suffix = 1;
+  while fields.contains_key(&unique_name) {
+    suffix += 1;
+    unique_name = format!("{name}{suffix}");
+  }
+  unique_name
+}
+
+/// Converts the path params of an OpenAPI path into mustache templates, e.g. `/users/{id}` into `/users/{{args.id}}`.
+fn to_path_template(path: &str) -> String {
+  path
+    .split('/')
+    .map(
+      |segment| match segment.strip_prefix('{').and_then(|param| param.strip_suffix('}')) {
+        Some(param) => format!("{{{{args.{param}}}}}"),
+        None => segment.to_string(),
+      },
+    )
+    .collect::<Vec<_>>()
+    .join("/")
+}
+
+fn to_type_name(name: &str, input: bool) -> String {
+  let name = to_pascal_case(name);
+  if input {
+    format!("{name}Input")
+  } else {
+    name
+  }
+}
+
+/// Returns the schema of the first successful JSON response, falling back to the default response.
+fn to_response_schema(operation: &Operation) -> Option<&ReferenceOr<Schema>> {
+  let responses = &operation.responses;
+  let success = responses
+    .responses
+    .iter()
+    .find(|(status, _)| matches!(status, StatusCode::Code(200..=299) | StatusCode::Range(2)))
+    .map(|(_, response)| response);
+  match success.or(responses.default.as_ref())? {
+    ReferenceOr::Item(response) => to_json_schema(&response.content),
+    ReferenceOr::Reference { .. } => None,
+  }
+}
+
+fn to_json_schema(content: &indexmap::IndexMap<String, MediaType>) -> Option<&ReferenceOr<Schema>> {
+  content
+    .iter()
+    .find(|(media_type, _)| media_type.contains("json"))
+    .and_then(|(_, media_type)| media_type.schema.as_ref())
+}
+
+fn is_enum(variants: &[Option<String>]) -> bool {
+  !variants.is_empty() && variants.iter().flatten().all(|variant| is_name(variant))
+}
+
+/// Objects with properties that aren't valid GraphQL names are kept as `JSON`.
+fn has_names(object: &ObjectType) -> bool {
+  object.properties.keys().all(|name| is_name(name))
+}
+
+fn is_object(schema: &Schema) -> bool {
+  matches!(&schema.schema_kind, SchemaKind::Type(SchemaType::Object(_)))
+}
+
+fn is_scalar(schema: &Schema) -> bool {
+  match &schema.schema_kind {
+    SchemaKind::Type(SchemaType::String(string)) => !is_enum(&string.enumeration),
+    SchemaKind::Type(SchemaType::Object(object)) => !has_names(object),
+    _ => true,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  const PETSTORE: &str = "tests/openapi/petstore.yml";
+
+  fn generate() -> Config {
+    from_openapi(&std::fs::read_to_string(PETSTORE).unwrap()).unwrap()
+  }
+
+  #[test]
+  fn test_component_types() {
+    let config = generate();
+    let pet = config.find_type("Pet").unwrap();
+
+    assert!(pet.fields["id"].required);
+    assert_eq!(pet.fields["name"].type_of, "String");
+    assert_eq!(pet.fields["status"].type_of, "Status");
+    assert!(pet.fields["tags"].list);
+    assert_eq!(
+      config.find_type("Status").unwrap().variants,
+      Some(BTreeSet::from(["AVAILABLE".to_string(), "SOLD".to_string()]))
+    );
+    assert!(config.find_type("PetInput").is_some());
+  }
+
+  #[test]
+  fn test_invalid_property_names() {
+    let config = generate();
+    let pet = config.find_type("Pet").unwrap();
+
+    assert_eq!(pet.fields["owner"].type_of, "JSON");
+    assert!(config.find_type("Owner").is_none());
+  }
+
+  #[test]
+  fn test_operations() {
+    let config = generate();
+    let query = config.find_type("Query").unwrap();
+    let mutation = config.find_type("Mutation").unwrap();
+
+    let list_pets = &query.fields["listPets"];
+    assert!(list_pets.list);
+    assert_eq!(
+      list_pets.http.as_ref().unwrap().query.get("limit").unwrap(),
+      "{{args.limit}}"
+    );
+
+    let pet = &query.fields["getPetsByPetId"];
+    assert!(pet.args["petId"].required);
+    assert_eq!(pet.http.as_ref().unwrap().path, "/pets/{{args.petId}}");
+
+    let create_pet = &mutation.fields["postPets"];
+    let http = create_pet.http.as_ref().unwrap();
+    assert_eq!(http.method, Method::POST);
+    assert_eq!(http.body.as_deref(), Some("{{args.body}}"));
+    assert_eq!(create_pet.args["body"].type_of, "PetInput");
+    assert_eq!(
+      config.upstream.base_url.as_deref(),
+      Some("http://petstore.example.com/v1")
+    );
+  }
+
+  #[test]
+  fn test_operation_names() {
+    let spec = r#"
+      openapi: 3.0.0
+      info: { title: Users, version: 1.0.0 }
+      paths:
+        /users:
+          get: { operationId: 1st-user, responses: {} }
+          post: { operationId: create_user, responses: {} }
+          put: { operationId: create-user, responses: {} }
+    "#;
+    let config = from_openapi(spec).unwrap();
+
+    assert!(config.find_type("Query").unwrap().fields.contains_key("getUsers"));
+    let mutation = config.find_type("Mutation").unwrap();
+    assert_eq!(
+      mutation.fields.keys().collect::<Vec<_>>(),
+      vec!["createUser", "createUser2"]
+    );
+    assert_eq!(
+      mutation.fields["createUser2"].http.as_ref().unwrap().method,
+      Method::POST
+    );
+  }
+
+  #[test]
+  fn test_generated_sdl_is_valid() {
+    let config = generate();
+    let sdl = config.to_sdl();
+    let config = Config::from_sdl(&sdl).to_result().unwrap();
+
+    crate::blueprint::Blueprint::try_from(&config).unwrap();
+  }
+
+  #[test]
+  fn test_to_operation_name() {
+    assert_eq!(
+      to_operation_name(&Method::GET, "/users/{id}/posts"),
+      "getUsersByIdPosts"
+    );
+  }
+}
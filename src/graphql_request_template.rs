@@ -0,0 +1,250 @@
+#![allow(clippy::too_many_arguments)]
+
+use std::fmt::Display;
+
+use derive_setters::Setters;
+use hyper::HeaderMap;
+use reqwest::header::{HeaderName, HeaderValue};
+
+use crate::has_headers::HasHeaders;
+use crate::lambda::GraphQLOperationContext;
+use crate::mustache::Mustache;
+use crate::path_string::{PathGraphql, PathString};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphQLOperationType {
+  Query,
+  Mutation,
+}
+
+impl Display for GraphQLOperationType {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      Self::Query => "query",
+      Self::Mutation => "mutation",
+    })
+  }
+}
+
+/// GraphqlRequestTemplate is the counterpart of RequestTemplate for upstream GraphQL services.
+/// Instead of rendering a URL, it renders a GraphQL document that selects the operation
+/// with its arguments, and forwards the selection set of the field being resolved.
+#[derive(Setters, Debug, Clone)]
+pub struct GraphqlRequestTemplate {
+  pub url: String,
+  pub operation_type: GraphQLOperationType,
+  pub operation_name: String,
+  pub operation_arguments: Option<Vec<(String, Mustache)>>,
+  pub headers: Vec<(String, Mustache)>,
+}
+
+impl GraphqlRequestTemplate {
+  pub fn new(
+    url: String,
+    operation_type: GraphQLOperationType,
+    operation_name: &str,
+    operation_arguments: Option<Vec<(String, Mustache)>>,
+    headers: Vec<(String, Mustache)>,
+  ) -> Self {
+    Self { url, operation_type, operation_name: operation_name.to_string(), operation_arguments, headers }
+  }
+
+  /// Creates a HeaderMap for the context
+  fn create_headers<C: PathString>(&self, ctx: &C) -> HeaderMap {
+    let mut header_map = HeaderMap::new();
+
+    for (k, v) in &self.headers {
+      if let Ok(header_name) = HeaderName::from_bytes(k.as_bytes()) {
+        if let Ok(header_value) = HeaderValue::from_str(&v.render(ctx)) {
+          header_map.insert(header_name, header_value);
+        }
+      }
+    }
+
+    header_map
+  }
+
+  /// Sets the headers for the request
+  fn set_headers<C: PathString + HasHeaders>(&self, mut req: reqwest::Request, ctx: &C) -> reqwest::Request {
+    let headers = self.create_headers(ctx);
+    if !headers.is_empty() {
+      req.headers_mut().extend(headers);
+    }
+
+    let headers = req.headers_mut();
+    headers.insert(
+      reqwest::header::CONTENT_TYPE,
+      HeaderValue::from_static("application/json"),
+    );
+    headers.extend(ctx.headers().to_owned());
+    req
+  }
+
+  /// Renders the GraphQL document for the given context
+  pub fn render_query<C: PathGraphql + PathString + GraphQLOperationContext>(&self, ctx: &C) -> String {
+    let operation_arguments = self
+      .operation_arguments
+      .as_ref()
+      .map(|args| {
+        args
+          .iter()
+          .map(|(name, value)| format!("{}: {}", name, value.render_graphql(ctx)))
+          .collect::<Vec<_>>()
+          .join(", ")
+      })
+      .filter(|args| !args.is_empty())
+      .map(|args| format!("({})", args))
+      .unwrap_or_default();
+
+    let selection_set = ctx.selection_set().map(|set| format!(" {}", set)).unwrap_or_default();
+
+    format!(
+      "{} {{ {}{}{} }}",
+      self.operation_type, self.operation_name, operation_arguments, selection_set
+    )
+  }
+
+  /// Creates a Request for the given context
+  pub fn to_request<C: PathString + HasHeaders + PathGraphql + GraphQLOperationContext>(
+    &self,
+    ctx: &C,
+  ) -> anyhow::Result<reqwest::Request> {
+    let url = url::Url::parse(self.url.as_str())?;
+    let mut req = reqwest::Request::new(reqwest::Method::POST, url);
+    req = self.set_headers(req, ctx);
+
+    let query = self.render_query(ctx);
+    let body = serde_json::json!({ "query": query }).to_string();
+    req.body_mut().replace(body.into());
+
+    Ok(req)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::borrow::Cow;
+
+  use hyper::HeaderMap;
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  use crate::graphql_request_template::{GraphQLOperationType, GraphqlRequestTemplate};
+  use crate::has_headers::HasHeaders;
+  use crate::lambda::GraphQLOperationContext;
+  use crate::mustache::Mustache;
+  use crate::path_string::{PathGraphql, PathString};
+
+  struct Context {
+    value: serde_json::Value,
+    headers: HeaderMap,
+  }
+
+  impl Default for Context {
+    fn default() -> Self {
+      Self { value: serde_json::Value::Null, headers: HeaderMap::new() }
+    }
+  }
+
+  impl PathString for Context {
+    fn path_string<T: AsRef<str>>(&self, parts: &[T]) -> Option<Cow<'_, str>> {
+      self.value.path_string(parts)
+    }
+  }
+
+  impl PathGraphql for Context {
+    fn path_graphql<T: AsRef<str>>(&self, parts: &[T]) -> Option<String> {
+      let value = async_graphql::Value::from_json(self.value.clone()).ok()?;
+      let mut value = &value;
+      for part in parts {
+        value = match value {
+          async_graphql::Value::Object(map) => map.get(part.as_ref())?,
+          _ => return None,
+        };
+      }
+      Some(value.to_string())
+    }
+  }
+
+  impl HasHeaders for Context {
+    fn headers(&self) -> &HeaderMap {
+      &self.headers
+    }
+  }
+
+  impl GraphQLOperationContext for Context {
+    fn selection_set(&self) -> Option<String> {
+      Some("{ id name }".to_string())
+    }
+  }
+
+  #[test]
+  fn test_query_without_args() {
+    let tmpl = GraphqlRequestTemplate::new(
+      "http://localhost:3000/graphql".to_string(),
+      GraphQLOperationType::Query,
+      "users",
+      None,
+      vec![],
+    );
+    let ctx = Context::default();
+    assert_eq!(tmpl.render_query(&ctx), "query { users { id name } }");
+  }
+
+  #[test]
+  fn test_query_with_args() {
+    let tmpl = GraphqlRequestTemplate::new(
+      "http://localhost:3000/graphql".to_string(),
+      GraphQLOperationType::Query,
+      "user",
+      Some(vec![
+        ("id".to_string(), Mustache::parse("{{args.id}}").unwrap()),
+        ("name".to_string(), Mustache::parse("{{args.name}}").unwrap()),
+      ]),
+      vec![],
+    );
+    let ctx = Context { value: json!({"args": {"id": 1, "name": "foo"}}), ..Default::default() };
+    assert_eq!(
+      tmpl.render_query(&ctx),
+      r#"query { user(id: 1, name: "foo") { id name } }"#
+    );
+  }
+
+  #[test]
+  fn test_mutation() {
+    let tmpl = GraphqlRequestTemplate::new(
+      "http://localhost:3000/graphql".to_string(),
+      GraphQLOperationType::Mutation,
+      "createUser",
+      Some(vec![("user".to_string(), Mustache::parse("{{args.user}}").unwrap())]),
+      vec![],
+    );
+    let ctx = Context { value: json!({"args": {"user": {"name": "foo"}}}), ..Default::default() };
+    assert_eq!(
+      tmpl.render_query(&ctx),
+      r#"mutation { createUser(user: {name: "foo"}) { id name } }"#
+    );
+  }
+
+  #[test]
+  fn test_to_request() {
+    let tmpl = GraphqlRequestTemplate::new(
+      "http://localhost:3000/graphql".to_string(),
+      GraphQLOperationType::Query,
+      "users",
+      None,
+      vec![("x-token".to_string(), Mustache::parse("{{args.token}}").unwrap())],
+    );
+    let ctx = Context { value: json!({"args": {"token": "abc"}}), ..Default::default() };
+    let req = tmpl.to_request(&ctx).unwrap();
+    let body = req.body().unwrap().as_bytes().unwrap().to_owned();
+
+    assert_eq!(req.method(), reqwest::Method::POST);
+    assert_eq!(req.url().to_string(), "http://localhost:3000/graphql");
+    assert_eq!(req.headers().get("x-token").unwrap(), "abc");
+    assert_eq!(
+      serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+      json!({"query": "query { users { id name } }"})
+    );
+  }
+}
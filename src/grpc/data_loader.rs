@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_graphql::async_trait;
+use async_graphql::dataloader::{DataLoader, Loader, NoCache};
+use async_graphql::futures_util::future::join_all;
+use async_graphql_value::ConstValue;
+use derive_setters::Setters;
+use prost::Message;
+use prost_reflect::DynamicMessage;
+
+use super::protobuf::{encode_message, ProtobufOperation};
+use super::request::execute_grpc_request;
+use crate::config::group_by::GroupBy;
+use crate::config::Batch;
+use crate::http::{DataLoaderRequest, HttpClient, Response};
+use crate::json::JsonLike;
+
+#[derive(Setters, Clone, Debug)]
+pub struct GrpcDataLoader<C>
+where
+  C: HttpClient + Send + Sync + 'static + Clone,
+{
+  pub client: C,
+  pub operation: ProtobufOperation,
+  pub group_by: Option<GroupBy>,
+  pub list: bool,
+}
+
+impl<C: HttpClient + Send + Sync + 'static + Clone> GrpcDataLoader<C> {
+  pub fn new(client: C, operation: ProtobufOperation, group_by: Option<GroupBy>) -> Self {
+    GrpcDataLoader { client, operation, group_by, list: false }
+  }
+
+  pub fn to_data_loader(self, batch: Batch) -> DataLoader<GrpcDataLoader<C>, NoCache> {
+    DataLoader::new(self, tokio::spawn)
+      .delay(Duration::from_millis(batch.delay as u64))
+      .max_batch_size(batch.max_size)
+  }
+
+  /// Merges all the requests into a single call.
+  /// Protobuf concatenates repeated fields when messages are merged,
+  /// so the batched input asks for the ids of every request at once.
+  /// List fields get every match of their ids, other fields only the first one.
+  #[allow(clippy::mutable_key_type)]
+  async fn load_batched(
+    &self,
+    group_by: &GroupBy,
+    keys: &[DataLoaderRequest],
+  ) -> Result<HashMap<DataLoaderRequest, Response>> {
+    let mut merged = DynamicMessage::new(self.operation.input_type.clone());
+    let mut ids = Vec::with_capacity(keys.len());
+    for key in keys {
+      let body = key
+        .to_request()
+        .body()
+        .and_then(|body| body.as_bytes())
+        .unwrap_or_default()
+        .to_vec();
+      let input = self.operation.decode_input(&body)?;
+      ids.push(self.operation.find_ids(&input)?);
+      merged.merge(input.encode_to_vec().as_slice())?;
+    }
+
+    let mut request = keys[0].to_request();
+    request.body_mut().replace(encode_message(&merged).into());
+    let res = execute_grpc_request(&self.client, &self.operation, request).await?;
+
+    let path = group_by.path();
+    let body_value = res.body.group_by(&path);
+    let mut hashmap = HashMap::with_capacity(keys.len());
+    for (key, ids) in keys.iter().zip(ids) {
+      let mut values = ids.iter().map(|id| {
+        body_value
+          .get(id)
+          .and_then(|values| values.first().cloned().cloned())
+          .unwrap_or(ConstValue::Null)
+      });
+      let value = if self.list {
+        ConstValue::List(values.collect())
+      } else {
+        values.next().unwrap_or(ConstValue::Null)
+      };
+      hashmap.insert(key.clone(), res.clone().body(value));
+    }
+
+    Ok(hashmap)
+  }
+}
+
+#[async_trait::async_trait]
+impl<C: HttpClient + Send + Sync + 'static + Clone> Loader<DataLoaderRequest> for GrpcDataLoader<C> {
+  type Value = Response;
+  type Error = Arc<anyhow::Error>;
+
+  async fn load(
+    &self,
+    keys: &[DataLoaderRequest],
+  ) -> async_graphql::Result<HashMap<DataLoaderRequest, Self::Value>, Self::Error> {
+    if let Some(group_by) = &self.group_by {
+      Ok(self.load_batched(group_by, keys).await?)
+    } else {
+      let results = keys.iter().map(|key| async {
+        let result = execute_grpc_request(&self.client, &self.operation, key.to_request()).await;
+        (key.clone(), result)
+      });
+
+      let results = join_all(results).await;
+
+      #[allow(clippy::mutable_key_type)]
+      let mut hashmap = HashMap::new();
+      for (key, value) in results {
+        hashmap.insert(key, value?);
+      }
+
+      Ok(hashmap)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::BTreeSet;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  use hyper::header::{HeaderValue, CONTENT_TYPE};
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  use super::*;
+  use crate::grpc::ProtobufSet;
+
+  #[derive(Clone)]
+  struct MockHttpClient {
+    operation: ProtobufOperation,
+    request_count: Arc<AtomicUsize>,
+  }
+
+  #[async_trait::async_trait]
+  impl HttpClient for MockHttpClient {
+    async fn execute(&self, req: reqwest::Request) -> Result<Response> {
+      self.request_count.fetch_add(1, Ordering::SeqCst);
+      let body = req.body().and_then(|body| body.as_bytes()).unwrap_or_default();
+      let ids = self.operation.find_ids(&self.operation.decode_input(body)?)?;
+      let news = ids
+        .iter()
+        .map(|id| json!({"id": id.parse::<i32>().unwrap(), "title": format!("Note {}", id)}))
+        .collect::<Vec<_>>();
+
+      let output = DynamicMessage::deserialize(self.operation.output_type.clone(), json!({ "news": news }))?;
+
+      let mut headers = reqwest::header::HeaderMap::new();
+      headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/grpc"));
+      Ok(
+        Response::default()
+          .headers(headers)
+          .body(ConstValue::Binary(encode_message(&output).into())),
+      )
+    }
+  }
+
+  fn create_request(operation: &ProtobufOperation, input: &str) -> DataLoaderRequest {
+    let mut req = reqwest::Request::new(
+      reqwest::Method::POST,
+      "http://localhost:50051/news.NewsService/GetMultipleNews"
+        .parse()
+        .unwrap(),
+    );
+    req.body_mut().replace(operation.convert_input(input).unwrap().into());
+    DataLoaderRequest::new(req, BTreeSet::new())
+  }
+
+  #[tokio::test]
+  async fn test_batched_load() {
+    let operation = ProtobufSet::from_proto_file("tests/grpc/news.proto")
+      .unwrap()
+      .find_operation("news.NewsService", "GetMultipleNews")
+      .unwrap();
+    let client = MockHttpClient { operation: operation.clone(), request_count: Arc::new(AtomicUsize::new(0)) };
+    let group_by = GroupBy::new(vec!["news".to_string(), "id".to_string()]);
+    let loader =
+      GrpcDataLoader::new(client.clone(), operation.clone(), Some(group_by)).to_data_loader(Batch::default().delay(1));
+
+    let key1 = create_request(&operation, r#"{"ids": [1]}"#);
+    let key2 = create_request(&operation, r#"{"ids": [2]}"#);
+    let results = join_all([loader.load_one(key1), loader.load_one(key2)]).await;
+    let bodies = results
+      .into_iter()
+      .map(|result| result.unwrap().unwrap().body)
+      .collect::<Vec<_>>();
+
+    assert_eq!(
+      client.request_count.load(Ordering::SeqCst),
+      1,
+      "Only one batched request should be made"
+    );
+    assert_eq!(
+      bodies,
+      vec![
+        ConstValue::from_json(json!({"id": 1, "title": "Note 1", "body": "", "postImage": ""})).unwrap(),
+        ConstValue::from_json(json!({"id": 2, "title": "Note 2", "body": "", "postImage": ""})).unwrap(),
+      ]
+    );
+  }
+  #[tokio::test]
+  async fn test_batched_load_list() {
+    let operation = ProtobufSet::from_proto_file("tests/grpc/news.proto")
+      .unwrap()
+      .find_operation("news.NewsService", "GetMultipleNews")
+      .unwrap();
+    let client = MockHttpClient { operation: operation.clone(), request_count: Arc::new(AtomicUsize::new(0)) };
+    let group_by = GroupBy::new(vec!["news".to_string(), "id".to_string()]);
+    let loader = GrpcDataLoader::new(client, operation.clone(), Some(group_by))
+      .list(true)
+      .to_data_loader(Batch::default().delay(1));
+
+    let key = create_request(&operation, r#"{"ids": [1]}"#);
+    let body = loader.load_one(key).await.unwrap().unwrap().body;
+
+    assert_eq!(
+      body,
+      ConstValue::from_json(json!([{"id": 1, "title": "Note 1", "body": "", "postImage": ""}])).unwrap()
+    );
+  }
+}
@@ -0,0 +1,9 @@
+mod data_loader;
+mod protobuf;
+mod request;
+mod request_template;
+
+pub use data_loader::GrpcDataLoader;
+pub use protobuf::{ProtobufOperation, ProtobufSet};
+pub use request::execute_grpc_request;
+pub use request_template::GrpcRequestTemplate;
@@ -0,0 +1,247 @@
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_graphql_value::ConstValue;
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, FieldDescriptor, Kind, MessageDescriptor, SerializeOptions};
+
+/// Size of the prefix gRPC puts in front of every message:
+/// a compression flag followed by the message length as a big endian u32.
+const HEADER_LEN: usize = 5;
+
+/// ProtobufSet holds all the definitions that were loaded from a `.proto` file.
+#[derive(Debug, Clone)]
+pub struct ProtobufSet {
+  pool: DescriptorPool,
+}
+
+impl ProtobufSet {
+  /// Compiles the `.proto` file at the given path.
+  /// Imports are resolved relative to the directory that contains the file.
+  pub fn from_proto_file(path: &str) -> Result<Self> {
+    let path = Path::new(path);
+    let include = path
+      .parent()
+      .filter(|dir| !dir.as_os_str().is_empty())
+      .unwrap_or(Path::new("."));
+    let pool = protox::Compiler::new([include])?
+      .include_imports(true)
+      .open_file(path)?
+      .descriptor_pool();
+
+    Ok(Self { pool })
+  }
+
+  /// Finds the unary `method` of the fully qualified `service`.
+  pub fn find_operation(&self, service: &str, method: &str) -> Result<ProtobufOperation> {
+    let service_descriptor = self
+      .pool
+      .get_service_by_name(service)
+      .with_context(|| format!("Couldn't find definitions for service '{service}'"))?;
+    let method_descriptor = service_descriptor
+      .methods()
+      .find(|descriptor| descriptor.name() == method)
+      .with_context(|| format!("Couldn't find method '{method}' in service '{service}'"))?;
+
+    if method_descriptor.is_client_streaming() || method_descriptor.is_server_streaming() {
+      bail!("Method '{method}' is a streaming method, only unary methods are supported");
+    }
+
+    Ok(ProtobufOperation {
+      path: format!("/{}/{}", service_descriptor.full_name(), method_descriptor.name()),
+      input_type: method_descriptor.input(),
+      output_type: method_descriptor.output(),
+    })
+  }
+}
+
+/// ProtobufOperation knows how to convert the input and the output of a single gRPC method.
+#[derive(Debug, Clone)]
+pub struct ProtobufOperation {
+  /// Path of the method on the upstream eg. `/news.NewsService/GetNews`
+  pub path: String,
+  pub input_type: MessageDescriptor,
+  pub output_type: MessageDescriptor,
+}
+
+impl ProtobufOperation {
+  /// Converts a JSON encoded input into a gRPC framed protobuf message.
+  pub fn convert_input(&self, input: &str) -> Result<Vec<u8>> {
+    let mut deserializer = serde_json::Deserializer::from_str(input);
+    let message = DynamicMessage::deserialize(self.input_type.clone(), &mut deserializer).with_context(|| {
+      format!(
+        "Failed to parse input according to type {}",
+        self.input_type.full_name()
+      )
+    })?;
+    deserializer.end()?;
+
+    Ok(encode_message(&message))
+  }
+
+  /// Decodes a gRPC framed protobuf message back into an input message.
+  pub fn decode_input(&self, bytes: &[u8]) -> Result<DynamicMessage> {
+    Ok(DynamicMessage::decode(self.input_type.clone(), strip_header(bytes)?)?)
+  }
+
+  /// Converts a gRPC framed protobuf message into a GraphQL value.
+  pub fn convert_output(&self, bytes: &[u8]) -> Result<ConstValue> {
+    let message = DynamicMessage::decode(self.output_type.clone(), strip_header(bytes)?)
+      .with_context(|| format!("Failed to decode response as {}", self.output_type.full_name()))?;
+    let options = SerializeOptions::new()
+      .stringify_64_bit_integers(false)
+      .skip_default_fields(false);
+    let json = message.serialize_with_options(serde_json::value::Serializer, &options)?;
+
+    Ok(ConstValue::from_json(json)?)
+  }
+
+  /// Returns the only repeated scalar field of the input message.
+  /// Requests for such methods can be merged together by concatenating that field.
+  pub fn batch_field(&self) -> Option<FieldDescriptor> {
+    let mut fields = self
+      .input_type
+      .fields()
+      .filter(|field| field.is_list() && !matches!(field.kind(), Kind::Message(_)));
+
+    match (fields.next(), fields.next()) {
+      (Some(field), None) => Some(field),
+      _ => None,
+    }
+  }
+
+  /// Returns the ids that were requested through the batch field of the given input.
+  pub fn find_ids(&self, input: &DynamicMessage) -> Result<Vec<String>> {
+    let field = self
+      .batch_field()
+      .ok_or_else(|| anyhow!("{} has no repeated field to batch on", self.input_type.full_name()))?;
+    let ids = input
+      .get_field(&field)
+      .as_list()
+      .unwrap_or_default()
+      .iter()
+      .filter_map(|value| match value {
+        prost_reflect::Value::String(value) => Some(value.clone()),
+        prost_reflect::Value::I32(value) => Some(value.to_string()),
+        prost_reflect::Value::I64(value) => Some(value.to_string()),
+        prost_reflect::Value::U32(value) => Some(value.to_string()),
+        prost_reflect::Value::U64(value) => Some(value.to_string()),
+        _ => None,
+      })
+      .collect();
+
+    Ok(ids)
+  }
+}
+
+/// Encodes a message with the gRPC framing.
+pub fn encode_message(message: &DynamicMessage) -> Vec<u8> {
+  let message = message.encode_to_vec();
+  let mut bytes = Vec::with_capacity(HEADER_LEN + message.len());
+  bytes.push(0);
+  bytes.extend_from_slice(&(message.len() as u32).to_be_bytes());
+  bytes.extend(message);
+  bytes
+}
+
+fn strip_header(bytes: &[u8]) -> Result<&[u8]> {
+  if bytes.len() < HEADER_LEN {
+    bail!(
+      "Invalid gRPC message: expected at least {HEADER_LEN} bytes, got {}",
+      bytes.len()
+    );
+  }
+  if bytes[0] != 0 {
+    bail!("Compressed gRPC messages are not supported");
+  }
+
+  Ok(&bytes[HEADER_LEN..])
+}
+
+#[cfg(test)]
+mod tests {
+  use async_graphql_value::ConstValue;
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  use super::*;
+
+  const NEWS_PROTO: &str = "tests/grpc/news.proto";
+
+  fn get_operation(method: &str) -> ProtobufOperation {
+    ProtobufSet::from_proto_file(NEWS_PROTO)
+      .unwrap()
+      .find_operation("news.NewsService", method)
+      .unwrap()
+  }
+
+  #[test]
+  fn test_unknown_service() {
+    let error = ProtobufSet::from_proto_file(NEWS_PROTO)
+      .unwrap()
+      .find_operation("news.UnknownService", "GetNews")
+      .unwrap_err();
+    assert_eq!(
+      error.to_string(),
+      "Couldn't find definitions for service 'news.UnknownService'"
+    );
+  }
+
+  #[test]
+  fn test_unknown_method() {
+    let error = ProtobufSet::from_proto_file(NEWS_PROTO)
+      .unwrap()
+      .find_operation("news.NewsService", "GetUnknown")
+      .unwrap_err();
+    assert_eq!(
+      error.to_string(),
+      "Couldn't find method 'GetUnknown' in service 'news.NewsService'"
+    );
+  }
+
+  #[test]
+  fn test_operation_path() {
+    assert_eq!(get_operation("GetNews").path, "/news.NewsService/GetNews");
+  }
+
+  #[test]
+  fn test_convert_input() {
+    let operation = get_operation("GetNews");
+    let bytes = operation.convert_input(r#"{"id": 1}"#).unwrap();
+    assert_eq!(bytes, [0, 0, 0, 0, 2, 8, 1]);
+  }
+
+  #[test]
+  fn test_convert_input_invalid() {
+    let operation = get_operation("GetNews");
+    assert!(operation.convert_input(r#"{"unknown": 1}"#).is_err());
+  }
+
+  #[test]
+  fn test_convert_output() {
+    let operation = get_operation("GetNews");
+    let mut message = DynamicMessage::new(operation.output_type.clone());
+    message.set_field_by_name("id", prost_reflect::Value::I32(1));
+    message.set_field_by_name("title", prost_reflect::Value::String("Note 1".to_string()));
+
+    let output = operation.convert_output(&encode_message(&message)).unwrap();
+    assert_eq!(
+      output,
+      ConstValue::from_json(json!({"id": 1, "title": "Note 1", "body": "", "postImage": ""})).unwrap()
+    );
+  }
+
+  #[test]
+  fn test_find_ids() {
+    let operation = get_operation("GetMultipleNews");
+    let bytes = operation.convert_input(r#"{"ids": [1, 2]}"#).unwrap();
+    let input = operation.decode_input(&bytes).unwrap();
+    assert_eq!(operation.find_ids(&input).unwrap(), vec!["1", "2"]);
+  }
+
+  #[test]
+  fn test_batch_field() {
+    assert_eq!(get_operation("GetMultipleNews").batch_field().unwrap().name(), "ids");
+    assert!(get_operation("GetNews").batch_field().is_none());
+  }
+}
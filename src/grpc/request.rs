@@ -0,0 +1,43 @@
+use anyhow::{bail, Result};
+use async_graphql_value::ConstValue;
+
+use super::protobuf::ProtobufOperation;
+use crate::http::{HttpClient, Response};
+
+/// Executes a gRPC request and decodes the protobuf response of the operation.
+pub async fn execute_grpc_request<C: HttpClient>(
+  client: &C,
+  operation: &ProtobufOperation,
+  request: reqwest::Request,
+) -> Result<Response> {
+  let response = client.execute(request).await?;
+
+  // Failures are sent as "trailers only" responses so the status can be read from the headers
+  if let Some(status) = response.headers.get("grpc-status") {
+    if status != "0" {
+      let message = response
+        .headers
+        .get("grpc-message")
+        .and_then(|message| message.to_str().ok())
+        .map(|message| {
+          percent_encoding::percent_decode_str(message)
+            .decode_utf8_lossy()
+            .to_string()
+        })
+        .unwrap_or_default();
+      bail!("gRPC error: status {} {}", status.to_str()?, message);
+    }
+  }
+
+  if !response.status.is_success() {
+    bail!("gRPC error: upstream responded with {}", response.status);
+  }
+
+  match &response.body {
+    ConstValue::Binary(bytes) => {
+      let body = operation.convert_output(bytes)?;
+      Ok(response.body(body))
+    }
+    _ => bail!("gRPC error: upstream didn't respond with a gRPC message"),
+  }
+}
@@ -0,0 +1,138 @@
+use derive_setters::Setters;
+use hyper::HeaderMap;
+use reqwest::header::{HeaderName, HeaderValue};
+
+use super::protobuf::ProtobufOperation;
+use crate::has_headers::HasHeaders;
+use crate::mustache::Mustache;
+use crate::path_string::PathString;
+
+/// GrpcRequestTemplate renders the body of a gRPC call as JSON
+/// and converts it into the protobuf input message of the operation.
+#[derive(Setters, Debug, Clone)]
+pub struct GrpcRequestTemplate {
+  pub url: String,
+  pub headers: Vec<(String, Mustache)>,
+  pub body: Option<Mustache>,
+  pub operation: ProtobufOperation,
+  /// Whether the field resolves to a list, which decides the shape of batched responses.
+  pub list: bool,
+}
+
+impl GrpcRequestTemplate {
+  pub fn new(url: String, operation: ProtobufOperation) -> Self {
+    Self { url, headers: Vec::new(), body: None, operation, list: false }
+  }
+
+  /// Creates a HeaderMap for the context
+  fn create_headers<C: PathString>(&self, ctx: &C) -> HeaderMap {
+    let mut header_map = HeaderMap::new();
+
+    for (k, v) in &self.headers {
+      if let Ok(header_name) = HeaderName::from_bytes(k.as_bytes()) {
+        if let Ok(header_value) = HeaderValue::from_str(&v.render(ctx)) {
+          header_map.insert(header_name, header_value);
+        }
+      }
+    }
+
+    header_map
+  }
+
+  /// Sets the headers for the request
+  fn set_headers<C: PathString + HasHeaders>(&self, mut req: reqwest::Request, ctx: &C) -> reqwest::Request {
+    let headers = req.headers_mut();
+    headers.extend(ctx.headers().to_owned());
+    headers.extend(self.create_headers(ctx));
+    headers.insert(
+      reqwest::header::CONTENT_TYPE,
+      HeaderValue::from_static("application/grpc"),
+    );
+    headers.insert(reqwest::header::TE, HeaderValue::from_static("trailers"));
+    req
+  }
+
+  /// Creates a Request for the given context
+  pub fn to_request<C: PathString + HasHeaders>(&self, ctx: &C) -> anyhow::Result<reqwest::Request> {
+    let url = url::Url::parse(self.url.as_str())?;
+    let mut req = reqwest::Request::new(reqwest::Method::POST, url);
+    req = self.set_headers(req, ctx);
+
+    let input = self
+      .body
+      .as_ref()
+      .map(|body| body.render(ctx))
+      .unwrap_or_else(|| "{}".to_string());
+    let body = self.operation.convert_input(&input)?;
+    req.body_mut().replace(body.into());
+
+    Ok(req)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::borrow::Cow;
+
+  use hyper::HeaderMap;
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  use super::GrpcRequestTemplate;
+  use crate::grpc::ProtobufSet;
+  use crate::has_headers::HasHeaders;
+  use crate::mustache::Mustache;
+  use crate::path_string::PathString;
+
+  struct Context {
+    value: serde_json::Value,
+    headers: HeaderMap,
+  }
+
+  impl PathString for Context {
+    fn path_string<T: AsRef<str>>(&self, parts: &[T]) -> Option<Cow<'_, str>> {
+      self.value.path_string(parts)
+    }
+  }
+
+  impl HasHeaders for Context {
+    fn headers(&self) -> &HeaderMap {
+      &self.headers
+    }
+  }
+
+  fn get_template(method: &str) -> GrpcRequestTemplate {
+    let operation = ProtobufSet::from_proto_file("tests/grpc/news.proto")
+      .unwrap()
+      .find_operation("news.NewsService", method)
+      .unwrap();
+    GrpcRequestTemplate::new(format!("http://localhost:50051{}", operation.path), operation)
+  }
+
+  #[test]
+  fn test_to_request() {
+    let tmpl = get_template("GetNews")
+      .body(Some(Mustache::parse(r#"{"id": {{args.id}}}"#).unwrap()))
+      .headers(vec![(
+        "x-token".to_string(),
+        Mustache::parse("{{args.token}}").unwrap(),
+      )]);
+    let ctx = Context { value: json!({"args": {"id": 2, "token": "abc"}}), headers: HeaderMap::new() };
+    let req = tmpl.to_request(&ctx).unwrap();
+
+    assert_eq!(req.method(), reqwest::Method::POST);
+    assert_eq!(req.url().to_string(), "http://localhost:50051/news.NewsService/GetNews");
+    assert_eq!(req.headers().get("content-type").unwrap(), "application/grpc");
+    assert_eq!(req.headers().get("x-token").unwrap(), "abc");
+    assert_eq!(req.body().unwrap().as_bytes().unwrap(), [0, 0, 0, 0, 2, 8, 2]);
+  }
+
+  #[test]
+  fn test_to_request_without_body() {
+    let tmpl = get_template("GetAllNews");
+    let ctx = Context { value: json!({}), headers: HeaderMap::new() };
+    let req = tmpl.to_request(&ctx).unwrap();
+
+    assert_eq!(req.body().unwrap().as_bytes().unwrap(), [0, 0, 0, 0, 0]);
+  }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use task_local_extensions::Extensions;
+
+use super::host_and_port;
+use crate::config;
+
+/// Middleware that fails requests to a host without sending them, once the host has failed too many times in a row.
+/// After `open` has elapsed, requests are let through again: a success closes the circuit,
+/// while a failure opens it for another `open` period.
+pub struct CircuitBreaker {
+  failure_threshold: usize,
+  open: Duration,
+  hosts: Mutex<HashMap<String, HostState>>,
+}
+
+#[derive(Default)]
+struct HostState {
+  failures: usize,
+  opened_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+  pub fn new(circuit_breaker: config::CircuitBreaker) -> Self {
+    CircuitBreaker {
+      failure_threshold: circuit_breaker.failure_threshold,
+      open: Duration::from_millis(circuit_breaker.open_ms),
+      hosts: Mutex::new(HashMap::new()),
+    }
+  }
+
+  fn is_open(&self, host: &str) -> bool {
+    let hosts = self.hosts.lock().unwrap();
+    hosts
+      .get(host)
+      .and_then(|state| state.opened_at)
+      .is_some_and(|opened_at| opened_at.elapsed() < self.open)
+  }
+
+  fn record(&self, host: String, success: bool) {
+    let mut hosts = self.hosts.lock().unwrap();
+    if success {
+      hosts.remove(&host);
+      return;
+    }
+
+    let state = hosts.entry(host).or_default();
+    state.failures += 1;
+    if state.failures >= self.failure_threshold {
+      state.opened_at = Some(Instant::now());
+    }
+  }
+}
+
+#[async_trait::async_trait]
+impl Middleware for CircuitBreaker {
+  async fn handle(
+    &self,
+    request: Request,
+    extensions: &mut Extensions,
+    next: Next<'_>,
+  ) -> reqwest_middleware::Result<Response> {
+    let host = host_and_port(request.url());
+    if self.is_open(&host) {
+      return Err(reqwest_middleware::Error::Middleware(anyhow::anyhow!(
+        "Circuit breaker is open for {host}"
+      )));
+    }
+
+    let result = next.run(request, extensions).await;
+    let success = result
+      .as_ref()
+      .is_ok_and(|response| !response.status().is_server_error());
+    self.record(host, success);
+    result
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_opens_after_threshold() {
+    let breaker = CircuitBreaker::new(config::CircuitBreaker { failure_threshold: 2, open_ms: 60000 });
+    let host = "localhost:80";
+
+    breaker.record(host.to_string(), false);
+    assert!(!breaker.is_open(host));
+    breaker.record(host.to_string(), false);
+    assert!(breaker.is_open(host));
+    assert!(!breaker.is_open("example.com:443"));
+  }
+
+  #[test]
+  fn test_success_resets_failures() {
+    let breaker = CircuitBreaker::new(config::CircuitBreaker { failure_threshold: 2, open_ms: 60000 });
+    let host = "localhost:80";
+
+    breaker.record(host.to_string(), false);
+    breaker.record(host.to_string(), true);
+    breaker.record(host.to_string(), false);
+    assert!(!breaker.is_open(host));
+  }
+
+  #[test]
+  fn test_closes_after_open_period() {
+    let breaker = CircuitBreaker::new(config::CircuitBreaker { failure_threshold: 1, open_ms: 0 });
+    let host = "localhost:80";
+
+    breaker.record(host.to_string(), false);
+    assert!(!breaker.is_open(host));
+  }
+}
@@ -1,11 +1,18 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 use std::time::Duration;
 
 use http_cache_reqwest::{Cache, CacheMode, HttpCache, HttpCacheOptions, MokaManager};
 use reqwest::Client;
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use task_local_extensions::Extensions;
 
-use super::Response;
-use crate::config::{self, Upstream};
+use super::circuit_breaker::CircuitBreaker;
+use super::retry::{Idempotent, Retry};
+use super::upstream_metrics::UpstreamMetrics;
+use super::upstream_tracing::UpstreamTracing;
+use super::{client_tls, host_and_port, Response};
+use crate::config::{self, Upstream, UpstreamTls};
 
 #[async_trait::async_trait]
 pub trait HttpClient {
@@ -23,21 +30,84 @@ impl HttpClient for DefaultHttpClient {
 #[derive(Clone)]
 pub struct DefaultHttpClient {
   client: ClientWithMiddleware,
+  stream_client: Client,
+  upstream: Upstream,
+  http2_only: bool,
+  /// Clients of the hosts whose TLS settings override the ones of the upstream, by `host:port`.
+  hosts: Arc<HashMap<String, DefaultHttpClient>>,
 }
 
 impl Default for DefaultHttpClient {
   fn default() -> Self {
     let upstream = config::Upstream::default();
     //TODO: default is used only in tests. Drop default and move it to test.
-    DefaultHttpClient::new(upstream)
+    DefaultHttpClient::new(upstream).expect("Failed to build client")
   }
 }
 
 impl DefaultHttpClient {
-  pub fn new(upstream: Upstream) -> Self {
+  pub fn new(upstream: Upstream) -> anyhow::Result<Self> {
+    Self::build(upstream, false)
+  }
+
+  /// Creates a client that only speaks HTTP/2, which is required to talk to gRPC upstreams.
+  pub fn with_http2_only(upstream: Upstream) -> anyhow::Result<Self> {
+    Self::build(upstream, true)
+  }
+
+  pub fn upstream(&self) -> &Upstream {
+    &self.upstream
+  }
+
+  /// Overrides the TLS settings of the upstream for the requests to the hosts, that are identified as `host:port`.
+  pub fn tls_hosts(mut self, tls_hosts: &BTreeMap<String, UpstreamTls>) -> anyhow::Result<Self> {
+    let hosts = tls_hosts
+      .iter()
+      .map(|(host, tls)| {
+        let upstream = self.upstream.clone().tls(Some(tls.clone()));
+        Ok((host.clone(), Self::build(upstream, self.http2_only)?))
+      })
+      .collect::<anyhow::Result<_>>()?;
+    self.hosts = Arc::new(hosts);
+    Ok(self)
+  }
+
+  /// Fails when the TLS files of the upstream can't be read,
+  /// which can happen after validation when they change before a reload.
+  fn build(upstream: Upstream, http2_only: bool) -> anyhow::Result<Self> {
+    let builder = Self::client_builder(&upstream, http2_only)?.timeout(Duration::from_secs(upstream.get_timeout()));
+    let mut client = ClientBuilder::new(builder.build()?);
+
+    if upstream.get_enable_http_cache() {
+      client = client.with(Cache(HttpCache {
+        mode: CacheMode::Default,
+        manager: MokaManager::default(),
+        options: HttpCacheOptions::default(),
+      }))
+    }
+
+    // Retries go through the circuit breaker, so that every attempt counts as a failure of the host
+    if let Some(retry) = upstream.retry.clone() {
+      client = client.with(Retry::new(retry));
+    }
+
+    if let Some(circuit_breaker) = upstream.circuit_breaker.clone() {
+      client = client.with(CircuitBreaker::new(circuit_breaker));
+    }
+
+    // Every attempt that reaches the upstream is measured, unlike cache hits and requests rejected by the circuit breaker
+    client = client.with(UpstreamMetrics).with(UpstreamTracing);
+
+    // Streams stay open for as long as the upstream keeps sending events,
+    // so they are not bound by the request timeout.
+    let stream_client = Self::client_builder(&upstream, http2_only)?.build()?;
+
+    Ok(DefaultHttpClient { client: client.build(), stream_client, upstream, http2_only, hosts: Default::default() })
+  }
+
+  fn client_builder(upstream: &Upstream, http2_only: bool) -> anyhow::Result<reqwest::ClientBuilder> {
     let mut builder = Client::builder()
       .tcp_keepalive(Some(Duration::from_secs(upstream.get_tcp_keep_alive())))
-      .timeout(Duration::from_secs(upstream.get_timeout()))
       .connect_timeout(Duration::from_secs(upstream.get_connect_timeout()))
       .http2_keep_alive_interval(Some(Duration::from_secs(upstream.get_keep_alive_interval())))
       .http2_keep_alive_timeout(Duration::from_secs(upstream.get_keep_alive_timeout()))
@@ -46,27 +116,59 @@ impl DefaultHttpClient {
       .pool_max_idle_per_host(upstream.get_pool_max_idle_per_host())
       .user_agent(upstream.get_user_agent());
 
+    if http2_only {
+      builder = builder.http2_prior_knowledge();
+    }
+
     if let Some(ref proxy) = upstream.proxy {
       builder = builder.proxy(reqwest::Proxy::http(proxy.url.clone()).expect("Failed to set proxy in http client"));
     }
 
-    let mut client = ClientBuilder::new(builder.build().expect("Failed to build client"));
-
-    if upstream.get_enable_http_cache() {
-      client = client.with(Cache(HttpCache {
-        mode: CacheMode::Default,
-        manager: MokaManager::default(),
-        options: HttpCacheOptions::default(),
-      }))
+    if let Some(ref tls) = upstream.tls {
+      builder = client_tls(builder, tls)?;
     }
 
-    DefaultHttpClient { client: client.build() }
+    Ok(builder)
+  }
+
+  /// The client for the host of the URL.
+  fn for_url(&self, url: &reqwest::Url) -> &Self {
+    if self.hosts.is_empty() {
+      return self;
+    }
+    self.hosts.get(&host_and_port(url)).unwrap_or(self)
   }
 
   pub async fn execute(&self, request: reqwest::Request) -> reqwest_middleware::Result<Response> {
     log::info!("{} {} ", request.method(), request.url());
-    let response = self.client.execute(request).await?;
+    let response = self.for_url(request.url()).client.execute(request).await?;
     let response = Response::from_response(response).await?;
     Ok(response)
   }
+
+  /// Executes a request that can be retried, even though its method isn't idempotent.
+  pub async fn execute_idempotent(&self, request: reqwest::Request) -> reqwest_middleware::Result<Response> {
+    log::info!("{} {} ", request.method(), request.url());
+    let mut extensions = Extensions::new();
+    extensions.insert(Idempotent);
+    let response = self
+      .for_url(request.url())
+      .client
+      .execute_with_extensions(request, &mut extensions)
+      .await?;
+    let response = Response::from_response(response).await?;
+    Ok(response)
+  }
+
+  /// Executes a request whose response body is consumed as a stream.
+  /// Such responses bypass the HTTP cache.
+  pub async fn execute_stream(&self, request: reqwest::Request) -> reqwest::Result<reqwest::Response> {
+    log::info!("{} {} ", request.method(), request.url());
+    self
+      .for_url(request.url())
+      .stream_client
+      .execute(request)
+      .await?
+      .error_for_status()
+  }
 }
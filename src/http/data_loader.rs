@@ -6,11 +6,15 @@ use async_graphql::async_trait;
 use async_graphql::dataloader::{DataLoader, Loader, NoCache};
 use async_graphql::futures_util::future::join_all;
 use async_graphql_value::ConstValue;
+use opentelemetry::trace::SpanKind;
+use opentelemetry::KeyValue;
 
 use crate::config::group_by::GroupBy;
 use crate::config::Batch;
 use crate::http::{DataLoaderRequest, HttpClient, Response};
 use crate::json::JsonLike;
+use crate::metrics::metrics;
+use crate::telemetry;
 
 #[derive(Default, Clone, Debug)]
 pub struct HttpDataLoader<C>
@@ -41,6 +45,24 @@ impl<C: HttpClient + Send + Sync + 'static + Clone> Loader<DataLoaderRequest> fo
     &self,
     keys: &[DataLoaderRequest],
   ) -> async_graphql::Result<HashMap<DataLoaderRequest, Self::Value>, Self::Error> {
+    metrics().data_loader_batch_size.observe(keys.len() as f64);
+    // Batches run in their own task, so they continue the trace of the first request of the batch
+    let parent = keys.first().map(|key| key.context().clone()).unwrap_or_default();
+    let cx = telemetry::start_span(
+      &parent,
+      "HttpDataLoader.load",
+      SpanKind::Internal,
+      vec![KeyValue::new("batch.size", keys.len() as i64)],
+    );
+    telemetry::in_span(cx, self.load_batch(keys)).await
+  }
+}
+
+impl<C: HttpClient + Send + Sync + 'static + Clone> HttpDataLoader<C> {
+  async fn load_batch(
+    &self,
+    keys: &[DataLoaderRequest],
+  ) -> Result<HashMap<DataLoaderRequest, Response>, Arc<anyhow::Error>> {
     if let Some(group_by) = self.batched.clone() {
       let mut keys = keys.to_vec();
       keys.sort_by(|a, b| a.to_request().url().cmp(b.to_request().url()));
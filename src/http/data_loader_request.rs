@@ -2,12 +2,16 @@ use std::collections::hash_map::DefaultHasher;
 use std::collections::BTreeSet;
 use std::hash::{Hash, Hasher};
 
+use opentelemetry::Context;
+
+/// The request of a data loader, along with the trace context it was made in.
+/// The context doesn't take part in the identity of the request.
 #[derive(Debug)]
-pub struct DataLoaderRequest(reqwest::Request, BTreeSet<String>);
+pub struct DataLoaderRequest(reqwest::Request, BTreeSet<String>, Context);
 
 impl DataLoaderRequest {
   pub fn new(req: reqwest::Request, headers: BTreeSet<String>) -> Self {
-    DataLoaderRequest(req, headers)
+    DataLoaderRequest(req, headers, Context::current())
   }
   pub fn to_request(&self) -> reqwest::Request {
     self.clone().0
@@ -15,6 +19,9 @@ impl DataLoaderRequest {
   pub fn headers(&self) -> &BTreeSet<String> {
     &self.1
   }
+  pub fn context(&self) -> &Context {
+    &self.2
+  }
 }
 impl Hash for DataLoaderRequest {
   fn hash<H: Hasher>(&self, state: &mut H) {
@@ -25,6 +32,9 @@ impl Hash for DataLoaderRequest {
         value.hash(state);
       }
     }
+    if let Some(body) = self.0.body().and_then(|body| body.as_bytes()) {
+      body.hash(state);
+    }
   }
 }
 
@@ -44,9 +54,12 @@ impl PartialEq for DataLoaderRequest {
 
 impl Clone for DataLoaderRequest {
   fn clone(&self) -> Self {
-    let mut req = reqwest::Request::new(reqwest::Method::GET, self.0.url().clone());
+    let mut req = reqwest::Request::new(self.0.method().clone(), self.0.url().clone());
     req.headers_mut().extend(self.0.headers().clone());
-    DataLoaderRequest(req, self.1.clone())
+    if let Some(body) = self.0.body().and_then(|body| body.as_bytes()) {
+      req.body_mut().replace(body.to_vec().into());
+    }
+    DataLoaderRequest(req, self.1.clone(), self.2.clone())
   }
 }
 
@@ -184,6 +197,30 @@ mod tests {
     assert_eq!(key1, key2);
   }
 
+  #[test]
+  fn test_clone_with_body() {
+    let mut req = reqwest::Request::new(reqwest::Method::POST, "http://localhost:8080".parse().unwrap());
+    req.body_mut().replace("foo".into());
+    let key1 = DataLoaderRequest::new(req, BTreeSet::new());
+    let key2 = key1.clone();
+
+    assert_eq!(key2.to_request().method(), reqwest::Method::POST);
+    assert_eq!(key2.to_request().body().unwrap().as_bytes().unwrap(), "foo".as_bytes());
+  }
+
+  #[test]
+  fn test_different_bodies() {
+    let mut req1 = reqwest::Request::new(reqwest::Method::POST, "http://localhost:8080".parse().unwrap());
+    req1.body_mut().replace("foo".into());
+    let mut req2 = reqwest::Request::new(reqwest::Method::POST, "http://localhost:8080".parse().unwrap());
+    req2.body_mut().replace("bar".into());
+
+    assert_ne!(
+      DataLoaderRequest::new(req1, BTreeSet::new()),
+      DataLoaderRequest::new(req2, BTreeSet::new())
+    );
+  }
+
   #[test]
   fn test_partial_eq_trait() {
     let key1 = create_endpoint_key(
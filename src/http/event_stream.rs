@@ -0,0 +1,105 @@
+use anyhow::Result;
+use async_graphql::futures_util::future::ready;
+use async_graphql::futures_util::stream::{self, Stream, StreamExt};
+
+/// Decodes a `text/event-stream` body into the data of each of its events.
+/// Comments and fields other than `data` (eg. `event`, `id` or `retry`) are ignored.
+pub fn decode_events<S, B, E>(bytes: S) -> impl Stream<Item = Result<String>>
+where
+  S: Stream<Item = std::result::Result<B, E>>,
+  B: AsRef<[u8]>,
+  E: Into<anyhow::Error>,
+{
+  bytes
+    .scan(EventDecoder::default(), |decoder, chunk| {
+      let events = match chunk {
+        Ok(chunk) => decoder.feed(chunk.as_ref()).into_iter().map(Ok).collect(),
+        Err(e) => vec![Err(e.into())],
+      };
+      ready(Some(stream::iter(events)))
+    })
+    .flatten()
+}
+
+/// Encodes the given data as a single event of a `text/event-stream` body.
+pub fn encode_event(data: &str) -> String {
+  let mut event = String::new();
+  for line in data.lines() {
+    event.push_str("data: ");
+    event.push_str(line);
+    event.push('\n');
+  }
+  event.push('\n');
+  event
+}
+
+#[derive(Default)]
+struct EventDecoder {
+  buffer: Vec<u8>,
+  data: Vec<String>,
+}
+
+impl EventDecoder {
+  /// Feeds a chunk of the body and returns the data of every event it completes.
+  fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+    self.buffer.extend_from_slice(chunk);
+    let mut events = Vec::new();
+
+    while let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+      let line = self.buffer.drain(..=end).collect::<Vec<_>>();
+      let line = String::from_utf8_lossy(&line);
+      let line = line.trim_end_matches(['\n', '\r']);
+
+      if line.is_empty() {
+        if !self.data.is_empty() {
+          events.push(self.data.join("\n"));
+          self.data.clear();
+        }
+      } else if let Some(value) = line.strip_prefix("data:") {
+        self.data.push(value.strip_prefix(' ').unwrap_or(value).to_string());
+      }
+    }
+
+    events
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use async_graphql::futures_util::stream::{self, StreamExt};
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  async fn decode(chunks: Vec<&'static str>) -> Vec<String> {
+    let bytes = stream::iter(chunks.into_iter().map(Ok::<_, anyhow::Error>));
+    decode_events(bytes)
+      .map(|event| event.unwrap())
+      .collect::<Vec<_>>()
+      .await
+  }
+
+  #[tokio::test]
+  async fn test_decode_events() {
+    let events = decode(vec!["data: {\"id\": 1}\n\n", "data: {\"id\": 2}\n\n"]).await;
+    assert_eq!(events, vec![r#"{"id": 1}"#, r#"{"id": 2}"#]);
+  }
+
+  #[tokio::test]
+  async fn test_decode_events_across_chunks() {
+    let events = decode(vec!["da", "ta: {\"id\"", ": 1}\r\n", "\r\n"]).await;
+    assert_eq!(events, vec![r#"{"id": 1}"#]);
+  }
+
+  #[tokio::test]
+  async fn test_decode_events_ignores_other_fields() {
+    let events = decode(vec![": keep-alive\n\n", "event: news\nid: 1\ndata: [1,\ndata: 2]\n\n"]).await;
+    assert_eq!(events, vec!["[1,\n2]"]);
+  }
+
+  #[test]
+  fn test_encode_event() {
+    assert_eq!(encode_event("{\"id\": 1}"), "data: {\"id\": 1}\n\n");
+    assert_eq!(encode_event("[1,\n2]"), "data: [1,\ndata: 2]\n\n");
+  }
+}
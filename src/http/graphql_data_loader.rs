@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_graphql::async_trait;
+use async_graphql::dataloader::{DataLoader, Loader, NoCache};
+use async_graphql::futures_util::future::join_all;
+use async_graphql_value::ConstValue;
+
+use crate::config::Batch;
+use crate::http::{DataLoaderRequest, HttpClient, Response};
+
+#[derive(Default, Clone, Debug)]
+pub struct GraphqlDataLoader<C>
+where
+  C: HttpClient + Send + Sync + 'static + Clone,
+{
+  pub client: C,
+  pub batch: bool,
+}
+
+impl<C: HttpClient + Send + Sync + 'static + Clone> GraphqlDataLoader<C> {
+  pub fn new(client: C, batch: bool) -> Self {
+    GraphqlDataLoader { client, batch }
+  }
+
+  pub fn to_data_loader(self, batch: Batch) -> DataLoader<GraphqlDataLoader<C>, NoCache> {
+    DataLoader::new(self, tokio::spawn)
+      .delay(Duration::from_millis(batch.delay as u64))
+      .max_batch_size(batch.max_size)
+  }
+}
+
+#[async_trait::async_trait]
+impl<C: HttpClient + Send + Sync + 'static + Clone> Loader<DataLoaderRequest> for GraphqlDataLoader<C> {
+  type Value = Response;
+  type Error = Arc<anyhow::Error>;
+
+  async fn load(
+    &self,
+    keys: &[DataLoaderRequest],
+  ) -> async_graphql::Result<HashMap<DataLoaderRequest, Self::Value>, Self::Error> {
+    if self.batch {
+      let Some((batched_req, keys)) = create_batched_request(keys) else {
+        return Ok(HashMap::new());
+      };
+      let result = self.client.execute(batched_req).await?;
+
+      Ok(extract_responses(result, &keys))
+    } else {
+      let results = keys.iter().map(|key| async {
+        let result = self.client.execute(key.to_request()).await;
+        (key.clone(), result)
+      });
+
+      let results = join_all(results).await;
+
+      #[allow(clippy::mutable_key_type)]
+      let mut hashmap = HashMap::new();
+      for (key, value) in results {
+        hashmap.insert(key, value?);
+      }
+
+      Ok(hashmap)
+    }
+  }
+}
+
+/// Merges the bodies of all the requests into a single GraphQL batch request (a JSON array).
+/// Returns the requests that made it into the batch, in the order of their responses.
+fn create_batched_request(keys: &[DataLoaderRequest]) -> Option<(reqwest::Request, Vec<DataLoaderRequest>)> {
+  let (keys, bodies): (Vec<_>, Vec<_>) = keys
+    .iter()
+    .filter_map(|key| {
+      let request = key.to_request();
+      let body = request.body().and_then(|body| body.as_bytes())?;
+      Some((key.clone(), String::from_utf8_lossy(body).to_string()))
+    })
+    .unzip();
+
+  let mut request = keys.first()?.to_request();
+  request.body_mut().replace(format!("[{}]", bodies.join(",")).into());
+  Some((request, keys))
+}
+
+/// Splits a GraphQL batch response back into one response per request.
+#[allow(clippy::mutable_key_type)]
+fn extract_responses(result: Response, keys: &[DataLoaderRequest]) -> HashMap<DataLoaderRequest, Response> {
+  let mut hashmap = HashMap::with_capacity(keys.len());
+
+  if let ConstValue::List(values) = &result.body {
+    for (key, value) in keys.iter().zip(values.iter()) {
+      hashmap.insert(key.clone(), result.clone().body(value.clone()));
+    }
+  } else {
+    // The upstream didn't return a batch, so every request shares the same response
+    for key in keys {
+      hashmap.insert(key.clone(), result.clone());
+    }
+  }
+
+  hashmap
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::BTreeSet;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  use serde_json::json;
+
+  use super::*;
+
+  #[derive(Clone)]
+  struct MockHttpClient {
+    request_count: Arc<AtomicUsize>,
+  }
+
+  #[async_trait::async_trait]
+  impl HttpClient for MockHttpClient {
+    async fn execute(&self, req: reqwest::Request) -> anyhow::Result<Response> {
+      self.request_count.fetch_add(1, Ordering::SeqCst);
+      let body = req.body().and_then(|body| body.as_bytes()).unwrap_or_default();
+      let queries: Vec<serde_json::Value> = serde_json::from_slice(body)?;
+      let data = queries
+        .iter()
+        .map(|query| json!({"data": {"query": query["query"]}}))
+        .collect::<Vec<_>>();
+      Ok(Response::default().body(ConstValue::from_json(json!(data))?))
+    }
+  }
+
+  fn create_request(query: &str) -> DataLoaderRequest {
+    let mut req = reqwest::Request::new(reqwest::Method::POST, "http://example.com/graphql".parse().unwrap());
+    req.body_mut().replace(json!({ "query": query }).to_string().into());
+    DataLoaderRequest::new(req, BTreeSet::new())
+  }
+
+  #[tokio::test]
+  async fn test_batched_load() {
+    let client = MockHttpClient { request_count: Arc::new(AtomicUsize::new(0)) };
+    let loader = GraphqlDataLoader::new(client.clone(), true).to_data_loader(Batch::default().delay(1));
+
+    let key1 = create_request("query { a }");
+    let key2 = create_request("query { b }");
+    let results = join_all([loader.load_one(key1), loader.load_one(key2)]).await;
+    let bodies = results
+      .into_iter()
+      .map(|result| result.unwrap().unwrap().body)
+      .collect::<Vec<_>>();
+
+    assert_eq!(
+      client.request_count.load(Ordering::SeqCst),
+      1,
+      "Only one batched request should be made"
+    );
+    assert_eq!(
+      bodies,
+      vec![
+        ConstValue::from_json(json!({"data": {"query": "query { a }"}})).unwrap(),
+        ConstValue::from_json(json!({"data": {"query": "query { b }"}})).unwrap(),
+      ]
+    );
+  }
+
+  #[tokio::test]
+  async fn test_batched_request_without_body() {
+    let client = MockHttpClient { request_count: Arc::new(AtomicUsize::new(0)) };
+    let empty = DataLoaderRequest::new(
+      reqwest::Request::new(reqwest::Method::POST, "http://example.com/graphql".parse().unwrap()),
+      BTreeSet::new(),
+    );
+    let key = create_request("query { b }");
+    let (request, keys) = create_batched_request(&[empty.clone(), key.clone()]).unwrap();
+    #[allow(clippy::mutable_key_type)]
+    let responses = extract_responses(client.execute(request).await.unwrap(), &keys);
+
+    assert!(!responses.contains_key(&empty));
+    assert_eq!(
+      responses[&key].body,
+      ConstValue::from_json(json!({"data": {"query": "query { b }"}})).unwrap()
+    );
+  }
+}
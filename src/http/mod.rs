@@ -1,13 +1,20 @@
+mod circuit_breaker;
 mod client;
 mod data_loader;
 
 mod data_loader_request;
+mod event_stream;
+mod graphql_data_loader;
 mod memo_client;
 mod method;
 mod request_context;
 mod response;
+mod retry;
 mod server;
 mod server_context;
+mod tls;
+mod upstream_metrics;
+mod upstream_tracing;
 
 use std::time::Duration;
 
@@ -15,12 +22,15 @@ use cache_control::CacheControl;
 pub use client::*;
 pub use data_loader::*;
 pub use data_loader_request::*;
+pub use event_stream::*;
+pub use graphql_data_loader::*;
 use hyper::header::CACHE_CONTROL;
 pub use method::Method;
 pub use request_context::RequestContext;
 pub use response::*;
-pub use server::start_server;
+pub use server::{start_server, start_server_with_reload};
 pub use server_context::ServerContext;
+pub use tls::*;
 
 pub fn max_age(res: &Response) -> Option<Duration> {
   let header = res.headers.get(CACHE_CONTROL)?;
@@ -29,6 +39,15 @@ pub fn max_age(res: &Response) -> Option<Duration> {
   policy.max_age
 }
 
+/// Identifies the host of a URL as `host:port`, with the default port of the scheme when the URL has none.
+pub fn host_and_port(url: &reqwest::Url) -> String {
+  format!(
+    "{}:{}",
+    url.host_str().unwrap_or_default(),
+    url.port_or_known_default().unwrap_or_default()
+  )
+}
+
 /// Returns the minimum TTL of the given responses.
 pub fn min_ttl<'a>(res_vec: impl Iterator<Item = &'a Response>) -> i32 {
   let mut min = -1;
@@ -1,18 +1,30 @@
+#![allow(clippy::too_many_arguments)]
+
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
+use async_graphql_value::ConstValue;
 use derive_setters::Setters;
 use hyper::HeaderMap;
 
 use super::{DefaultHttpClient, Response, ServerContext};
+use crate::auth::Identity;
 use crate::blueprint::Server;
+use crate::cache::TtlCache;
 use crate::config::{self, Upstream};
+use crate::lambda::CacheKey;
 
 #[derive(Setters)]
 pub struct RequestContext {
   pub http_client: DefaultHttpClient,
+  pub http2_only_client: DefaultHttpClient,
   pub server: Server,
   pub upstream: Upstream,
+  /// Clients of the named upstream profiles.
+  pub upstream_clients: Arc<HashMap<String, DefaultHttpClient>>,
   pub req_headers: HeaderMap,
+  pub cache: TtlCache<CacheKey, ConstValue>,
+  pub identity: Option<Identity>,
   min_max_age: Arc<Mutex<Option<u64>>>,
 }
 
@@ -21,18 +33,54 @@ impl Default for RequestContext {
     let config = config::Config::default();
     //TODO: default is used only in tests. Drop default and move it to test.
     let server = Server::try_from(config.server.clone()).unwrap();
-    RequestContext::new(DefaultHttpClient::default(), server, config.upstream.clone())
+    RequestContext::new(
+      DefaultHttpClient::default(),
+      DefaultHttpClient::with_http2_only(config.upstream.clone()).unwrap(),
+      server,
+      config.upstream.clone(),
+    )
   }
 }
 
 impl RequestContext {
-  pub fn new(http_client: DefaultHttpClient, server: Server, upstream: Upstream) -> Self {
-    Self { req_headers: HeaderMap::new(), http_client, server, upstream, min_max_age: Arc::new(Mutex::new(None)) }
+  pub fn new(
+    http_client: DefaultHttpClient,
+    http2_only_client: DefaultHttpClient,
+    server: Server,
+    upstream: Upstream,
+  ) -> Self {
+    Self {
+      req_headers: HeaderMap::new(),
+      http_client,
+      http2_only_client,
+      server,
+      upstream,
+      upstream_clients: Default::default(),
+      cache: TtlCache::default(),
+      identity: None,
+      min_max_age: Arc::new(Mutex::new(None)),
+    }
   }
 
   pub async fn execute(&self, req: reqwest::Request) -> anyhow::Result<Response> {
     Ok(self.http_client.execute(req).await?)
   }
+  pub async fn execute_idempotent(&self, req: reqwest::Request) -> anyhow::Result<Response> {
+    Ok(self.http_client.execute_idempotent(req).await?)
+  }
+  /// The client of the upstream profile, or the default client without one.
+  pub fn http_client_of(&self, upstream: Option<&str>) -> &DefaultHttpClient {
+    upstream
+      .and_then(|name| self.upstream_clients.get(name))
+      .unwrap_or(&self.http_client)
+  }
+  /// The settings of the upstream profile, or the unnamed `@upstream` without one.
+  pub fn upstream_of(&self, upstream: Option<&str>) -> &Upstream {
+    match upstream.and_then(|name| self.upstream_clients.get(name)) {
+      Some(http_client) => http_client.upstream(),
+      None => &self.upstream,
+    }
+  }
   fn set_min_max_age_conc(&self, min_max_age: u64) {
     *self.min_max_age.lock().unwrap() = Some(min_max_age);
   }
@@ -59,9 +107,12 @@ impl From<&ServerContext> for RequestContext {
     let http_client = server_ctx.http_client.clone();
     Self::new(
       http_client,
+      server_ctx.http2_only_client.clone(),
       server_ctx.blueprint.server.clone(),
       server_ctx.blueprint.upstream.clone(),
     )
+    .upstream_clients(server_ctx.upstream_clients.clone())
+    .cache(server_ctx.cache.clone())
   }
 }
 
@@ -14,11 +14,22 @@ impl Response {
     let status = resp.status();
     let headers = resp.headers().to_owned();
     let body = resp.bytes().await?;
+    if is_grpc(&headers) {
+      // gRPC messages are decoded later on, as the message type depends on the operation
+      return Ok(Response { status, headers, body: async_graphql::Value::Binary(body) });
+    }
     let json = serde_json::from_slice(&body)?;
     Ok(Response { status, headers, body: json })
   }
 }
 
+fn is_grpc(headers: &reqwest::header::HeaderMap) -> bool {
+  headers
+    .get(reqwest::header::CONTENT_TYPE)
+    .and_then(|value| value.to_str().ok())
+    .is_some_and(|value| value.starts_with("application/grpc"))
+}
+
 impl ResponseLike for Response {
   fn status(&self) -> reqwest::StatusCode {
     self.status
@@ -0,0 +1,133 @@
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use reqwest::{Method, Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use task_local_extensions::Extensions;
+
+use crate::config::{self, RetryError, RetryOn};
+
+/// The longest time to wait between two attempts, however many attempts were made.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Marks a request as safe to retry, even though its method isn't idempotent.
+#[derive(Clone, Copy, Debug)]
+pub struct Idempotent;
+
+/// Middleware that retries the requests that fail with one of the configured statuses or errors.
+pub struct Retry {
+  max_attempts: usize,
+  backoff: Duration,
+  retry_on: BTreeSet<RetryOn>,
+}
+
+impl Retry {
+  pub fn new(retry: config::Retry) -> Self {
+    Retry {
+      max_attempts: retry.max_attempts,
+      backoff: Duration::from_millis(retry.backoff_ms),
+      retry_on: retry.retry_on,
+    }
+  }
+
+  fn is_retryable(request: &Request, extensions: &Extensions) -> bool {
+    let method = request.method();
+    method == Method::GET
+      || method == Method::HEAD
+      || method == Method::PUT
+      || method == Method::DELETE
+      || extensions.get::<Idempotent>().is_some()
+  }
+
+  /// Doubles the wait after every attempt, up to `MAX_BACKOFF`.
+  fn backoff(&self, attempt: usize) -> Duration {
+    u32::try_from(attempt - 1)
+      .ok()
+      .and_then(|exponent| 2u32.checked_pow(exponent))
+      .and_then(|factor| self.backoff.checked_mul(factor))
+      .map_or(MAX_BACKOFF, |backoff| backoff.min(MAX_BACKOFF))
+  }
+
+  fn should_retry(&self, result: &reqwest_middleware::Result<Response>) -> bool {
+    match result {
+      Ok(response) => self.retry_on.contains(&RetryOn::Status(response.status().as_u16())),
+      Err(reqwest_middleware::Error::Reqwest(error)) => {
+        (error.is_timeout() && self.retry_on.contains(&RetryOn::Error(RetryError::Timeout)))
+          || (error.is_connect() && self.retry_on.contains(&RetryOn::Error(RetryError::Connect)))
+      }
+      Err(reqwest_middleware::Error::Middleware(_)) => false,
+    }
+  }
+}
+
+#[async_trait::async_trait]
+impl Middleware for Retry {
+  async fn handle(
+    &self,
+    request: Request,
+    extensions: &mut Extensions,
+    next: Next<'_>,
+  ) -> reqwest_middleware::Result<Response> {
+    if !Self::is_retryable(&request, extensions) {
+      return next.run(request, extensions).await;
+    }
+
+    let mut attempt = 1;
+    loop {
+      // Requests with a streaming body can't be cloned, and so are sent only once
+      let Some(attempt_request) = request.try_clone() else {
+        return next.run(request, extensions).await;
+      };
+      let result = next.clone().run(attempt_request, extensions).await;
+      if attempt >= self.max_attempts || !self.should_retry(&result) {
+        return result;
+      }
+
+      let backoff = self.backoff(attempt);
+      log::warn!("Retrying {} {} in {:?}", request.method(), request.url(), backoff);
+      tokio::time::sleep(backoff).await;
+      attempt += 1;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn request(method: Method) -> Request {
+    Request::new(method, "http://localhost/users".parse().unwrap())
+  }
+
+  #[test]
+  fn test_is_retryable() {
+    let mut extensions = Extensions::new();
+    assert!(Retry::is_retryable(&request(Method::GET), &extensions));
+    assert!(Retry::is_retryable(&request(Method::DELETE), &extensions));
+    assert!(!Retry::is_retryable(&request(Method::POST), &extensions));
+
+    extensions.insert(Idempotent);
+    assert!(Retry::is_retryable(&request(Method::POST), &extensions));
+  }
+
+  #[test]
+  fn test_should_retry_status() {
+    let retry = Retry::new(config::Retry::default());
+    let response = |status: u16| Ok(hyper::Response::builder().status(status).body("").unwrap().into());
+
+    assert!(retry.should_retry(&response(503)));
+    assert!(!retry.should_retry(&response(500)));
+    assert!(!retry.should_retry(&response(200)));
+  }
+
+  #[test]
+  fn test_backoff() {
+    let retry = Retry::new(config::Retry { backoff_ms: 100, ..Default::default() });
+    assert_eq!(retry.backoff(1), Duration::from_millis(100));
+    assert_eq!(retry.backoff(3), Duration::from_millis(400));
+    assert_eq!(retry.backoff(64), MAX_BACKOFF);
+
+    let retry = Retry::new(config::Retry { backoff_ms: u64::MAX, ..Default::default() });
+    assert_eq!(retry.backoff(2), MAX_BACKOFF);
+  }
+}
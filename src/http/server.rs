@@ -1,17 +1,45 @@
+#![allow(clippy::too_many_arguments)]
+
 use std::collections::BTreeSet;
-use std::sync::Arc;
+use std::net::SocketAddr;
+use std::pin::pin;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use anyhow::Result;
-use async_graphql::http::GraphiQLSource;
+use async_graphql::dynamic::Schema;
+use async_graphql::futures_util::future::ready;
+use async_graphql::futures_util::{SinkExt, StreamExt};
+use async_graphql::http::{GraphiQLSource, WebSocket, WebSocketProtocols, WsMessage};
+use async_graphql::parser::types::{DocumentOperations, OperationType};
+use async_graphql::Data;
+use hyper::header::{
+  ACCEPT, ACCESS_CONTROL_REQUEST_METHOD, ALLOW, CONNECTION, CONTENT_TYPE, ORIGIN, SEC_WEBSOCKET_ACCEPT,
+  SEC_WEBSOCKET_KEY, SEC_WEBSOCKET_PROTOCOL, UPGRADE,
+};
+use hyper::server::conn::Http;
 use hyper::service::{make_service_fn, service_fn};
+use hyper::upgrade::Upgraded;
 use hyper::{Body, HeaderMap, Request, Response, StatusCode};
+use opentelemetry::trace::FutureExt;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_rustls::TlsAcceptor;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::frame::CloseFrame;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
 
 use super::request_context::RequestContext;
 use super::ServerContext;
-use crate::async_graphql_hyper;
 use crate::blueprint::Blueprint;
 use crate::cli::CLIError;
-use crate::config::Config;
+use crate::config::{self, Config};
+use crate::metrics::metrics;
+use crate::persisted_queries::{PersistedQueries, PersistedQueryExecutor};
+use crate::{async_graphql_hyper, telemetry};
 
 fn graphiql() -> Result<Response<Body>> {
   Ok(Response::new(Body::from(
@@ -19,14 +47,104 @@ fn graphiql() -> Result<Response<Body>> {
   )))
 }
 
-async fn graphql_request(req: Request<Body>, server_ctx: &ServerContext) -> Result<Response<Body>> {
+fn create_request_context(req: &Request<Body>, server_ctx: &ServerContext) -> Arc<RequestContext> {
   let upstream = server_ctx.blueprint.upstream.clone();
   let allowed = upstream.get_allowed_headers();
   let headers = create_allowed_headers(req.headers(), &allowed);
+  let identity = server_ctx
+    .blueprint
+    .server
+    .auth
+    .as_ref()
+    .and_then(|auth| auth.authenticate(req.headers()));
+  Arc::new(RequestContext::from(server_ctx).req_headers(headers).identity(identity))
+}
+
+/// Executes a request, or a JSON array of requests as a batch.
+async fn graphql_request(req: Request<Body>, server_ctx: &ServerContext) -> Result<Response<Body>> {
+  let req_ctx = create_request_context(&req, server_ctx);
+  let graphql_response_json = accepts_graphql_response_json(&req);
   let bytes = hyper::body::to_bytes(req.into_body()).await?;
-  let request: async_graphql_hyper::GraphQLRequest = serde_json::from_slice(&bytes)?;
-  let req_ctx = Arc::new(RequestContext::from(server_ctx).req_headers(headers));
-  let mut response = request.data(req_ctx.clone()).execute(&server_ctx.schema).await;
+  let request: async_graphql_hyper::GraphQLBatchRequest = serde_json::from_slice(&bytes)?;
+  let persisted_queries = server_ctx.blueprint.server.persisted_queries.as_ref();
+  execute_graphql_request(request, persisted_queries, req_ctx, server_ctx, graphql_response_json).await
+}
+
+/// Executes a request that is sent as the query string of a GET request.
+/// Only queries can be sent this way, as GET requests must not have side effects.
+async fn graphql_get_request(req: Request<Body>, server_ctx: &ServerContext) -> Result<Response<Body>> {
+  let req_ctx = create_request_context(&req, server_ctx);
+  let graphql_response_json = accepts_graphql_response_json(&req);
+  let query: async_graphql_hyper::GraphQLQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or_default())?;
+  let request = async_graphql_hyper::GraphQLRequest::from(query);
+  let request = match request
+    .persisted(server_ctx.blueprint.server.persisted_queries.as_ref())
+    .await
+  {
+    Ok(request) => request,
+    Err(response) if graphql_response_json => return response.to_graphql_response_json(),
+    Err(response) => return response.to_response(),
+  };
+  if is_mutation(&request.0) {
+    return method_not_allowed();
+  }
+  execute_graphql_request(request.into(), None, req_ctx, server_ctx, graphql_response_json).await
+}
+
+async fn execute_graphql_request(
+  request: async_graphql_hyper::GraphQLBatchRequest,
+  persisted_queries: Option<&PersistedQueries>,
+  req_ctx: Arc<RequestContext>,
+  server_ctx: &ServerContext,
+  graphql_response_json: bool,
+) -> Result<Response<Body>> {
+  let mut response = request
+    .data(req_ctx.clone())
+    .execute_persisted(&server_ctx.schema, persisted_queries)
+    .await;
+
+  if server_ctx.blueprint.server.enable_cache_control_header {
+    if let Some(ttl) = req_ctx.get_min_max_age() {
+      response = response.set_cache_control(ttl as i32);
+    }
+  }
+  let mut resp = if graphql_response_json {
+    response.to_graphql_response_json()?
+  } else {
+    response.to_response()?
+  };
+  if !server_ctx.blueprint.server.response_headers.is_empty() {
+    resp
+      .headers_mut()
+      .extend(server_ctx.blueprint.server.response_headers.clone());
+  }
+
+  Ok(resp)
+}
+
+/// Checks if the operation that the request selects is a mutation.
+/// Queries that can't be parsed are left for the execution to report.
+fn is_mutation(request: &async_graphql::Request) -> bool {
+  let Ok(document) = async_graphql::parser::parse_query(&request.query) else {
+    return false;
+  };
+  let operation = match (&document.operations, &request.operation_name) {
+    (DocumentOperations::Single(operation), _) => Some(operation),
+    (DocumentOperations::Multiple(operations), Some(name)) => operations.get(name.as_str()),
+    (DocumentOperations::Multiple(_), None) => None,
+  };
+  operation.is_some_and(|operation| operation.node.ty == OperationType::Mutation)
+}
+
+/// Executes the operation of a REST endpoint and responds with its data.
+async fn rest_request(
+  req: Request<Body>,
+  request: async_graphql::Request,
+  server_ctx: &ServerContext,
+) -> Result<Response<Body>> {
+  let req_ctx = create_request_context(&req, server_ctx);
+  let response = server_ctx.schema.execute(request.data(req_ctx.clone())).await;
+  let mut response = async_graphql_hyper::GraphQLRestResponse(response);
 
   if server_ctx.blueprint.server.enable_cache_control_header {
     if let Some(ttl) = req_ctx.get_min_max_age() {
@@ -42,12 +160,225 @@ async fn graphql_request(req: Request<Body>, server_ctx: &ServerContext) -> Resu
 
   Ok(resp)
 }
+
+fn find_rest_request(req: &Request<Body>, server_ctx: &ServerContext) -> Option<async_graphql::Request> {
+  server_ctx
+    .blueprint
+    .rest
+    .iter()
+    .find_map(|endpoint| endpoint.matches(req.method(), req.uri()))
+}
+
+/// Executes the request as a stream and sends every response as a server-sent event.
+async fn graphql_stream_request(req: Request<Body>, server_ctx: &ServerContext) -> Result<Response<Body>> {
+  let req_ctx = create_request_context(&req, server_ctx);
+  let bytes = hyper::body::to_bytes(req.into_body()).await?;
+  let request: async_graphql_hyper::GraphQLRequest = serde_json::from_slice(&bytes)?;
+  let request = match request
+    .persisted(server_ctx.blueprint.server.persisted_queries.as_ref())
+    .await
+  {
+    Ok(request) => request,
+    Err(response) => return response.to_response(),
+  };
+  let mut resp = request.data(req_ctx).execute_stream(&server_ctx.schema).to_response()?;
+  if !server_ctx.blueprint.server.response_headers.is_empty() {
+    resp
+      .headers_mut()
+      .extend(server_ctx.blueprint.server.response_headers.clone());
+  }
+
+  Ok(resp)
+}
+
+/// Upgrades the connection to a WebSocket that speaks one of the GraphQL over WebSocket protocols.
+fn graphql_ws_request(mut req: Request<Body>, server_ctx: Arc<ServerContext>) -> Result<Response<Body>> {
+  let protocol = req
+    .headers()
+    .get(SEC_WEBSOCKET_PROTOCOL)
+    .and_then(|protocols| protocols.to_str().ok())
+    .and_then(|protocols| {
+      protocols
+        .split(',')
+        .find_map(|protocol| WebSocketProtocols::from_str(protocol.trim()).ok())
+    });
+  let (Some(key), Some(protocol)) = (req.headers().get(SEC_WEBSOCKET_KEY), protocol) else {
+    return bad_request();
+  };
+  let accept = derive_accept_key(key.as_bytes());
+  let req_ctx = create_request_context(&req, &server_ctx);
+
+  tokio::spawn(async move {
+    match hyper::upgrade::on(&mut req).await {
+      Ok(upgraded) => {
+        let socket = WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+        let executor = PersistedQueryExecutor::new(
+          server_ctx.schema.clone(),
+          server_ctx.blueprint.server.persisted_queries.clone(),
+        );
+        serve_graphql_ws(socket, protocol, req_ctx, executor).await;
+      }
+      Err(e) => log::error!("WebSocket upgrade failed: {}", e),
+    }
+  });
+
+  Ok(
+    Response::builder()
+      .status(StatusCode::SWITCHING_PROTOCOLS)
+      .header(CONNECTION, "upgrade")
+      .header(UPGRADE, "websocket")
+      .header(SEC_WEBSOCKET_ACCEPT, accept)
+      .header(SEC_WEBSOCKET_PROTOCOL, protocol.sec_websocket_protocol())
+      .body(Body::empty())?,
+  )
+}
+
+async fn serve_graphql_ws(
+  socket: WebSocketStream<Upgraded>,
+  protocol: WebSocketProtocols,
+  req_ctx: Arc<RequestContext>,
+  executor: PersistedQueryExecutor<Schema>,
+) {
+  let (mut sink, stream) = socket.split();
+  let messages = stream
+    .take_while(|message| ready(message.is_ok()))
+    .filter_map(|message| {
+      ready(match message {
+        Ok(Message::Text(text)) => Some(text.into_bytes()),
+        Ok(Message::Binary(bytes)) => Some(bytes),
+        _ => None,
+      })
+    });
+  let mut data = Data::default();
+  data.insert(req_ctx);
+  let responses = WebSocket::new(executor, messages, protocol).connection_data(data);
+  let mut responses = pin!(responses);
+
+  while let Some(message) = responses.next().await {
+    let message = match message {
+      WsMessage::Text(text) => Message::Text(text),
+      WsMessage::Close(code, reason) => Message::Close(Some(CloseFrame { code: code.into(), reason: reason.into() })),
+    };
+    if sink.send(message).await.is_err() {
+      break;
+    }
+  }
+}
+
+fn is_websocket_upgrade(req: &Request<Body>) -> bool {
+  req
+    .headers()
+    .get(UPGRADE)
+    .and_then(|upgrade| upgrade.to_str().ok())
+    .is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket"))
+}
+
+fn accepts_graphql_response_json(req: &Request<Body>) -> bool {
+  req
+    .headers()
+    .get(ACCEPT)
+    .and_then(|accept| accept.to_str().ok())
+    .is_some_and(|accept| accept.contains("application/graphql-response+json"))
+}
+
+fn accepts_event_stream(req: &Request<Body>) -> bool {
+  req
+    .headers()
+    .get(ACCEPT)
+    .and_then(|accept| accept.to_str().ok())
+    .is_some_and(|accept| accept.contains("text/event-stream"))
+}
+
+fn metrics_request() -> Result<Response<Body>> {
+  Ok(
+    Response::builder()
+      .header(CONTENT_TYPE, prometheus::TEXT_FORMAT)
+      .body(Body::from(metrics().encode()?))?,
+  )
+}
+
+fn method_not_allowed() -> Result<Response<Body>> {
+  let body = serde_json::json!({"errors": [{"message": "Mutations can only be sent with POST"}]});
+  Ok(
+    Response::builder()
+      .status(StatusCode::METHOD_NOT_ALLOWED)
+      .header(ALLOW, "POST")
+      .header(CONTENT_TYPE, "application/json")
+      .body(Body::from(body.to_string()))?,
+  )
+}
+
+/// Answers a failed request, with a bad request when its body or query string can't be read.
+fn error_response(error: &anyhow::Error) -> Result<Response<Body>> {
+  let status = if error.is::<serde_json::Error>() || error.is::<serde_urlencoded::de::Error>() {
+    StatusCode::BAD_REQUEST
+  } else {
+    StatusCode::INTERNAL_SERVER_ERROR
+  };
+  let body = serde_json::json!({"errors": [{"message": error.to_string()}]});
+  Ok(
+    Response::builder()
+      .status(status)
+      .header(CONTENT_TYPE, "application/json")
+      .body(Body::from(body.to_string()))?,
+  )
+}
+
 fn not_found() -> Result<Response<Body>> {
   Ok(Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty())?)
 }
+fn bad_request() -> Result<Response<Body>> {
+  Ok(
+    Response::builder()
+      .status(StatusCode::BAD_REQUEST)
+      .body(Body::empty())?,
+  )
+}
 async fn handle_request(req: Request<Body>, state: Arc<ServerContext>) -> Result<Response<Body>> {
+  // The spans of the request continue the trace of the client, if it sent one
+  let parent = telemetry::extract(req.headers());
+  let cors = state.blueprint.server.cors.clone();
+  let origin = req.headers().get(ORIGIN).cloned();
+  if let Some(cors) = &cors {
+    if req.method() == hyper::Method::OPTIONS && req.headers().contains_key(ACCESS_CONTROL_REQUEST_METHOD) {
+      let mut response = Response::builder().status(StatusCode::NO_CONTENT).body(Body::empty())?;
+      response.headers_mut().extend(cors.preflight_headers(origin.as_ref()));
+      return Ok(response);
+    }
+  }
+  let mut response = match route_request(req, state).with_context(parent).await {
+    Ok(response) => response,
+    Err(error) => error_response(&error)?,
+  };
+  // Failed requests carry the CORS headers as well, so that browsers can read their errors
+  if let Some(cors) = &cors {
+    response.headers_mut().extend(cors.response_headers(origin.as_ref()));
+  }
+  Ok(response)
+}
+
+async fn route_request(req: Request<Body>, state: Arc<ServerContext>) -> Result<Response<Body>> {
+  if req.method() == hyper::Method::GET && state.blueprint.server.metrics_path.as_deref() == Some(req.uri().path()) {
+    return metrics_request();
+  }
+
+  if req.uri().path() != "/graphql" {
+    if let Some(request) = find_rest_request(&req, &state) {
+      return rest_request(req, request, state.as_ref()).await;
+    }
+  }
+
   match *req.method() {
+    hyper::Method::GET if req.uri().path() == "/graphql" && is_websocket_upgrade(&req) => {
+      graphql_ws_request(req, state)
+    }
+    hyper::Method::GET if req.uri().path() == "/graphql" && req.uri().query().is_some() => {
+      graphql_get_request(req, state.as_ref()).await
+    }
     hyper::Method::GET if state.blueprint.server.enable_graphiql => graphiql(),
+    hyper::Method::POST if req.uri().path() == "/graphql" && accepts_event_stream(&req) => {
+      graphql_stream_request(req, state.as_ref()).await
+    }
     hyper::Method::POST if req.uri().path() == "/graphql" => graphql_request(req, state.as_ref()).await,
     _ => not_found(),
   }
@@ -63,18 +394,132 @@ fn create_allowed_headers(headers: &HeaderMap, allowed: &BTreeSet<String>) -> He
   new_headers
 }
 pub async fn start_server(config: Config) -> Result<()> {
+  let (_, reloads) = mpsc::channel(1);
+  start_server_with_reload(config, reloads).await
+}
+
+/// Starts the server, and switches to the configs received on `reloads`.
+/// Requests that are in flight during a switch finish on the previous config,
+/// and configs that fail validation are reported without replacing the current one.
+pub async fn start_server_with_reload(config: Config, reloads: mpsc::Receiver<Config>) -> Result<()> {
   let blueprint = Blueprint::try_from(&config).map_err(CLIError::from)?;
-  let state = Arc::new(ServerContext::new(blueprint.clone()));
-  let make_svc = make_service_fn(move |_conn| {
-    let state = Arc::clone(&state);
-    async move { Ok::<_, anyhow::Error>(service_fn(move |req| handle_request(req, state.clone()))) }
-  });
+  if let Some(telemetry) = &blueprint.server.telemetry {
+    telemetry::init_otlp(telemetry)?;
+  }
+  let state = Arc::new(RwLock::new(Arc::new(ServerContext::new(blueprint.clone())?)));
+  tokio::spawn(reload(state.clone(), config.server.clone(), reloads));
+
   let addr = (blueprint.server.hostname, blueprint.server.port).into();
-  let server = hyper::Server::try_bind(&addr).map_err(CLIError::from)?.serve(make_svc);
+  match blueprint.server.tls.clone() {
+    Some(tls) => {
+      let listener = TcpListener::bind(addr).await.map_err(CLIError::from)?;
+      log_launch(&blueprint, "https", addr);
+      serve_tls(listener, TlsAcceptor::from(tls), state).await
+    }
+    None => {
+      let make_svc = make_service_fn(move |_conn| {
+        let state = Arc::clone(&state);
+        async move {
+          Ok::<_, anyhow::Error>(service_fn(move |req| {
+            let state = state.read().unwrap().clone();
+            handle_request(req, state)
+          }))
+        }
+      });
+      let server = hyper::Server::try_bind(&addr)
+        .map_err(CLIError::from)?
+        .http1_only(!blueprint.server.enable_h2c)
+        .serve(make_svc);
+      log_launch(&blueprint, "http", addr);
+      Ok(server.await.map_err(CLIError::from)?)
+    }
+  }
+}
+
+fn log_launch(blueprint: &Blueprint, scheme: &str, addr: SocketAddr) {
   log::info!("🚀 Tailcall launched at [{}]", addr);
   if blueprint.server.enable_graphiql {
-    log::info!("🌍 Playground: http://{}", addr);
+    log::info!("🌍 Playground: {}://{}", scheme, addr);
+  }
+}
+
+/// How long a client has to complete the TLS handshake.
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long to wait before accepting connections again, after failing to accept one.
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Accepts TLS connections, and serves each of them over the HTTP version negotiated with ALPN.
+async fn serve_tls(listener: TcpListener, acceptor: TlsAcceptor, state: Arc<RwLock<Arc<ServerContext>>>) -> Result<()> {
+  loop {
+    let stream = match listener.accept().await {
+      Ok((stream, _)) => stream,
+      Err(e) => {
+        // Such as running out of file descriptors, which are released as other connections close
+        log::error!("Failed to accept a connection: {}", e);
+        tokio::time::sleep(ACCEPT_BACKOFF).await;
+        continue;
+      }
+    };
+    let acceptor = acceptor.clone();
+    let state = state.clone();
+    tokio::spawn(async move {
+      let stream = match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(e)) => return log::debug!("TLS handshake failed: {}", e),
+        Err(_) => return log::debug!("TLS handshake timed out"),
+      };
+      let http2 = stream.get_ref().1.alpn_protocol() == Some(b"h2");
+      let service = service_fn(move |req| {
+        let state = state.read().unwrap().clone();
+        handle_request(req, state)
+      });
+      let connection = Http::new()
+        .http2_only(http2)
+        .serve_connection(stream, service)
+        .with_upgrades();
+      if let Err(e) = connection.await {
+        log::debug!("Connection failed: {}", e);
+      }
+    });
+  }
+}
+
+/// Switches to the configs received on `reloads`.
+/// `server` holds the settings the server started with, as some of them only apply on start.
+async fn reload(state: Arc<RwLock<Arc<ServerContext>>>, server: config::Server, mut reloads: mpsc::Receiver<Config>) {
+  while let Some(config) = reloads.recv().await {
+    match Blueprint::try_from(&config) {
+      Ok(blueprint) => {
+        let changes = restart_only_changes(&server, &config.server);
+        if !changes.is_empty() {
+          log::warn!("Changes to {} of the server require a restart", changes.join(", "));
+        }
+        match ServerContext::new(blueprint) {
+          Ok(server_ctx) => {
+            *state.write().unwrap() = Arc::new(server_ctx);
+            log::info!("🔄 Configuration reloaded");
+          }
+          Err(error) => log::error!("Keeping the previous configuration: {error}"),
+        }
+      }
+      Err(error) => {
+        let error = CLIError::from(error).description("Keeping the previous configuration".to_string());
+        eprintln!("{}", error.color(true));
+      }
+    }
   }
+}
 
-  Ok(server.await.map_err(CLIError::from)?)
+/// The settings that are only read when the server starts, and differ between the configs.
+fn restart_only_changes(current: &config::Server, next: &config::Server) -> Vec<&'static str> {
+  [
+    ("hostname", current.hostname != next.hostname),
+    ("port", current.port != next.port),
+    ("tls", current.tls != next.tls),
+    ("h2c", current.h2c != next.h2c),
+    ("telemetry", current.telemetry != next.telemetry),
+  ]
+  .into_iter()
+  .filter_map(|(setting, changed)| changed.then_some(setting))
+  .collect()
 }
@@ -1,31 +1,117 @@
+#![allow(clippy::too_many_arguments)]
+
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_graphql::dynamic;
+use async_graphql_value::ConstValue;
 use derive_setters::Setters;
 
 use crate::blueprint::{Blueprint, Definition};
-use crate::http::{DefaultHttpClient, HttpDataLoader};
-use crate::lambda::{Expression, Operation};
+use crate::cache::TtlCache;
+use crate::grpc::GrpcDataLoader;
+use crate::http::{DefaultHttpClient, GraphqlDataLoader, HttpDataLoader};
+use crate::lambda::{CacheKey, Expression, Operation};
 
 #[derive(Setters, Clone)]
 pub struct ServerContext {
   pub schema: dynamic::Schema,
   pub http_client: DefaultHttpClient,
+  pub http2_only_client: DefaultHttpClient,
+  /// Clients of the named upstream profiles.
+  pub upstream_clients: Arc<HashMap<String, DefaultHttpClient>>,
   pub blueprint: Blueprint,
+  pub cache: TtlCache<CacheKey, ConstValue>,
 }
 
-fn assign_data_loaders(blueprint: &mut Blueprint, http_client: DefaultHttpClient) -> &Blueprint {
+fn assign_data_loaders<'a>(
+  blueprint: &'a mut Blueprint,
+  http_client: DefaultHttpClient,
+  http2_only_client: DefaultHttpClient,
+  upstream_clients: &HashMap<String, DefaultHttpClient>,
+) -> &'a Blueprint {
+  let batch = blueprint.upstream.batch.clone().unwrap_or_default();
+  let mut assign = |expr: &mut Expression| match expr {
+    Expression::Unsafe(Operation::Endpoint(req_template, group_by, dl)) => {
+      let http_client = match &req_template.endpoint.upstream {
+        Some(name) => &upstream_clients[name],
+        None => &http_client,
+      };
+      let batch = http_client.upstream().batch.clone().unwrap_or_default();
+      let data_loader = HttpDataLoader::new(http_client.clone(), group_by.clone()).to_data_loader(batch);
+      *dl = Some(Arc::new(data_loader));
+    }
+    Expression::Unsafe(Operation::GraphQLEndpoint(_, batched, dl)) => {
+      let data_loader = GraphqlDataLoader::new(http_client.clone(), *batched).to_data_loader(batch.clone());
+      *dl = Some(Arc::new(data_loader));
+    }
+    Expression::Unsafe(Operation::Grpc(req_template, group_by, dl)) => {
+      let data_loader = GrpcDataLoader::new(
+        http2_only_client.clone(),
+        req_template.operation.clone(),
+        group_by.clone(),
+      )
+      .list(req_template.list)
+      .to_data_loader(batch.clone());
+      *dl = Some(Arc::new(data_loader));
+    }
+    _ => {}
+  };
   for def in blueprint.definitions.iter_mut() {
     if let Definition::ObjectTypeDefinition(def) = def {
       for field in &mut def.fields {
-        if let Some(Expression::Unsafe(Operation::Endpoint(req_template, group_by, _))) = &mut field.resolver {
-          let data_loader = HttpDataLoader::new(http_client.clone(), group_by.clone())
-            .to_data_loader(blueprint.upstream.batch.clone().unwrap_or_default());
-          field.resolver = Some(Expression::Unsafe(Operation::Endpoint(
-            req_template.clone(),
-            group_by.clone(),
-            Some(Arc::new(data_loader)),
-          )));
+        // Cached operations, and the steps of pipelines, still go through the data loaders
+        if let Some(resolver) = field.resolver.as_mut() {
+          resolver.walk_mut(&mut assign);
+        }
+      }
+    }
+  }
+  share_data_loaders(blueprint)
+}
+
+/// Points the operations of `@call` to the data loaders of the query fields they call,
+/// so that they're batched together with the query fields.
+/// The operation of a field is the one that produces its value, through calls and the last step of pipelines.
+fn share_data_loaders(blueprint: &mut Blueprint) -> &Blueprint {
+  let query = blueprint.query();
+  let operations: HashMap<String, Operation> = blueprint
+    .definitions
+    .iter()
+    .filter_map(|def| match def {
+      Definition::ObjectTypeDefinition(def) if def.name == query => Some(def),
+      _ => None,
+    })
+    .flat_map(|def| def.fields.iter())
+    .filter_map(|field| {
+      Some((
+        field.name.clone(),
+        resolved_operation(field.resolver.as_ref()?)?.clone(),
+      ))
+    })
+    .collect();
+
+  let mut share = |expr: &mut Expression| {
+    let Expression::Call(call) = expr else {
+      return;
+    };
+    let Some(target) = operations.get(call.query()) else {
+      return;
+    };
+    match (call.expression_mut().and_then(resolved_operation_mut), target) {
+      (Some(Operation::Endpoint(_, _, dl)), Operation::Endpoint(_, _, target_dl)) => *dl = target_dl.clone(),
+      (Some(Operation::GraphQLEndpoint(_, _, dl)), Operation::GraphQLEndpoint(_, _, target_dl)) => {
+        *dl = target_dl.clone()
+      }
+      (Some(Operation::Grpc(_, _, dl)), Operation::Grpc(_, _, target_dl)) => *dl = target_dl.clone(),
+      _ => {}
+    }
+  };
+  for def in blueprint.definitions.iter_mut() {
+    if let Definition::ObjectTypeDefinition(def) = def {
+      for field in &mut def.fields {
+        if let Some(resolver) = field.resolver.as_mut() {
+          resolver.walk_mut(&mut share);
         }
       }
     }
@@ -33,10 +119,61 @@ fn assign_data_loaders(blueprint: &mut Blueprint, http_client: DefaultHttpClient
   blueprint
 }
 
+fn resolved_operation(expr: &Expression) -> Option<&Operation> {
+  match expr {
+    Expression::Unsafe(operation) => Some(operation),
+    Expression::Cache(cache) => resolved_operation(cache.expression()),
+    Expression::Call(call) => resolved_operation(call.expression()?),
+    Expression::Protected(expr) | Expression::Pipe(_, expr) => resolved_operation(expr),
+    _ => None,
+  }
+}
+
+fn resolved_operation_mut(expr: &mut Expression) -> Option<&mut Operation> {
+  match expr {
+    Expression::Unsafe(operation) => Some(operation),
+    Expression::Cache(cache) => resolved_operation_mut(cache.expression_mut()),
+    Expression::Call(call) => resolved_operation_mut(call.expression_mut()?),
+    Expression::Protected(expr) | Expression::Pipe(_, expr) => resolved_operation_mut(expr),
+    _ => None,
+  }
+}
+
 impl ServerContext {
-  pub fn new(blueprint: Blueprint) -> Self {
-    let http_client = DefaultHttpClient::new(blueprint.upstream.clone());
-    let schema = assign_data_loaders(&mut blueprint.clone(), http_client.clone()).to_schema();
-    ServerContext { schema, http_client, blueprint }
+  pub fn new(blueprint: Blueprint) -> anyhow::Result<Self> {
+    let insecure = std::iter::once(&blueprint.upstream)
+      .chain(blueprint.upstreams.values())
+      .filter_map(|upstream| upstream.tls.as_ref())
+      .chain(blueprint.tls_hosts.values())
+      .any(|tls| tls.insecure_skip_verify);
+    if insecure {
+      log::warn!("The certificates of some upstreams aren't verified, as insecureSkipVerify is enabled");
+    }
+    let http_client = DefaultHttpClient::new(blueprint.upstream.clone())?.tls_hosts(&blueprint.tls_hosts)?;
+    let http2_only_client =
+      DefaultHttpClient::with_http2_only(blueprint.upstream.clone())?.tls_hosts(&blueprint.tls_hosts)?;
+    let upstream_clients: HashMap<_, _> = blueprint
+      .upstreams
+      .iter()
+      .map(|(name, upstream)| {
+        let http_client = DefaultHttpClient::new(upstream.clone())?.tls_hosts(&blueprint.tls_hosts)?;
+        Ok((name.clone(), http_client))
+      })
+      .collect::<anyhow::Result<_>>()?;
+    let schema = assign_data_loaders(
+      &mut blueprint.clone(),
+      http_client.clone(),
+      http2_only_client.clone(),
+      &upstream_clients,
+    )
+    .to_schema();
+    Ok(ServerContext {
+      schema,
+      http_client,
+      http2_only_client,
+      upstream_clients: Arc::new(upstream_clients),
+      blueprint,
+      cache: TtlCache::default(),
+    })
   }
 }
@@ -0,0 +1,70 @@
+use std::fs::File;
+use std::io::BufReader;
+
+use anyhow::{anyhow, Context, Result};
+use rustls::{Certificate, PrivateKey, ServerConfig};
+
+use crate::config;
+
+/// Builds the TLS config of the server from the certificate chain and private key of the PEM files.
+/// HTTP/2 is preferred over HTTP/1.1 when the client supports both.
+pub fn server_config(cert: &str, key: &str) -> Result<ServerConfig> {
+  let mut config = ServerConfig::builder()
+    .with_safe_defaults()
+    .with_no_client_auth()
+    .with_single_cert(load_certs(cert)?, load_private_key(key)?)
+    .map_err(|e| anyhow!("Invalid certificate or private key: {e}"))?;
+  config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+  Ok(config)
+}
+
+pub fn load_certs(path: &str) -> Result<Vec<Certificate>> {
+  let file = File::open(path).with_context(|| format!("Couldn't read certificate file '{path}'"))?;
+  let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+    .with_context(|| format!("Couldn't parse certificate file '{path}'"))?;
+  if certs.is_empty() {
+    return Err(anyhow!("No certificate found in '{path}'"));
+  }
+  Ok(certs.into_iter().map(Certificate).collect())
+}
+
+/// Reads the first PKCS#8, PKCS#1 or SEC1 private key of the file.
+pub fn load_private_key(path: &str) -> Result<PrivateKey> {
+  let file = File::open(path).with_context(|| format!("Couldn't read private key file '{path}'"))?;
+  let mut reader = BufReader::new(file);
+  loop {
+    match rustls_pemfile::read_one(&mut reader).with_context(|| format!("Couldn't parse private key file '{path}'"))? {
+      Some(
+        rustls_pemfile::Item::PKCS8Key(key) | rustls_pemfile::Item::RSAKey(key) | rustls_pemfile::Item::ECKey(key),
+      ) => return Ok(PrivateKey(key)),
+      Some(_) => continue,
+      None => return Err(anyhow!("No private key found in '{path}'")),
+    }
+  }
+}
+
+/// Applies the TLS settings of an upstream to the builder of its client.
+/// The CA certificates are trusted in addition to the built-in roots.
+pub fn client_tls(mut builder: reqwest::ClientBuilder, tls: &config::UpstreamTls) -> Result<reqwest::ClientBuilder> {
+  if let Some(path) = &tls.ca_cert {
+    for cert in load_certs(path)? {
+      builder = builder.add_root_certificate(reqwest::Certificate::from_der(&cert.0)?);
+    }
+  }
+
+  match (&tls.client_cert, &tls.client_key) {
+    (Some(cert), Some(key)) => {
+      // Both files are parsed first, so that their errors name the file at fault
+      load_certs(cert)?;
+      load_private_key(key)?;
+      let mut pem = std::fs::read(key)?;
+      pem.extend(std::fs::read(cert)?);
+      let identity = reqwest::Identity::from_pem(&pem).map_err(|e| anyhow!("Invalid client certificate: {e}"))?;
+      builder = builder.identity(identity);
+    }
+    (None, None) => {}
+    _ => return Err(anyhow!("clientCert and clientKey must be set together")),
+  }
+
+  Ok(builder.danger_accept_invalid_certs(tls.insecure_skip_verify))
+}
@@ -0,0 +1,40 @@
+use std::time::Instant;
+
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use task_local_extensions::Extensions;
+
+use crate::metrics::metrics;
+
+/// Middleware that counts and times the requests sent to upstreams.
+/// Requests that fail without a response are counted with an `error` status.
+pub struct UpstreamMetrics;
+
+#[async_trait::async_trait]
+impl Middleware for UpstreamMetrics {
+  async fn handle(
+    &self,
+    request: Request,
+    extensions: &mut Extensions,
+    next: Next<'_>,
+  ) -> reqwest_middleware::Result<Response> {
+    let host = request.url().host_str().unwrap_or_default().to_string();
+    let method = request.method().to_string();
+    let start = Instant::now();
+
+    let result = next.run(request, extensions).await;
+    let status = match &result {
+      Ok(response) => response.status().as_u16().to_string(),
+      Err(_) => "error".to_string(),
+    };
+    metrics()
+      .upstream_requests
+      .with_label_values(&[&host, &method, &status])
+      .inc();
+    metrics()
+      .upstream_request_duration
+      .with_label_values(&[&host, &method])
+      .observe(start.elapsed().as_secs_f64());
+    result
+  }
+}
@@ -0,0 +1,48 @@
+use opentelemetry::trace::{FutureExt, SpanKind, Status, TraceContextExt};
+use opentelemetry::{Context, KeyValue};
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use task_local_extensions::Extensions;
+
+use crate::telemetry;
+
+/// Middleware that creates a span for every request sent to an upstream,
+/// and propagates the trace to the upstream with the `traceparent` header.
+pub struct UpstreamTracing;
+
+#[async_trait::async_trait]
+impl Middleware for UpstreamTracing {
+  async fn handle(
+    &self,
+    mut request: Request,
+    extensions: &mut Extensions,
+    next: Next<'_>,
+  ) -> reqwest_middleware::Result<Response> {
+    let method = request.method().to_string();
+    let cx = telemetry::start_span(
+      &Context::current(),
+      format!("HTTP {method}"),
+      SpanKind::Client,
+      vec![
+        KeyValue::new("http.request.method", method),
+        KeyValue::new("url.full", request.url().to_string()),
+      ],
+    );
+    telemetry::inject(&cx, request.headers_mut());
+
+    let result = next.run(request, extensions).with_context(cx.clone()).await;
+    match &result {
+      Ok(response) => {
+        let status = response.status();
+        cx.span()
+          .set_attribute(KeyValue::new("http.response.status_code", status.as_u16() as i64));
+        if status.is_server_error() {
+          cx.span().set_status(Status::error(status.to_string()));
+        }
+      }
+      Err(error) => cx.span().set_status(Status::error(error.to_string())),
+    }
+    cx.span().end();
+    result
+  }
+}
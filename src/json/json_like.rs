@@ -298,7 +298,7 @@ mod tests {
 
   #[test]
   fn test_group_by_key() {
-    let arr = vec![
+    let arr = [
       (json!("1"), json!({"id": "1"})),
       (json!("2"), json!({"id": "2"})),
       (json!("2"), json!({"id": "2"})),
@@ -321,7 +321,7 @@ mod tests {
 
   #[test]
   fn test_group_by_numeric_key() {
-    let arr = vec![
+    let arr = [
       (json!(1), json!({"id": 1})),
       (json!(2), json!({"id": 2})),
       (json!(2), json!({"id": 2})),
@@ -0,0 +1,172 @@
+use std::num::NonZeroU64;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_graphql_value::ConstValue;
+
+use super::{EvaluationContext, Expression, Operation, ResolverContextLike};
+use crate::metrics::metrics;
+
+/// Cache keeps the resolved value of an IO operation for `max_age` seconds.
+/// Values are keyed by the full rendered request, including every header sent upstream.
+#[derive(Clone, Debug)]
+pub struct Cache {
+  max_age: NonZeroU64,
+  expr: Box<Expression>,
+}
+
+impl Cache {
+  /// Wraps every IO operation of the expression with a cache.
+  pub fn wrap(max_age: NonZeroU64, expr: Expression) -> Expression {
+    match expr {
+      Expression::Unsafe(Operation::JS(input, script)) => {
+        Expression::Unsafe(Operation::JS(Box::new(Cache::wrap(max_age, *input)), script))
+      }
+      Expression::Unsafe(operation) => {
+        Expression::Cache(Cache { max_age, expr: Box::new(Expression::Unsafe(operation)) })
+      }
+      Expression::Input(input, path) => Expression::Input(Box::new(Cache::wrap(max_age, *input)), path),
+      Expression::EqualTo(left, right) => Expression::EqualTo(
+        Box::new(Cache::wrap(max_age, *left)),
+        Box::new(Cache::wrap(max_age, *right)),
+      ),
+      Expression::Call(call) => Expression::Call(call.map_expression(|expr| Cache::wrap(max_age, expr))),
+      Expression::Pipe(input, expr) => Expression::Pipe(
+        Box::new(Cache::wrap(max_age, *input)),
+        Box::new(Cache::wrap(max_age, *expr)),
+      ),
+      expr => expr,
+    }
+  }
+
+  pub fn max_age(&self) -> NonZeroU64 {
+    self.max_age
+  }
+
+  pub fn expression(&self) -> &Expression {
+    &self.expr
+  }
+
+  pub fn expression_mut(&mut self) -> &mut Expression {
+    &mut self.expr
+  }
+
+  pub async fn eval<'a, Ctx: ResolverContextLike<'a> + Sync + Send>(
+    &'a self,
+    ctx: &'a EvaluationContext<'a, Ctx>,
+  ) -> Result<ConstValue> {
+    let Some(key) = self.key(ctx)? else {
+      return self.expr.eval(ctx).await;
+    };
+
+    if let Some((value, ttl)) = ctx.req_ctx.cache.get(&key).await {
+      metrics().cache_hits.inc();
+      set_min_max_age(ctx, ttl.as_secs());
+      return Ok(value);
+    }
+    metrics().cache_misses.inc();
+
+    let value = self.expr.eval(ctx).await?;
+    ctx
+      .req_ctx
+      .cache
+      .insert(key, (value.clone(), Duration::from_secs(self.max_age.get())))
+      .await;
+    set_min_max_age(ctx, self.max_age.get());
+
+    Ok(value)
+  }
+
+  /// Renders the request of the operation into a cache key.
+  fn key<'a, Ctx: ResolverContextLike<'a> + Sync + Send>(
+    &'a self,
+    ctx: &'a EvaluationContext<'a, Ctx>,
+  ) -> Result<Option<CacheKey>> {
+    let req = match self.expr.as_ref() {
+      Expression::Unsafe(Operation::Endpoint(req_template, _, _)) => req_template.to_request(ctx)?,
+      Expression::Unsafe(Operation::GraphQLEndpoint(req_template, _, _)) => req_template.to_request(ctx)?,
+      Expression::Unsafe(Operation::Grpc(req_template, _, _)) => req_template.to_request(ctx)?,
+      _ => return Ok(None),
+    };
+
+    Ok(Some(CacheKey::from(&req)))
+  }
+}
+
+/// The method, url, headers and body of a rendered request.
+/// Two operations share a cached value only if all of them are equal.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct CacheKey {
+  method: String,
+  url: String,
+  headers: Vec<(String, Vec<u8>)>,
+  body: Option<Vec<u8>>,
+}
+
+impl From<&reqwest::Request> for CacheKey {
+  fn from(req: &reqwest::Request) -> Self {
+    let mut headers = req
+      .headers()
+      .iter()
+      .map(|(name, value)| (name.as_str().to_string(), value.as_bytes().to_vec()))
+      .collect::<Vec<_>>();
+    headers.sort();
+
+    CacheKey {
+      method: req.method().to_string(),
+      url: req.url().to_string(),
+      headers,
+      body: req.body().and_then(|body| body.as_bytes()).map(|body| body.to_vec()),
+    }
+  }
+}
+
+fn set_min_max_age<'a, Ctx: ResolverContextLike<'a>>(ctx: &EvaluationContext<'a, Ctx>, max_age: u64) {
+  if ctx.req_ctx.server.get_enable_cache_control() {
+    ctx.req_ctx.set_min_max_age(max_age);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::num::NonZeroU64;
+
+  use reqwest::header::{HeaderValue, AUTHORIZATION};
+
+  use super::{Cache, CacheKey};
+  use crate::lambda::{Expression, Lambda};
+  use crate::request_template::RequestTemplate;
+
+  #[test]
+  fn test_wrap_operation() {
+    let max_age = NonZeroU64::new(60).unwrap();
+    let req_template = RequestTemplate::new("http://localhost:3000/users").unwrap();
+    let expr = Lambda::<serde_json::Value>::from_request_template(req_template).expression;
+    let expr = Cache::wrap(max_age, Expression::Input(Box::new(expr), vec!["name".to_string()]));
+
+    let Expression::Input(input, _) = expr else {
+      panic!("Expected the input to be preserved")
+    };
+    assert!(matches!(*input, Expression::Cache(cache) if cache.max_age() == max_age));
+  }
+
+  #[test]
+  fn test_wrap_literal() {
+    let expr = Cache::wrap(NonZeroU64::new(60).unwrap(), Expression::Literal(serde_json::json!(1)));
+    assert!(matches!(expr, Expression::Literal(_)));
+  }
+
+  #[test]
+  fn test_key_includes_headers() {
+    let request = |token: &str| {
+      let mut req = reqwest::Request::new(reqwest::Method::GET, "http://localhost:3000/users".parse().unwrap());
+      req
+        .headers_mut()
+        .insert(AUTHORIZATION, HeaderValue::from_str(token).unwrap());
+      CacheKey::from(&req)
+    };
+
+    assert_eq!(request("Bearer a"), request("Bearer a"));
+    assert_ne!(request("Bearer a"), request("Bearer b"));
+  }
+}
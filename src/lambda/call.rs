@@ -0,0 +1,72 @@
+use anyhow::{anyhow, Result};
+use async_graphql::Name;
+use async_graphql_value::ConstValue;
+use indexmap::IndexMap;
+
+use super::{CallContext, EvaluationContext, Expression, ResolverContextLike};
+
+/// Call resolves a field with the resolver of a field of the query type, and the arguments of its own.
+/// The resolver is evaluated outside of the parent value of the field, as the query field would be,
+/// with the selection of the calling field.
+/// The resolver is linked once every field of the query type is compiled.
+#[derive(Clone, Debug)]
+pub struct Call {
+  query: String,
+  args: Vec<(String, Expression)>,
+  expr: Option<Box<Expression>>,
+}
+
+impl Call {
+  pub fn new(query: String, args: Vec<(String, Expression)>) -> Self {
+    Self { query, args, expr: None }
+  }
+
+  /// The name of the called field of the query type.
+  pub fn query(&self) -> &str {
+    &self.query
+  }
+
+  /// The resolver of the called field, once it's linked.
+  pub fn expression(&self) -> Option<&Expression> {
+    self.expr.as_deref()
+  }
+
+  pub fn expression_mut(&mut self) -> Option<&mut Expression> {
+    self.expr.as_deref_mut()
+  }
+
+  pub fn link(&mut self, expr: Expression) {
+    self.expr = Some(Box::new(expr));
+  }
+
+  /// The expressions of the arguments, followed by the called resolver.
+  pub fn expressions_mut(&mut self) -> impl Iterator<Item = &mut Expression> {
+    self
+      .args
+      .iter_mut()
+      .map(|(_, expr)| expr)
+      .chain(self.expr.as_deref_mut())
+  }
+
+  pub fn map_expression(mut self, f: impl FnOnce(Expression) -> Expression) -> Self {
+    self.expr = self.expr.map(|expr| Box::new(f(*expr)));
+    self
+  }
+
+  pub async fn eval<'a, Ctx: ResolverContextLike<'a> + Sync + Send>(
+    &'a self,
+    ctx: &'a EvaluationContext<'a, Ctx>,
+  ) -> Result<ConstValue> {
+    let mut args = IndexMap::new();
+    for (name, expr) in self.args.iter() {
+      args.insert(Name::new(name), expr.eval(ctx).await?);
+    }
+    let call_ctx = CallContext::new(args, ctx.graphql_ctx.field());
+    let ctx = EvaluationContext::new(ctx.req_ctx, &call_ctx).timeout(ctx.timeout);
+    let expr = self
+      .expr
+      .as_ref()
+      .ok_or_else(|| anyhow!("query '{}' isn't linked", self.query))?;
+    expr.eval(&ctx).await
+  }
+}
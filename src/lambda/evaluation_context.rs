@@ -1,10 +1,10 @@
 use std::time::Duration;
 
-use async_graphql::{Name, Value};
+use async_graphql::{Name, SelectionField, Value};
 use derive_setters::Setters;
 use reqwest::header::HeaderMap;
 
-use super::{EmptyResolverContext, ResolverContextLike};
+use super::{EmptyResolverContext, GraphQLOperationContext, ResolverContextLike};
 use crate::http::RequestContext;
 
 // TODO: rename to ResolverContext
@@ -64,6 +64,52 @@ impl<'a, Ctx: ResolverContextLike<'a>> EvaluationContext<'a, Ctx> {
   }
 }
 
+impl<'a, Ctx: ResolverContextLike<'a>> GraphQLOperationContext for EvaluationContext<'a, Ctx> {
+  fn selection_set(&self) -> Option<String> {
+    let selection_set = self.graphql_ctx.field()?.selection_set();
+
+    format_selection_set(selection_set)
+  }
+}
+
+fn format_selection_set<'a>(selection_set: impl Iterator<Item = SelectionField<'a>>) -> Option<String> {
+  let set = selection_set.map(format_selection_field).collect::<Vec<_>>();
+
+  if set.is_empty() {
+    return None;
+  }
+
+  Some(format!("{{ {} }}", set.join(" ")))
+}
+
+fn format_selection_field(field: SelectionField) -> String {
+  let name = field.name();
+  let arguments = format_selection_field_arguments(field);
+  let selection_set = format_selection_set(field.selection_set());
+
+  if let Some(set) = selection_set {
+    format!("{name}{arguments} {set}")
+  } else {
+    format!("{name}{arguments}")
+  }
+}
+
+fn format_selection_field_arguments(field: SelectionField) -> String {
+  let arguments = field.arguments().unwrap_or_default();
+
+  if arguments.is_empty() {
+    return String::new();
+  }
+
+  let arguments = arguments
+    .iter()
+    .map(|(name, value)| format!("{name}: {value}"))
+    .collect::<Vec<_>>()
+    .join(", ");
+
+  format!("({arguments})")
+}
+
 fn get_path_value<'a, T: AsRef<str>>(input: &'a Value, path: &[T]) -> Option<&'a Value> {
   let mut value = Some(input);
   for name in path {
@@ -9,9 +9,11 @@ use serde::Serialize;
 use serde_json::Value;
 use thiserror::Error;
 
-use super::ResolverContextLike;
+use super::{Cache, Call, ItemContext, ResolverContextLike};
 use crate::config::group_by::GroupBy;
-use crate::http::{max_age, DefaultHttpClient, HttpDataLoader};
+use crate::graphql_request_template::GraphqlRequestTemplate;
+use crate::grpc::{execute_grpc_request, GrpcDataLoader, GrpcRequestTemplate};
+use crate::http::{max_age, DataLoaderRequest, DefaultHttpClient, GraphqlDataLoader, HttpDataLoader, Response};
 #[cfg(feature = "unsafe-js")]
 use crate::javascript;
 use crate::json::JsonLike;
@@ -19,27 +21,78 @@ use crate::lambda::EvaluationContext;
 use crate::request_template::RequestTemplate;
 
 #[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum Expression {
   Context(Context),
   Literal(Value), // TODO: this should async_graphql::Value
   EqualTo(Box<Expression>, Box<Expression>),
   Unsafe(Operation),
   Input(Box<Expression>, Vec<String>),
+  Cache(Cache),
+  /// Evaluates the expression only for authenticated requests.
+  Protected(Box<Expression>),
+  If(Box<Expression>, Box<Expression>, Box<Expression>),
+  And(Vec<Expression>),
+  Or(Vec<Expression>),
+  Not(Box<Expression>),
+  Compare(Comparison, Box<Expression>, Box<Expression>),
+  Math(MathOperation, Box<Expression>, Box<Expression>),
+  /// Joins the values into a string, rendering `null` as an empty string.
+  Concat(Vec<Expression>),
+  /// Evaluates to the first value that isn't `null`.
+  Coalesce(Vec<Expression>),
+  /// Evaluates the second expression for every item of the list, with the item as its `value`.
+  Map(Box<Expression>, Box<Expression>),
+  /// Keeps the items of the list for which the second expression is true, with the item as its `value`.
+  Filter(Box<Expression>, Box<Expression>),
+  /// Evaluates the resolver of a field of the query type, as `@call` does.
+  Call(Call),
+  /// Evaluates the second expression with the value of the first as its `value`, as the steps of `@pipeline` do.
+  Pipe(Box<Expression>, Box<Expression>),
 }
 
 #[derive(Clone, Debug)]
 pub enum Context {
   Value,
   Path(Vec<String>),
+  Args(Vec<String>),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Comparison {
+  Lt,
+  Lte,
+  Gt,
+  Gte,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum MathOperation {
+  Add,
+  Sub,
+  Mul,
+  Div,
+  Mod,
 }
 
 #[derive(Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum Operation {
   Endpoint(
     RequestTemplate,
     Option<GroupBy>,
     Option<Arc<DataLoader<HttpDataLoader<DefaultHttpClient>, NoCache>>>,
   ),
+  GraphQLEndpoint(
+    GraphqlRequestTemplate,
+    bool,
+    Option<Arc<DataLoader<GraphqlDataLoader<DefaultHttpClient>, NoCache>>>,
+  ),
+  Grpc(
+    GrpcRequestTemplate,
+    Option<GroupBy>,
+    Option<Arc<DataLoader<GrpcDataLoader<DefaultHttpClient>, NoCache>>>,
+  ),
   JS(Box<Expression>, String),
 }
 
@@ -52,6 +105,16 @@ impl Debug for Operation {
         .field("group_by", group_by)
         .field("dl", &dl.clone().map(|a| a.clone().loader().batched.clone()))
         .finish(),
+      Operation::GraphQLEndpoint(req_template, batch, _) => f
+        .debug_struct("GraphQLEndpoint")
+        .field("req_template", req_template)
+        .field("batch", batch)
+        .finish(),
+      Operation::Grpc(req_template, group_by, _) => f
+        .debug_struct("Grpc")
+        .field("req_template", req_template)
+        .field("group_by", group_by)
+        .finish(),
       Operation::JS(input, script) => f
         .debug_struct("JS")
         .field("input", input)
@@ -71,6 +134,15 @@ pub enum EvaluationError {
 
   #[error("APIValidationError: {0:?}")]
   APIValidationError(Vec<String>),
+
+  #[error("GraphQLError: {0}")]
+  GraphQLError(String),
+
+  #[error("Unauthenticated")]
+  Unauthenticated,
+
+  #[error("ExpressionError: {0}")]
+  ExpressionError(String),
 }
 
 impl<'a> From<crate::valid::ValidationError<&'a str>> for EvaluationError {
@@ -80,6 +152,55 @@ impl<'a> From<crate::valid::ValidationError<&'a str>> for EvaluationError {
 }
 
 impl Expression {
+  /// Returns the IO operation of the expression, looking through the `@cache` and `@protected` wrappers.
+  pub fn operation(&self) -> Option<&Operation> {
+    match self {
+      Expression::Unsafe(operation) => Some(operation),
+      Expression::Cache(cache) => cache.expression().operation(),
+      Expression::Protected(expr) => expr.operation(),
+      _ => None,
+    }
+  }
+
+  pub fn operation_mut(&mut self) -> Option<&mut Operation> {
+    match self {
+      Expression::Unsafe(operation) => Some(operation),
+      Expression::Cache(cache) => cache.expression_mut().operation_mut(),
+      Expression::Protected(expr) => expr.operation_mut(),
+      _ => None,
+    }
+  }
+
+  /// Calls `f` on the expression, and then on every expression nested in it.
+  pub fn walk_mut(&mut self, f: &mut impl FnMut(&mut Expression)) {
+    f(self);
+    match self {
+      Expression::Context(_) | Expression::Literal(_) => {}
+      Expression::Unsafe(Operation::JS(input, _)) => input.walk_mut(f),
+      Expression::Unsafe(_) => {}
+      Expression::Input(expr, _) | Expression::Protected(expr) | Expression::Not(expr) => expr.walk_mut(f),
+      Expression::Cache(cache) => cache.expression_mut().walk_mut(f),
+      Expression::Call(call) => call.expressions_mut().for_each(|expr| expr.walk_mut(f)),
+      Expression::EqualTo(left, right)
+      | Expression::Compare(_, left, right)
+      | Expression::Math(_, left, right)
+      | Expression::Map(left, right)
+      | Expression::Filter(left, right)
+      | Expression::Pipe(left, right) => {
+        left.walk_mut(f);
+        right.walk_mut(f);
+      }
+      Expression::If(cond, then, otherwise) => {
+        cond.walk_mut(f);
+        then.walk_mut(f);
+        otherwise.walk_mut(f);
+      }
+      Expression::And(exprs) | Expression::Or(exprs) | Expression::Concat(exprs) | Expression::Coalesce(exprs) => {
+        exprs.iter_mut().for_each(|expr| expr.walk_mut(f))
+      }
+    }
+  }
+
   pub fn eval<'a, Ctx: ResolverContextLike<'a> + Sync + Send>(
     &'a self,
     ctx: &'a EvaluationContext<'a, Ctx>,
@@ -89,29 +210,116 @@ impl Expression {
         Expression::Context(op) => match op {
           Context::Value => Ok(ctx.value().cloned().unwrap_or(async_graphql::Value::Null)),
           Context::Path(path) => Ok(ctx.path_value(path).cloned().unwrap_or(async_graphql::Value::Null)),
+          Context::Args(path) => Ok(ctx.arg(path).cloned().unwrap_or(async_graphql::Value::Null)),
         },
         Expression::Input(input, path) => {
           let inp = &input.eval(ctx).await?;
           Ok(inp.get_path(path).unwrap_or(&async_graphql::Value::Null).clone())
         }
         Expression::Literal(value) => Ok(serde_json::from_value(value.clone())?),
+        Expression::Cache(cache) => cache.eval(ctx).await,
+        Expression::Call(call) => call.eval(ctx).await,
+        Expression::Pipe(input, expr) => {
+          let input_ctx = ItemContext::new(input.eval(ctx).await?, ctx.graphql_ctx.args().cloned());
+          let ctx = EvaluationContext::new(ctx.req_ctx, &input_ctx).timeout(ctx.timeout);
+          expr.eval(&ctx).await
+        }
+        Expression::Protected(expr) => match ctx.req_ctx.identity {
+          Some(_) => expr.eval(ctx).await,
+          None => Err(EvaluationError::Unauthenticated.into()),
+        },
         Expression::EqualTo(left, right) => Ok(async_graphql::Value::from(
           left.eval(ctx).await? == right.eval(ctx).await?,
         )),
+        Expression::If(cond, then, otherwise) => {
+          if is_true(&cond.eval(ctx).await?)? {
+            then.eval(ctx).await
+          } else {
+            otherwise.eval(ctx).await
+          }
+        }
+        Expression::And(exprs) => {
+          for expr in exprs {
+            if !is_true(&expr.eval(ctx).await?)? {
+              return Ok(async_graphql::Value::from(false));
+            }
+          }
+          Ok(async_graphql::Value::from(true))
+        }
+        Expression::Or(exprs) => {
+          for expr in exprs {
+            if is_true(&expr.eval(ctx).await?)? {
+              return Ok(async_graphql::Value::from(true));
+            }
+          }
+          Ok(async_graphql::Value::from(false))
+        }
+        Expression::Not(expr) => Ok(async_graphql::Value::from(!is_true(&expr.eval(ctx).await?)?)),
+        Expression::Compare(comparison, left, right) => {
+          Ok(compare(*comparison, &left.eval(ctx).await?, &right.eval(ctx).await?)?)
+        }
+        Expression::Math(operation, left, right) => {
+          Ok(math(*operation, &left.eval(ctx).await?, &right.eval(ctx).await?)?)
+        }
+        Expression::Concat(exprs) => {
+          let mut result = String::new();
+          for expr in exprs {
+            match expr.eval(ctx).await? {
+              async_graphql::Value::Null => {}
+              async_graphql::Value::String(value) => result.push_str(&value),
+              value => result.push_str(&value.to_string()),
+            }
+          }
+          Ok(async_graphql::Value::String(result))
+        }
+        Expression::Coalesce(exprs) => {
+          for expr in exprs {
+            let value = expr.eval(ctx).await?;
+            if value != async_graphql::Value::Null {
+              return Ok(value);
+            }
+          }
+          Ok(async_graphql::Value::Null)
+        }
+        Expression::Map(list, expr) => {
+          let Some(items) = as_list(list.eval(ctx).await?)? else {
+            return Ok(async_graphql::Value::Null);
+          };
+          let mut result = Vec::with_capacity(items.len());
+          for item in items {
+            let item_ctx = ItemContext::new(item, ctx.graphql_ctx.args().cloned());
+            let ctx = EvaluationContext::new(ctx.req_ctx, &item_ctx).timeout(ctx.timeout);
+            result.push(expr.eval(&ctx).await?);
+          }
+          Ok(async_graphql::Value::List(result))
+        }
+        Expression::Filter(list, expr) => {
+          let Some(items) = as_list(list.eval(ctx).await?)? else {
+            return Ok(async_graphql::Value::Null);
+          };
+          let mut result = Vec::new();
+          for item in items {
+            let item_ctx = ItemContext::new(item, ctx.graphql_ctx.args().cloned());
+            let keep = {
+              let ctx = EvaluationContext::new(ctx.req_ctx, &item_ctx).timeout(ctx.timeout);
+              is_true(&expr.eval(&ctx).await?)?
+            };
+            if keep {
+              result.push(item_ctx.into_value());
+            }
+          }
+          Ok(async_graphql::Value::List(result))
+        }
         Expression::Unsafe(operation) => {
           match operation {
             Operation::Endpoint(req_template, _, dl) => {
               let req = req_template.to_request(ctx)?;
               let is_get = req.method() == reqwest::Method::GET;
+              let upstream = req_template.endpoint.upstream.as_deref();
+              let batch = &ctx.req_ctx.upstream_of(upstream).batch;
               // Attempt to short circuit GET request
-              if is_get && ctx.req_ctx.upstream.batch.is_some() {
-                let headers = ctx
-                  .req_ctx
-                  .upstream
-                  .batch
-                  .clone()
-                  .map(|s| s.headers)
-                  .unwrap_or_default();
+              if is_get && batch.is_some() {
+                let headers = batch.clone().map(|s| s.headers).unwrap_or_default();
                 let endpoint_key = crate::http::DataLoaderRequest::new(req, headers);
                 let resp = dl
                   .as_ref()
@@ -129,11 +337,13 @@ impl Expression {
               }
 
               // Prepare for HTTP calls
-              let res = ctx
-                .req_ctx
-                .execute(req)
-                .await
-                .map_err(|e| EvaluationError::IOException(e.to_string()))?;
+              let http_client = ctx.req_ctx.http_client_of(upstream);
+              let res = if req_template.endpoint.idempotent {
+                http_client.execute_idempotent(req).await
+              } else {
+                http_client.execute(req).await
+              }
+              .map_err(|e| EvaluationError::IOException(e.to_string()))?;
               if ctx.req_ctx.server.get_enable_http_validation() {
                 req_template
                   .endpoint
@@ -149,6 +359,60 @@ impl Expression {
               }
               Ok(res.body)
             }
+            Operation::GraphQLEndpoint(req_template, _, dl) => {
+              let req = req_template.to_request(ctx)?;
+              let res = if let Some(dl) = dl {
+                let headers = ctx
+                  .req_ctx
+                  .upstream
+                  .batch
+                  .clone()
+                  .map(|s| s.headers)
+                  .unwrap_or_default();
+                dl.load_one(DataLoaderRequest::new(req, headers))
+                  .await
+                  .map_err(|e| EvaluationError::IOException(e.to_string()))?
+                  .unwrap_or_default()
+              } else {
+                ctx
+                  .req_ctx
+                  .execute(req)
+                  .await
+                  .map_err(|e| EvaluationError::IOException(e.to_string()))?
+              };
+              if ctx.req_ctx.server.get_enable_cache_control() && res.status.is_success() {
+                if let Some(max_age) = max_age(&res) {
+                  ctx.req_ctx.set_min_max_age(max_age.as_secs());
+                }
+              }
+              Ok(parse_graphql_response(&res, &req_template.operation_name)?)
+            }
+            Operation::Grpc(req_template, _, dl) => {
+              let req = req_template.to_request(ctx)?;
+              let res = if let Some(dl) = dl {
+                let headers = ctx
+                  .req_ctx
+                  .upstream
+                  .batch
+                  .clone()
+                  .map(|s| s.headers)
+                  .unwrap_or_default();
+                dl.load_one(DataLoaderRequest::new(req, headers))
+                  .await
+                  .map_err(|e| EvaluationError::IOException(e.to_string()))?
+                  .unwrap_or_default()
+              } else {
+                execute_grpc_request(&ctx.req_ctx.http2_only_client, &req_template.operation, req)
+                  .await
+                  .map_err(|e| EvaluationError::IOException(e.to_string()))?
+              };
+              if ctx.req_ctx.server.get_enable_cache_control() && res.status.is_success() {
+                if let Some(max_age) = max_age(&res) {
+                  ctx.req_ctx.set_min_max_age(max_age.as_secs());
+                }
+              }
+              Ok(res.body)
+            }
             Operation::JS(input, script) => {
               let result;
               #[cfg(not(feature = "unsafe-js"))]
@@ -172,3 +436,132 @@ impl Expression {
     })
   }
 }
+
+/// `null` is false, and any other value than a boolean is an error.
+fn is_true(value: &async_graphql::Value) -> Result<bool, EvaluationError> {
+  match value {
+    async_graphql::Value::Boolean(value) => Ok(*value),
+    async_graphql::Value::Null => Ok(false),
+    value => Err(EvaluationError::ExpressionError(format!(
+      "expected a boolean, found '{value}'"
+    ))),
+  }
+}
+
+fn as_list(value: async_graphql::Value) -> Result<Option<Vec<async_graphql::Value>>, EvaluationError> {
+  match value {
+    async_graphql::Value::List(items) => Ok(Some(items)),
+    async_graphql::Value::Null => Ok(None),
+    value => Err(EvaluationError::ExpressionError(format!(
+      "expected a list, found '{value}'"
+    ))),
+  }
+}
+
+/// Numbers are compared with numbers and strings with strings. The comparisons with `null` are `null`.
+fn compare(
+  comparison: Comparison,
+  left: &async_graphql::Value,
+  right: &async_graphql::Value,
+) -> Result<async_graphql::Value, EvaluationError> {
+  use async_graphql::Value;
+
+  let ordering = match (left, right) {
+    (Value::Null, _) | (_, Value::Null) => return Ok(Value::Null),
+    (Value::Number(left), Value::Number(right)) => match (left.as_i64(), right.as_i64()) {
+      (Some(left), Some(right)) => left.partial_cmp(&right),
+      _ => left.as_f64().partial_cmp(&right.as_f64()),
+    },
+    (Value::String(left), Value::String(right)) => left.partial_cmp(right),
+    (left, right) => {
+      return Err(EvaluationError::ExpressionError(format!(
+        "can't compare '{left}' with '{right}'"
+      )))
+    }
+  };
+  let Some(ordering) = ordering else {
+    return Ok(Value::Null);
+  };
+  Ok(Value::from(match comparison {
+    Comparison::Lt => ordering.is_lt(),
+    Comparison::Lte => ordering.is_le(),
+    Comparison::Gt => ordering.is_gt(),
+    Comparison::Gte => ordering.is_ge(),
+  }))
+}
+
+/// Integers stay integers unless the result doesn't fit, or is a fraction. The operations on `null` are `null`.
+fn math(
+  operation: MathOperation,
+  left: &async_graphql::Value,
+  right: &async_graphql::Value,
+) -> Result<async_graphql::Value, EvaluationError> {
+  use async_graphql::Value;
+
+  let (left, right) = match (left, right) {
+    (Value::Null, _) | (_, Value::Null) => return Ok(Value::Null),
+    (Value::Number(left), Value::Number(right)) => (left, right),
+    (left, right) => {
+      return Err(EvaluationError::ExpressionError(format!(
+        "expected numbers, found '{left}' and '{right}'"
+      )))
+    }
+  };
+  if matches!(operation, MathOperation::Div | MathOperation::Mod) && right.as_f64() == Some(0.0) {
+    return Err(EvaluationError::ExpressionError("division by zero".to_string()));
+  }
+
+  if let (Some(left), Some(right)) = (left.as_i64(), right.as_i64()) {
+    let result = match operation {
+      MathOperation::Add => left.checked_add(right),
+      MathOperation::Sub => left.checked_sub(right),
+      MathOperation::Mul => left.checked_mul(right),
+      MathOperation::Div if left.checked_rem(right) == Some(0) => left.checked_div(right),
+      MathOperation::Div => None,
+      MathOperation::Mod => left.checked_rem(right),
+    };
+    if let Some(result) = result {
+      return Ok(Value::from(result));
+    }
+  }
+
+  let (left, right) = (left.as_f64().unwrap_or_default(), right.as_f64().unwrap_or_default());
+  let result = match operation {
+    MathOperation::Add => left + right,
+    MathOperation::Sub => left - right,
+    MathOperation::Mul => left * right,
+    MathOperation::Div => left / right,
+    MathOperation::Mod => left % right,
+  };
+  async_graphql::Number::from_f64(result)
+    .map(Value::Number)
+    .ok_or_else(|| EvaluationError::ExpressionError(format!("'{result}' is not a valid number")))
+}
+
+/// Extracts `data.<field_name>` out of an upstream GraphQL response,
+/// failing with the upstream errors if there are any.
+fn parse_graphql_response(res: &Response, field_name: &str) -> Result<async_graphql::Value, EvaluationError> {
+  if let Some(async_graphql::Value::List(errors)) = res.body.get_key("errors") {
+    if !errors.is_empty() {
+      let messages = errors
+        .iter()
+        .map(|error| {
+          error
+            .get_key("message")
+            .and_then(|message| message.as_str_ok().ok())
+            .unwrap_or("Unknown error")
+            .to_string()
+        })
+        .collect::<Vec<_>>();
+      return Err(EvaluationError::GraphQLError(messages.join(", ")));
+    }
+  }
+
+  Ok(
+    res
+      .body
+      .get_path(&["data", field_name])
+      .cloned()
+      .unwrap_or(async_graphql::Value::Null),
+  )
+}
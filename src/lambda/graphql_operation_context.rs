@@ -0,0 +1,3 @@
+pub trait GraphQLOperationContext {
+  fn selection_set(&self) -> Option<String>;
+}
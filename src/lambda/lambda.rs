@@ -70,7 +70,7 @@ mod tests {
 
   use crate::endpoint::Endpoint;
   use crate::http::RequestContext;
-  use crate::lambda::{EmptyResolverContext, EvaluationContext, Lambda};
+  use crate::lambda::{Comparison, EmptyResolverContext, EvaluationContext, Expression, Lambda, MathOperation};
   use crate::request_template::RequestTemplate;
 
   impl<B> Lambda<B>
@@ -118,6 +118,155 @@ mod tests {
     assert_eq!(result.as_object().unwrap().get("name").unwrap(), "Hans")
   }
 
+  fn literal(value: serde_json::Value) -> Box<Expression> {
+    Box::new(Expression::Literal(value))
+  }
+
+  #[tokio::test]
+  async fn test_if() {
+    let lambda = Lambda::<String>::new(Expression::If(
+      Box::new(Lambda::from(2).eq(Lambda::from(2)).expression),
+      literal(json!("yes")),
+      literal(json!("no")),
+    ));
+    assert_eq!(lambda.eval().await.unwrap(), "yes");
+
+    let lambda = Lambda::<String>::new(Expression::If(
+      literal(json!(null)),
+      literal(json!("yes")),
+      literal(json!("no")),
+    ));
+    assert_eq!(lambda.eval().await.unwrap(), "no");
+
+    let lambda = Lambda::<String>::new(Expression::If(
+      literal(json!(1)),
+      literal(json!("yes")),
+      literal(json!("no")),
+    ));
+    assert!(lambda.eval().await.is_err());
+  }
+
+  #[tokio::test]
+  async fn test_and_or_not() {
+    // The operands after the first false one aren't evaluated
+    let lambda = Lambda::<bool>::new(Expression::And(vec![
+      Expression::Literal(json!(false)),
+      Expression::Literal(json!(1)),
+    ]));
+    assert!(!lambda.eval().await.unwrap());
+
+    let lambda = Lambda::<bool>::new(Expression::Or(vec![
+      Expression::Literal(json!(null)),
+      Expression::Literal(json!(true)),
+    ]));
+    assert!(lambda.eval().await.unwrap());
+
+    let lambda = Lambda::<bool>::new(Expression::Not(literal(json!(true))));
+    assert!(!lambda.eval().await.unwrap());
+  }
+
+  #[tokio::test]
+  async fn test_compare() {
+    let compare = |comparison, left, right| {
+      Lambda::<Option<bool>>::new(Expression::Compare(comparison, literal(left), literal(right)))
+    };
+    assert_eq!(
+      compare(Comparison::Gte, json!(18), json!(18)).eval().await.unwrap(),
+      Some(true)
+    );
+    assert_eq!(
+      compare(Comparison::Lt, json!(1.5), json!(1)).eval().await.unwrap(),
+      Some(false)
+    );
+    assert_eq!(
+      compare(Comparison::Gt, json!("b"), json!("a")).eval().await.unwrap(),
+      Some(true)
+    );
+    assert_eq!(
+      compare(Comparison::Lte, json!(null), json!(1)).eval().await.unwrap(),
+      None
+    );
+    assert!(compare(Comparison::Lt, json!("1"), json!(1)).eval().await.is_err());
+  }
+
+  #[tokio::test]
+  async fn test_math() {
+    let math = |operation, left, right| {
+      Lambda::<serde_json::Value>::new(Expression::Math(operation, literal(left), literal(right)))
+    };
+    assert_eq!(
+      math(MathOperation::Add, json!(1), json!(2)).eval().await.unwrap(),
+      json!(3)
+    );
+    assert_eq!(
+      math(MathOperation::Sub, json!(1), json!(0.5)).eval().await.unwrap(),
+      json!(0.5)
+    );
+    assert_eq!(
+      math(MathOperation::Mul, json!(3), json!(4)).eval().await.unwrap(),
+      json!(12)
+    );
+    assert_eq!(
+      math(MathOperation::Div, json!(6), json!(3)).eval().await.unwrap(),
+      json!(2)
+    );
+    assert_eq!(
+      math(MathOperation::Div, json!(7), json!(2)).eval().await.unwrap(),
+      json!(3.5)
+    );
+    assert_eq!(
+      math(MathOperation::Mod, json!(7), json!(2)).eval().await.unwrap(),
+      json!(1)
+    );
+    assert_eq!(
+      math(MathOperation::Add, json!(null), json!(2)).eval().await.unwrap(),
+      json!(null)
+    );
+    assert!(math(MathOperation::Div, json!(1), json!(0)).eval().await.is_err());
+    assert!(math(MathOperation::Div, json!(i64::MIN), json!(-1))
+      .eval()
+      .await
+      .is_ok());
+    assert!(math(MathOperation::Add, json!("1"), json!(2)).eval().await.is_err());
+  }
+
+  #[tokio::test]
+  async fn test_concat_and_coalesce() {
+    let lambda = Lambda::<String>::new(Expression::Concat(vec![
+      Expression::Literal(json!("a")),
+      Expression::Literal(json!(null)),
+      Expression::Literal(json!(1)),
+    ]));
+    assert_eq!(lambda.eval().await.unwrap(), "a1");
+
+    let lambda = Lambda::<i32>::new(Expression::Coalesce(vec![
+      Expression::Literal(json!(null)),
+      Expression::Literal(json!(1)),
+      Expression::Literal(json!(2)),
+    ]));
+    assert_eq!(lambda.eval().await.unwrap(), 1);
+  }
+
+  #[tokio::test]
+  async fn test_map_and_filter() {
+    let users = literal(json!([{"age": 12}, {"age": 30}]));
+    let age = Box::new(Lambda::context_field("age".to_string()).expression);
+
+    let lambda = Lambda::<Vec<i32>>::new(Expression::Map(users.clone(), age.clone()));
+    assert_eq!(lambda.eval().await.unwrap(), vec![12, 30]);
+
+    let is_adult = Box::new(Expression::Compare(Comparison::Gte, age, literal(json!(18))));
+    let lambda = Lambda::<serde_json::Value>::new(Expression::Filter(users, is_adult));
+    assert_eq!(lambda.eval().await.unwrap(), json!([{"age": 30}]));
+  }
+
+  #[tokio::test]
+  async fn test_pipe() {
+    let name = Box::new(Lambda::context_field("name".to_string()).expression);
+    let lambda = Lambda::<String>::new(Expression::Pipe(literal(json!({"name": "Hans"})), name));
+    assert_eq!(lambda.eval().await.unwrap(), "Hans");
+  }
+
   #[cfg(feature = "unsafe-js")]
   #[tokio::test]
   async fn test_unsafe_js() {
@@ -1,9 +1,17 @@
+mod cache;
+mod call;
 mod evaluation_context;
 mod expression;
+mod graphql_operation_context;
 mod lambda;
 mod resolver_context_like;
+mod stream;
 
+pub use cache::{Cache, CacheKey};
+pub use call::Call;
 pub use evaluation_context::EvaluationContext;
-pub use expression::{Expression, Operation};
+pub use expression::{Comparison, Context, EvaluationError, Expression, MathOperation, Operation};
+pub use graphql_operation_context::GraphQLOperationContext;
 pub use lambda::Lambda;
-pub use resolver_context_like::{EmptyResolverContext, ResolverContextLike};
+pub use resolver_context_like::{ArgsContext, CallContext, EmptyResolverContext, ItemContext, ResolverContextLike};
+pub use stream::Stream;
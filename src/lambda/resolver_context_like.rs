@@ -1,10 +1,11 @@
 use async_graphql::dynamic::ResolverContext;
-use async_graphql::{Name, Value};
+use async_graphql::{Name, SelectionField, Value};
 use indexmap::IndexMap;
 
 pub trait ResolverContextLike<'a> {
   fn value(&'a self) -> Option<&'a Value>;
   fn args(&'a self) -> Option<&'a IndexMap<Name, Value>>;
+  fn field(&'a self) -> Option<SelectionField<'a>>;
 }
 
 pub struct EmptyResolverContext;
@@ -17,6 +18,96 @@ impl<'a> ResolverContextLike<'a> for EmptyResolverContext {
   fn args(&'a self) -> Option<&'a IndexMap<Name, Value>> {
     None
   }
+
+  fn field(&'a self) -> Option<SelectionField<'a>> {
+    None
+  }
+}
+
+/// ArgsContext owns the arguments of a field, so that it can be resolved outside of the GraphQL context.
+/// It's used by subscription streams and by the resolution of federated entities.
+#[derive(Clone, Default)]
+pub struct ArgsContext {
+  args: IndexMap<Name, Value>,
+}
+
+impl ArgsContext {
+  pub fn new(args: IndexMap<Name, Value>) -> Self {
+    Self { args }
+  }
+}
+
+impl<'a> ResolverContextLike<'a> for ArgsContext {
+  fn value(&'a self) -> Option<&'a Value> {
+    None
+  }
+
+  fn args(&'a self) -> Option<&'a IndexMap<Name, Value>> {
+    Some(&self.args)
+  }
+
+  fn field(&'a self) -> Option<SelectionField<'a>> {
+    None
+  }
+}
+
+/// CallContext resolves the field called by `@call` with the arguments of the call,
+/// and the selection of the field that calls it, which is forwarded to GraphQL upstreams.
+pub struct CallContext<'a> {
+  args: IndexMap<Name, Value>,
+  field: Option<SelectionField<'a>>,
+}
+
+impl<'a> CallContext<'a> {
+  pub fn new(args: IndexMap<Name, Value>, field: Option<SelectionField<'a>>) -> Self {
+    Self { args, field }
+  }
+}
+
+impl<'a, 'b: 'a> ResolverContextLike<'a> for CallContext<'b> {
+  fn value(&'a self) -> Option<&'a Value> {
+    None
+  }
+
+  fn args(&'a self) -> Option<&'a IndexMap<Name, Value>> {
+    Some(&self.args)
+  }
+
+  fn field(&'a self) -> Option<SelectionField<'a>> {
+    self.field
+  }
+}
+
+/// ItemContext resolves an expression against a value other than the parent value of the field:
+/// an item of a list for `map` and `filter`, or the output of the previous step of a pipeline.
+/// The arguments are those of the field.
+pub struct ItemContext {
+  value: Value,
+  args: Option<IndexMap<Name, Value>>,
+}
+
+impl ItemContext {
+  pub fn new(value: Value, args: Option<IndexMap<Name, Value>>) -> Self {
+    Self { value, args }
+  }
+
+  pub fn into_value(self) -> Value {
+    self.value
+  }
+}
+
+impl<'a> ResolverContextLike<'a> for ItemContext {
+  fn value(&'a self) -> Option<&'a Value> {
+    Some(&self.value)
+  }
+
+  fn args(&'a self) -> Option<&'a IndexMap<Name, Value>> {
+    self.args.as_ref()
+  }
+
+  fn field(&'a self) -> Option<SelectionField<'a>> {
+    None
+  }
 }
 
 impl<'a> ResolverContextLike<'a> for ResolverContext<'a> {
@@ -27,4 +118,8 @@ impl<'a> ResolverContextLike<'a> for ResolverContext<'a> {
   fn args(&'a self) -> Option<&'a IndexMap<Name, Value>> {
     Some(self.args.as_index_map())
   }
+
+  fn field(&'a self) -> Option<SelectionField<'a>> {
+    Some(self.ctx.field())
+  }
 }
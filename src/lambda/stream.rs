@@ -0,0 +1,147 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_graphql::futures_util::stream::{self, BoxStream, StreamExt};
+use async_graphql_value::ConstValue;
+use reqwest::header::{HeaderValue, ACCEPT};
+use tokio::time::{interval, Interval, MissedTickBehavior};
+
+use super::{ArgsContext, EvaluationContext, EvaluationError, Expression};
+use crate::http::{decode_events, RequestContext};
+use crate::request_template::RequestTemplate;
+
+/// Stream describes how a subscription field receives new values from its upstream.
+#[derive(Clone, Debug)]
+pub enum Stream {
+  /// Evaluates the expression at a fixed interval and emits its value whenever it changes.
+  /// The stream ends after the first error.
+  Poll(Expression, Duration),
+  /// Keeps a single request open and emits the data of every event the upstream sends.
+  EventStream(RequestTemplate),
+  /// Subscribes to the stream only for authenticated requests.
+  Protected(Box<Stream>),
+}
+
+impl Stream {
+  pub fn subscribe(
+    &self,
+    req_ctx: Arc<RequestContext>,
+    graphql_ctx: ArgsContext,
+  ) -> BoxStream<'static, Result<ConstValue>> {
+    match self.clone() {
+      Stream::Poll(expr, period) => {
+        let mut ticks = interval(period);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let poller = Poller { expr, ticks, req_ctx, graphql_ctx, last: None };
+        // An upstream error is emitted once and ends the subscription, instead of being repeated on every tick
+        stream::unfold(Some(poller), |poller| async move {
+          let mut poller = poller?;
+          match poller.next().await {
+            Ok(value) => Some((Ok(value), Some(poller))),
+            Err(error) => Some((Err(error), None)),
+          }
+        })
+        .boxed()
+      }
+      Stream::EventStream(req_template) => {
+        let events = async move {
+          let ctx = EvaluationContext::new(&req_ctx, &graphql_ctx);
+          let mut req = req_template.to_request(&ctx)?;
+          req
+            .headers_mut()
+            .insert(ACCEPT, HeaderValue::from_static("text/event-stream"));
+          let response = req_ctx
+            .http_client_of(req_template.endpoint.upstream.as_deref())
+            .execute_stream(req)
+            .await?;
+          anyhow::Ok(decode_events(response.bytes_stream()))
+        };
+
+        stream::once(events)
+          .flat_map(|events| match events {
+            Ok(events) => events
+              .map(|data| Ok(serde_json::from_str::<ConstValue>(&data?)?))
+              .boxed(),
+            Err(e) => stream::once(async { Err(e) }).boxed(),
+          })
+          .boxed()
+      }
+      Stream::Protected(stream) => match req_ctx.identity {
+        Some(_) => stream.subscribe(req_ctx, graphql_ctx),
+        None => stream::iter([Err(EvaluationError::Unauthenticated.into())]).boxed(),
+      },
+    }
+  }
+}
+
+struct Poller {
+  expr: Expression,
+  ticks: Interval,
+  req_ctx: Arc<RequestContext>,
+  graphql_ctx: ArgsContext,
+  last: Option<ConstValue>,
+}
+
+impl Poller {
+  /// Waits for the next value that differs from the last one emitted.
+  async fn next(&mut self) -> Result<ConstValue> {
+    loop {
+      self.ticks.tick().await;
+      let ctx = EvaluationContext::new(&self.req_ctx, &self.graphql_ctx);
+      let value = self.expr.eval(&ctx).await?;
+      if self.last.as_ref() != Some(&value) {
+        self.last = Some(value.clone());
+        return Ok(value);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use async_graphql::futures_util::StreamExt;
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn test_poll_skips_unchanged_values() {
+    let stream = Stream::Poll(Expression::Literal(json!({"id": 1})), Duration::from_millis(1));
+    let values = stream
+      .subscribe(Arc::new(RequestContext::default()), ArgsContext::default())
+      .take_until(tokio::time::sleep(Duration::from_millis(50)))
+      .map(|value| value.unwrap())
+      .collect::<Vec<_>>()
+      .await;
+
+    assert_eq!(values, vec![ConstValue::from_json(json!({"id": 1})).unwrap()]);
+  }
+
+  #[tokio::test]
+  async fn test_poll_ends_after_error() {
+    let expr = Expression::Protected(Box::new(Expression::Literal(json!({"id": 1}))));
+    let stream = Stream::Poll(expr, Duration::from_millis(1));
+    let values = stream
+      .subscribe(Arc::new(RequestContext::default()), ArgsContext::default())
+      .map(|value| value.map_err(|error| error.to_string()))
+      .collect::<Vec<_>>()
+      .await;
+
+    assert_eq!(values, vec![Err("Unauthenticated".to_string())]);
+  }
+
+  #[tokio::test]
+  async fn test_protected_requires_identity() {
+    let stream = Stream::Poll(Expression::Literal(json!({"id": 1})), Duration::from_millis(1));
+    let stream = Stream::Protected(Box::new(stream));
+    let errors = stream
+      .subscribe(Arc::new(RequestContext::default()), ArgsContext::default())
+      .map(|value| value.unwrap_err().to_string())
+      .collect::<Vec<_>>()
+      .await;
+
+    assert_eq!(errors, vec!["Unauthenticated".to_string()]);
+  }
+}
@@ -1,5 +1,6 @@
 #![allow(clippy::module_inception)]
 pub mod async_graphql_hyper;
+pub mod auth;
 pub mod blueprint;
 pub mod cache;
 pub mod cli;
@@ -7,15 +8,22 @@ pub mod config;
 pub mod directive;
 pub mod document;
 pub mod endpoint;
+pub mod generator;
+pub mod graphql_request_template;
+pub mod grpc;
 pub mod has_headers;
 pub mod http;
 #[cfg(feature = "unsafe-js")]
 pub mod javascript;
 pub mod json;
 pub mod lambda;
+pub mod metrics;
 pub mod mustache;
 pub mod path_string;
+pub mod persisted_queries;
 pub mod print_schema;
 pub mod request_template;
+pub mod rest;
+pub mod telemetry;
 pub mod try_fold;
 pub mod valid;
@@ -0,0 +1,202 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use lru::LruCache;
+use prometheus::{
+  Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts, Registry, TextEncoder,
+};
+
+lazy_static::lazy_static! {
+  static ref METRICS: Metrics = Metrics::new();
+}
+
+/// The number of operation names that get a series of their own at any time.
+const MAX_OPERATION_NAMES: usize = 100;
+
+/// Metrics collected by the server, exposed in the Prometheus text format by `@server(metrics)`.
+pub struct Metrics {
+  registry: Registry,
+  pub graphql_operations: IntCounterVec,
+  pub graphql_operation_duration: HistogramVec,
+  pub upstream_requests: IntCounterVec,
+  pub upstream_request_duration: HistogramVec,
+  pub data_loader_batch_size: Histogram,
+  pub cache_hits: IntCounter,
+  pub cache_misses: IntCounter,
+  pub global_timeouts: IntCounter,
+  operation_names: Mutex<OperationNames>,
+}
+
+impl Metrics {
+  fn new() -> Self {
+    let registry = Registry::new_custom(Some("tailcall".to_string()), None).expect("Failed to create metrics registry");
+    let graphql_operations = IntCounterVec::new(
+      Opts::new(
+        "graphql_operations_total",
+        "GraphQL operations executed, by operation name",
+      ),
+      &["operation"],
+    )
+    .unwrap();
+    let graphql_operation_duration = HistogramVec::new(
+      HistogramOpts::new(
+        "graphql_operation_duration_seconds",
+        "Duration of GraphQL operations, by operation name",
+      ),
+      &["operation"],
+    )
+    .unwrap();
+    let upstream_requests = IntCounterVec::new(
+      Opts::new(
+        "upstream_requests_total",
+        "Requests sent to upstreams, by host, method and status",
+      ),
+      &["host", "method", "status"],
+    )
+    .unwrap();
+    let upstream_request_duration = HistogramVec::new(
+      HistogramOpts::new(
+        "upstream_request_duration_seconds",
+        "Duration of requests sent to upstreams, by host and method",
+      ),
+      &["host", "method"],
+    )
+    .unwrap();
+    let data_loader_batch_size = Histogram::with_opts(
+      HistogramOpts::new(
+        "data_loader_batch_size",
+        "Number of requests loaded together by the HTTP data loader",
+      )
+      .buckets(prometheus::exponential_buckets(1.0, 2.0, 11).unwrap()),
+    )
+    .unwrap();
+    let cache_hits = IntCounter::new("cache_hits_total", "Values of @cache fields read from the cache").unwrap();
+    let cache_misses = IntCounter::new("cache_misses_total", "Values of @cache fields missing from the cache").unwrap();
+    let global_timeouts = IntCounter::new(
+      "global_timeouts_total",
+      "Operations cancelled by the globalResponseTimeout",
+    )
+    .unwrap();
+
+    registry.register(Box::new(graphql_operations.clone())).unwrap();
+    registry.register(Box::new(graphql_operation_duration.clone())).unwrap();
+    registry.register(Box::new(upstream_requests.clone())).unwrap();
+    registry.register(Box::new(upstream_request_duration.clone())).unwrap();
+    registry.register(Box::new(data_loader_batch_size.clone())).unwrap();
+    registry.register(Box::new(cache_hits.clone())).unwrap();
+    registry.register(Box::new(cache_misses.clone())).unwrap();
+    registry.register(Box::new(global_timeouts.clone())).unwrap();
+
+    Metrics {
+      registry,
+      graphql_operations,
+      graphql_operation_duration,
+      upstream_requests,
+      upstream_request_duration,
+      data_loader_batch_size,
+      cache_hits,
+      cache_misses,
+      global_timeouts,
+      operation_names: Mutex::new(OperationNames::new(MAX_OPERATION_NAMES)),
+    }
+  }
+
+  /// Counts and times an executed operation.
+  /// Operation names are set by the clients, so only the ones that recur get a series of their own,
+  /// and the others are all counted as `other`.
+  pub fn observe_operation(&self, operation_name: Option<&str>, duration: Duration) {
+    let mut names = self.operation_names.lock().unwrap();
+    let operation = match operation_name {
+      Some(name) => {
+        let (label, evicted) = names.label(name);
+        // The series of names that aren't labelled anymore are dropped, so that their number stays bounded
+        if let Some(evicted) = evicted {
+          let _ = self.graphql_operations.remove_label_values(&[&evicted]);
+          let _ = self.graphql_operation_duration.remove_label_values(&[&evicted]);
+        }
+        label
+      }
+      None => "anonymous".to_string(),
+    };
+    self.graphql_operations.with_label_values(&[&operation]).inc();
+    self
+      .graphql_operation_duration
+      .with_label_values(&[&operation])
+      .observe(duration.as_secs_f64());
+  }
+
+  /// Encodes all the metrics in the Prometheus text format.
+  pub fn encode(&self) -> anyhow::Result<String> {
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+    Ok(String::from_utf8(buffer)?)
+  }
+}
+
+/// The operation names used as labels, of which there are at most `max`.
+/// A name is labelled once it's seen again while it's still a candidate, so that names that are sent once are
+/// counted as `other`. The least recently used names make room for new ones, in both sets.
+struct OperationNames {
+  candidates: LruCache<String, ()>,
+  labelled: LruCache<String, ()>,
+}
+
+impl OperationNames {
+  fn new(max: usize) -> Self {
+    Self { candidates: LruCache::new(max), labelled: LruCache::new(max) }
+  }
+
+  /// The label of the name, along with the name that isn't labelled anymore to make room for it.
+  fn label(&mut self, name: &str) -> (String, Option<String>) {
+    if self.labelled.get(name).is_some() {
+      return (name.to_string(), None);
+    }
+    if self.candidates.pop(name).is_some() {
+      let evicted = self.labelled.push(name.to_string(), ()).map(|(evicted, _)| evicted);
+      return (name.to_string(), evicted);
+    }
+    self.candidates.put(name.to_string(), ());
+    ("other".to_string(), None)
+  }
+}
+
+/// Returns the metrics of the process.
+pub fn metrics() -> &'static Metrics {
+  &METRICS
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_encode() {
+    metrics().graphql_operations.with_label_values(&["GetUser"]).inc();
+    metrics().cache_hits.inc();
+    let text = metrics().encode().unwrap();
+
+    assert!(text.contains("tailcall_graphql_operations_total{operation=\"GetUser\"}"));
+    assert!(text.contains("# TYPE tailcall_cache_hits_total counter"));
+    assert!(text.contains("# TYPE tailcall_data_loader_batch_size histogram"));
+  }
+
+  #[test]
+  fn test_operation_names() {
+    let mut names = OperationNames::new(2);
+    let label = |name: &str| (name.to_string(), None);
+
+    assert_eq!(names.label("GetUser"), label("other"));
+    assert_eq!(names.label("GetUser"), label("GetUser"));
+    assert_eq!(names.label("GetPost"), label("other"));
+    assert_eq!(names.label("GetPost"), label("GetPost"));
+    assert_eq!(names.label("GetUser"), label("GetUser"));
+
+    // GetPost is the least recently used name
+    assert_eq!(names.label("GetComment"), label("other"));
+    assert_eq!(
+      names.label("GetComment"),
+      ("GetComment".to_string(), Some("GetPost".to_string()))
+    );
+    assert_eq!(names.label("GetPost"), label("other"));
+  }
+}
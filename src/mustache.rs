@@ -1,6 +1,12 @@
+use anyhow::anyhow;
 use nom::{Finish, IResult};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde_json::Value;
 
-use crate::path_string::PathString;
+use crate::path_string::{PathGraphql, PathString};
+
+/// Characters that are kept as is by the `urlencode` filter, the unreserved characters of RFC 3986.
+const URL_ENCODE: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Mustache(Vec<Segment>);
@@ -8,7 +14,62 @@ pub struct Mustache(Vec<Segment>);
 #[derive(Debug, Clone, PartialEq)]
 pub enum Segment {
   Literal(String),
-  Expression(Vec<String>),
+  /// A path, and the filters its value goes through, such as `{{args.limit | default: 10}}`.
+  Expression(Vec<String>, Vec<Filter>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+  /// Replaces a missing or null value.
+  Default(Value),
+  UrlEncode,
+  Trim,
+  /// Joins the items of a list with the separator.
+  Join(String),
+  /// Writes the value as JSON.
+  Json,
+}
+
+impl Filter {
+  pub fn name(&self) -> &'static str {
+    match self {
+      Filter::Default(_) => "default",
+      Filter::UrlEncode => "urlencode",
+      Filter::Trim => "trim",
+      Filter::Join(_) => "join",
+      Filter::Json => "json",
+    }
+  }
+
+  fn apply(&self, value: Value) -> Value {
+    match (self, value) {
+      (Filter::Default(default), Value::Null) => default.clone(),
+      (Filter::Default(_), value) => value,
+      (Filter::UrlEncode, value) => Value::String(utf8_percent_encode(&to_text(&value), URL_ENCODE).to_string()),
+      (Filter::Trim, value) => Value::String(to_text(&value).trim().to_string()),
+      (Filter::Join(separator), Value::Array(items)) => {
+        Value::String(items.iter().map(to_text).collect::<Vec<_>>().join(separator))
+      }
+      (Filter::Join(_), value) => value,
+      (Filter::Json, value) => Value::String(value.to_string()),
+    }
+  }
+}
+
+/// The text of a value in a rendered template. Lists and objects are written as JSON.
+fn to_text(value: &Value) -> String {
+  match value {
+    Value::Null => String::new(),
+    Value::String(s) => s.clone(),
+    value => value.to_string(),
+  }
+}
+
+/// Passes the value of the path through the filters. Missing values are null.
+fn filter(value: Option<Value>, filters: &[Filter]) -> Value {
+  filters
+    .iter()
+    .fold(value.unwrap_or_default(), |value, filter| filter.apply(value))
 }
 
 impl From<Vec<Segment>> for Mustache {
@@ -22,7 +83,7 @@ impl Mustache {
     match self {
       Mustache(segments) => {
         for s in segments {
-          if let Segment::Expression(_) = s {
+          if let Segment::Expression(..) = s {
             return false;
           }
         }
@@ -31,12 +92,18 @@ impl Mustache {
     }
   }
 
+  /// Parses a template. Fails when one of its `{{` doesn't start a valid expression.
   pub fn parse(str: &str) -> anyhow::Result<Mustache> {
-    let result = parse_mustache(str).finish();
-    match result {
-      Ok((_, mustache)) => Ok(mustache),
-      Err(_) => Ok(Mustache::from(vec![Segment::Literal(str.to_string())])),
-    }
+    let rest = match parse_mustache(str).finish() {
+      Ok(("", mustache)) => return Ok(mustache),
+      Ok((rest, _)) => rest,
+      Err(_) if str.is_empty() => return Ok(Mustache::from(vec![Segment::Literal(str.to_string())])),
+      Err(_) => str,
+    };
+    // Literals take everything but `{{`, so the rest starts with the invalid expression,
+    // that ends at the first closing braces if there are any
+    let end = rest.find("}}").map_or(rest.len(), |end| end + 2);
+    Err(anyhow!("Invalid template expression '{}'", &rest[..end]))
   }
 
   pub fn render(&self, value: &impl PathString) -> String {
@@ -45,18 +112,42 @@ impl Mustache {
         .iter()
         .map(|segment| match segment {
           Segment::Literal(text) => text.clone(),
-          Segment::Expression(parts) => value.path_string(parts).map(|a| a.to_string()).unwrap_or_default(),
+          Segment::Expression(parts, filters) if filters.is_empty() => {
+            value.path_string(parts).map(|a| a.to_string()).unwrap_or_default()
+          }
+          Segment::Expression(parts, filters) => to_text(&filter(value.path_json(parts), filters)),
         })
         .collect(),
     }
   }
 
-  pub fn expression_segments(&self) -> Vec<&Vec<String>> {
+  pub fn render_graphql(&self, value: &(impl PathGraphql + PathString)) -> String {
+    match self {
+      Mustache(segments) => segments
+        .iter()
+        .map(|segment| match segment {
+          Segment::Literal(text) => text.to_string(),
+          Segment::Expression(parts, filters) if filters.is_empty() => value.path_graphql(parts).unwrap_or_default(),
+          Segment::Expression(parts, filters) => {
+            async_graphql::Value::from_json(filter(value.path_json(parts), filters))
+              .map(|value| value.to_string())
+              .unwrap_or_default()
+          }
+        })
+        .collect(),
+    }
+  }
+
+  pub fn segments(&self) -> &[Segment] {
+    &self.0
+  }
+
+  pub fn expression_segments(&self) -> Vec<(&Vec<String>, &Vec<Filter>)> {
     match self {
       Mustache(segments) => segments
         .iter()
         .filter_map(|seg| match seg {
-          Segment::Expression(parts) => Some(parts),
+          Segment::Expression(parts, filters) => Some((parts, filters)),
           _ => None,
         })
         .collect(),
@@ -64,34 +155,109 @@ impl Mustache {
   }
 }
 
+/// A key of the path: an identifier such as `user_id` or `x-user-id`, a list index, or a quoted key.
 fn parse_name(input: &str) -> IResult<&str, String> {
+  let identifier = nom::combinator::recognize(nom::sequence::pair(
+    nom::branch::alt((nom::character::complete::alpha1, nom::bytes::complete::tag("_"))),
+    nom::bytes::complete::take_while(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-'),
+  ));
+  let quoted = nom::branch::alt((
+    nom::sequence::delimited(
+      nom::character::complete::char('"'),
+      nom::bytes::complete::take_till1(|c| c == '"'),
+      nom::character::complete::char('"'),
+    ),
+    nom::sequence::delimited(
+      nom::character::complete::char('\''),
+      nom::bytes::complete::take_till1(|c| c == '\''),
+      nom::character::complete::char('\''),
+    ),
+  ));
   nom::combinator::map(
-    nom::sequence::tuple((
+    nom::sequence::delimited(
       nom::character::complete::multispace0,
-      nom::character::complete::alpha1,
-      nom::character::complete::alphanumeric0,
+      nom::branch::alt((identifier, nom::character::complete::digit1, quoted)),
+      nom::character::complete::multispace0,
+    ),
+    String::from,
+  )(input)
+}
+
+/// A quoted string, or a bare literal such as `10` or `true` that is read as JSON when it's valid JSON.
+fn parse_argument(input: &str) -> IResult<&str, Value> {
+  let quoted = nom::combinator::map(
+    nom::sequence::delimited(
+      nom::character::complete::char('"'),
+      nom::bytes::complete::take_till(|c| c == '"'),
+      nom::character::complete::char('"'),
+    ),
+    |s: &str| Value::String(s.to_string()),
+  );
+  let bare = nom::combinator::map(
+    nom::bytes::complete::take_till1(|c: char| c.is_whitespace() || c == '|' || c == '}'),
+    |s: &str| serde_json::from_str(s).unwrap_or_else(|_| Value::String(s.to_string())),
+  );
+  nom::sequence::delimited(
+    nom::character::complete::multispace0,
+    nom::branch::alt((quoted, bare)),
+    nom::character::complete::multispace0,
+  )(input)
+}
+
+fn parse_filter(input: &str) -> IResult<&str, Filter> {
+  let with_argument = |name: &'static str| {
+    nom::sequence::preceded(
+      nom::sequence::tuple((
+        nom::bytes::complete::tag(name),
+        nom::character::complete::multispace0,
+        nom::character::complete::char(':'),
+      )),
+      parse_argument,
+    )
+  };
+  let without_argument = |name: &'static str| {
+    nom::sequence::terminated(nom::bytes::complete::tag(name), nom::character::complete::multispace0)
+  };
+  nom::sequence::preceded(
+    nom::sequence::pair(
+      nom::character::complete::char('|'),
       nom::character::complete::multispace0,
+    ),
+    nom::branch::alt((
+      nom::combinator::map(with_argument("default"), Filter::Default),
+      nom::combinator::map(with_argument("join"), |separator| Filter::Join(to_text(&separator))),
+      nom::combinator::map(without_argument("urlencode"), |_| Filter::UrlEncode),
+      nom::combinator::map(without_argument("trim"), |_| Filter::Trim),
+      nom::combinator::map(without_argument("json"), |_| Filter::Json),
     )),
-    |(_, a, b, _)| format!("{}{}", a, b),
   )(input)
 }
 
-fn parse_expression(input: &str) -> IResult<&str, Vec<String>> {
+fn parse_expression(input: &str) -> IResult<&str, (Vec<String>, Vec<Filter>)> {
   nom::combinator::map(
     nom::sequence::tuple((
       nom::bytes::complete::tag("{{"),
       nom::multi::separated_list1(nom::character::complete::char('.'), parse_name),
+      nom::multi::many0(parse_filter),
       nom::bytes::complete::tag("}}"),
     )),
-    |(_, vec, _)| vec,
+    |(_, path, filters, _)| (path, filters),
   )(input)
 }
 
 fn parse_segment(input: &str) -> IResult<&str, Segment> {
-  let expression = nom::combinator::map(parse_expression, Segment::Expression);
-  let literal = nom::combinator::map(nom::bytes::complete::take_while1(|c| c != '{'), |r: &str| {
-    Segment::Literal(r.to_string())
-  });
+  let expression = nom::combinator::map(parse_expression, |(path, filters)| Segment::Expression(path, filters));
+  // A single `{` is part of the literal, which allows templating JSON bodies
+  let literal = nom::combinator::map(
+    nom::combinator::recognize(nom::multi::many1(nom::branch::alt((
+      nom::bytes::complete::take_while1(|c| c != '{'),
+      nom::sequence::terminated(
+        nom::bytes::complete::tag("{"),
+        nom::combinator::not(nom::character::complete::char('{')),
+      ),
+    )))),
+    |r: &str| Segment::Literal(r.to_string()),
+  );
 
   nom::branch::alt((expression, literal))(input)
 }
@@ -104,8 +270,9 @@ fn parse_mustache(input: &str) -> IResult<&str, Mustache> {
 mod tests {
   mod parse {
     use pretty_assertions::assert_eq;
+    use serde_json::json;
 
-    use crate::mustache::{Mustache, Segment};
+    use crate::mustache::{Filter, Mustache, Segment};
 
     #[test]
     fn test_single_literal() {
@@ -123,10 +290,10 @@ mod tests {
       let mustache: Mustache = Mustache::parse(s).unwrap();
       assert_eq!(
         mustache,
-        Mustache::from(vec![Segment::Expression(vec![
-          "hello".to_string(),
-          "world".to_string()
-        ])])
+        Mustache::from(vec![Segment::Expression(
+          vec!["hello".to_string(), "world".to_string()],
+          vec![]
+        )])
       );
     }
 
@@ -138,9 +305,9 @@ mod tests {
         mustache,
         Mustache::from(vec![
           Segment::Literal("http://localhost:8090/".to_string()),
-          Segment::Expression(vec!["foo".to_string(), "bar".to_string()]),
+          Segment::Expression(vec!["foo".to_string(), "bar".to_string()], vec![]),
           Segment::Literal("/api/".to_string()),
-          Segment::Expression(vec!["hello".to_string(), "world".to_string()]),
+          Segment::Expression(vec!["hello".to_string(), "world".to_string()], vec![]),
           Segment::Literal("/end".to_string())
         ])
       );
@@ -152,7 +319,10 @@ mod tests {
       let mustache: Mustache = Mustache::parse(s).unwrap();
       assert_eq!(
         mustache,
-        Mustache::from(vec![Segment::Expression(vec!["foo".to_string(), "bar".to_string()])])
+        Mustache::from(vec![Segment::Expression(
+          vec!["foo".to_string(), "bar".to_string()],
+          vec![]
+        )])
       );
     }
 
@@ -160,7 +330,7 @@ mod tests {
     fn test_parse_expression_with_valid_input() {
       let result = Mustache::parse("{{ foo.bar }} extra").unwrap();
       let expected = Mustache::from(vec![
-        Segment::Expression(vec!["foo".to_string(), "bar".to_string()]),
+        Segment::Expression(vec!["foo".to_string(), "bar".to_string()], vec![]),
         Segment::Literal(" extra".to_string()),
       ]);
       assert_eq!(result, expected);
@@ -178,9 +348,9 @@ mod tests {
       let result = Mustache::parse("prefix {{foo.bar}} middle {{baz.qux}} suffix").unwrap();
       let expected = Mustache::from(vec![
         Segment::Literal("prefix ".to_string()),
-        Segment::Expression(vec!["foo".to_string(), "bar".to_string()]),
+        Segment::Expression(vec!["foo".to_string(), "bar".to_string()], vec![]),
         Segment::Literal(" middle ".to_string()),
-        Segment::Expression(vec!["baz".to_string(), "qux".to_string()]),
+        Segment::Expression(vec!["baz".to_string(), "qux".to_string()], vec![]),
         Segment::Literal(" suffix".to_string()),
       ]);
       assert_eq!(result, expected);
@@ -196,20 +366,102 @@ mod tests {
     #[test]
     fn test_parse_segments_only_expression() {
       let result = Mustache::parse("{{foo.bar}}").unwrap();
-      let expected = Mustache(vec![Segment::Expression(vec!["foo".to_string(), "bar".to_string()])]);
+      let expected = Mustache(vec![Segment::Expression(
+        vec!["foo".to_string(), "bar".to_string()],
+        vec![],
+      )]);
       assert_eq!(result, expected);
     }
 
     #[test]
     fn test_unfinished_expression() {
-      let s = r"{{hello.world";
-      let mustache: Mustache = Mustache::parse(s).unwrap();
+      let error = Mustache::parse(r"foo/{{hello.world").unwrap_err();
+      assert_eq!(error.to_string(), "Invalid template expression '{{hello.world'");
+    }
+
+    #[test]
+    fn test_invalid_expression() {
+      let error = Mustache::parse(r"/users/{{value.user id}}/posts").unwrap_err();
+      assert_eq!(error.to_string(), "Invalid template expression '{{value.user id}}'");
+    }
+
+    #[test]
+    fn test_identifiers() {
+      let mustache = Mustache::parse(
+        r#"{{value.user_id}}{{headers.x-user-id}}{{value.items.0.id}}{{value."first name"}}{{ value.'$id' }}"#,
+      )
+      .unwrap();
+      let expression =
+        |parts: &[&str]| Segment::Expression(parts.iter().map(|part| part.to_string()).collect(), vec![]);
+      assert_eq!(
+        mustache,
+        Mustache::from(vec![
+          expression(&["value", "user_id"]),
+          expression(&["headers", "x-user-id"]),
+          expression(&["value", "items", "0", "id"]),
+          expression(&["value", "first name"]),
+          expression(&["value", "$id"]),
+        ])
+      );
+    }
+
+    #[test]
+    fn test_json_literal() {
+      let mustache = Mustache::parse(r#"{"id": {{args.id}}}"#).unwrap();
       assert_eq!(
         mustache,
-        Mustache::from(vec![Segment::Literal("{{hello.world".to_string())])
+        Mustache::from(vec![
+          Segment::Literal(r#"{"id": "#.to_string()),
+          Segment::Expression(vec!["args".to_string(), "id".to_string()], vec![]),
+          Segment::Literal("}".to_string())
+        ])
       );
     }
 
+    #[test]
+    fn test_filters() {
+      let mustache = Mustache::parse(r#"{{args.limit | default: 10}}/{{ args.ids|join: "," | urlencode }}"#).unwrap();
+      assert_eq!(
+        mustache,
+        Mustache::from(vec![
+          Segment::Expression(
+            vec!["args".to_string(), "limit".to_string()],
+            vec![Filter::Default(json!(10))]
+          ),
+          Segment::Literal("/".to_string()),
+          Segment::Expression(
+            vec!["args".to_string(), "ids".to_string()],
+            vec![Filter::Join(",".to_string()), Filter::UrlEncode]
+          ),
+        ])
+      );
+    }
+
+    #[test]
+    fn test_filter_arguments() {
+      let mustache =
+        Mustache::parse(r#"{{value.name | default: "John Doe" | trim | json}}{{value.id | default: id}}"#).unwrap();
+      assert_eq!(
+        mustache,
+        Mustache::from(vec![
+          Segment::Expression(
+            vec!["value".to_string(), "name".to_string()],
+            vec![Filter::Default(json!("John Doe")), Filter::Trim, Filter::Json]
+          ),
+          Segment::Expression(
+            vec!["value".to_string(), "id".to_string()],
+            vec![Filter::Default(json!("id"))]
+          ),
+        ])
+      );
+    }
+
+    #[test]
+    fn test_unknown_filter() {
+      let error = Mustache::parse("{{args.id | upper}}").unwrap_err();
+      assert_eq!(error.to_string(), "Invalid template expression '{{args.id | upper}}'");
+    }
+
     #[test]
     fn test_new_number() {
       let mustache = Mustache::parse("123").unwrap();
@@ -222,7 +474,7 @@ mod tests {
     use serde_json::json;
 
     use crate::mustache::{Mustache, Segment};
-    use crate::path_string::PathString;
+    use crate::path_string::{PathGraphql, PathString};
 
     #[test]
     fn test_query_params_template() {
@@ -233,6 +485,47 @@ mod tests {
       assert_eq!(result, "/v1/templates?project-id=123");
     }
 
+    #[test]
+    fn test_render_filters() {
+      let ctx = json!({
+        "args": {"ids": [1, 2, 3], "name": "  Jane Doe ", "input": {"a": 1}, "missing": null}
+      });
+      let render = |template: &str| Mustache::parse(template).unwrap().render(&ctx);
+      assert_eq!(render("{{args.limit | default: 10}}"), "10");
+      assert_eq!(render("{{args.missing | default: \"none\"}}"), "none");
+      assert_eq!(render("{{args.name | default: \"none\"}}"), "  Jane Doe ");
+      assert_eq!(render("{{args.name | trim | urlencode}}"), "Jane%20Doe");
+      assert_eq!(render("{{args.ids | join: \",\"}}"), "1,2,3");
+      assert_eq!(render("{{args.input | json}}"), r#"{"a":1}"#);
+      assert_eq!(render("{{args.limit | trim}}"), "");
+    }
+
+    #[test]
+    fn test_render_graphql_filters() {
+      struct Args(serde_json::Value);
+
+      impl PathString for Args {
+        fn path_string<T: AsRef<str>>(&self, parts: &[T]) -> Option<Cow<'_, str>> {
+          self.0.path_string(parts)
+        }
+
+        fn path_json<T: AsRef<str>>(&self, parts: &[T]) -> Option<serde_json::Value> {
+          self.0.path_json(parts)
+        }
+      }
+
+      impl PathGraphql for Args {
+        fn path_graphql<T: AsRef<str>>(&self, _: &[T]) -> Option<String> {
+          None
+        }
+      }
+
+      let ctx = Args(json!({"args": {"ids": [1, 2, 3]}}));
+      let render = |template: &str| Mustache::parse(template).unwrap().render_graphql(&ctx);
+      assert_eq!(render("{{args.ids | join: \",\"}}"), r#""1,2,3""#);
+      assert_eq!(render("{{args.limit | default: 10}}"), "10");
+    }
+
     #[test]
     fn test_render_mixed() {
       struct DummyPath;
@@ -253,9 +546,9 @@ mod tests {
 
       let mustache = Mustache::from(vec![
         Segment::Literal("prefix ".to_string()),
-        Segment::Expression(vec!["foo".to_string(), "bar".to_string()]),
+        Segment::Expression(vec!["foo".to_string(), "bar".to_string()], vec![]),
         Segment::Literal(" middle ".to_string()),
-        Segment::Expression(vec!["baz".to_string(), "qux".to_string()]),
+        Segment::Expression(vec!["baz".to_string(), "qux".to_string()], vec![]),
         Segment::Literal(" suffix".to_string()),
       ]);
 
@@ -274,7 +567,7 @@ mod tests {
 
       let mustache = Mustache::from(vec![
         Segment::Literal("prefix ".to_string()),
-        Segment::Expression(vec!["foo".to_string(), "bar".to_string()]),
+        Segment::Expression(vec!["foo".to_string(), "bar".to_string()], vec![]),
         Segment::Literal(" suffix".to_string()),
       ]);
 
@@ -299,11 +592,37 @@ mod tests {
 
       let mustache = Mustache::from(vec![
         Segment::Literal("    ".to_string()),
-        Segment::Expression(vec!["foo".to_string()]),
+        Segment::Expression(vec!["foo".to_string()], vec![]),
         Segment::Literal("    ".to_string()),
       ]);
 
       assert_eq!(mustache.render(&DummyPath).as_str(), "    bar    ");
     }
+
+    #[test]
+    fn test_render_graphql() {
+      struct DummyPath;
+
+      impl PathString for DummyPath {
+        fn path_string<T: AsRef<str>>(&self, _: &[T]) -> Option<Cow<'_, str>> {
+          None
+        }
+      }
+
+      impl PathGraphql for DummyPath {
+        fn path_graphql<T: AsRef<str>>(&self, parts: &[T]) -> Option<String> {
+          let parts: Vec<&str> = parts.iter().map(AsRef::as_ref).collect();
+
+          if parts == ["args", "name"] {
+            Some(r#""foo""#.to_string())
+          } else {
+            None
+          }
+        }
+      }
+
+      let mustache = Mustache::parse("{{args.name}}").unwrap();
+      assert_eq!(mustache.render_graphql(&DummyPath), r#""foo""#);
+    }
   }
 }
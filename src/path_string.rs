@@ -7,6 +7,19 @@ use crate::lambda::{EvaluationContext, ResolverContextLike};
 
 pub trait PathString {
   fn path_string<T: AsRef<str>>(&self, path: &[T]) -> Option<Cow<'_, str>>;
+
+  /// Resolves a path into its JSON value, that the filters of a template operate on.
+  fn path_json<T: AsRef<str>>(&self, path: &[T]) -> Option<serde_json::Value> {
+    self
+      .path_string(path)
+      .map(|s| serde_json::Value::String(s.into_owned()))
+  }
+}
+
+/// Resolves a path into a value written as a GraphQL literal,
+/// so that it can be embedded as an argument in a GraphQL document.
+pub trait PathGraphql {
+  fn path_graphql<T: AsRef<str>>(&self, path: &[T]) -> Option<String>;
 }
 
 impl PathString for serde_json::Value {
@@ -18,6 +31,10 @@ impl PathString for serde_json::Value {
       _ => None,
     })
   }
+
+  fn path_json<T: AsRef<str>>(&self, path: &[T]) -> Option<serde_json::Value> {
+    self.get_path(path).cloned()
+  }
 }
 
 fn convert_value(value: &async_graphql::Value) -> Option<Cow<'_, str>> {
@@ -40,11 +57,49 @@ impl<'a, Ctx: ResolverContextLike<'a>> PathString for EvaluationContext<'a, Ctx>
     }
 
     path.split_first().and_then(|(head, tail)| match head.as_ref() {
-      "value" => convert_value(ctx.path_value(tail)?),
+      "value" | "input" => convert_value(ctx.path_value(tail)?),
       "args" => convert_value(ctx.arg(tail)?),
       "headers" => ctx.header(tail[0].as_ref()).map(|v| v.into()),
       "vars" => ctx.var(tail[0].as_ref()).map(|v| v.into()),
       _ => None,
     })
   }
+
+  fn path_json<T: AsRef<str>>(&self, path: &[T]) -> Option<serde_json::Value> {
+    let ctx = self;
+
+    if path.len() < 2 {
+      return None;
+    }
+
+    path.split_first().and_then(|(head, tail)| match head.as_ref() {
+      "value" | "input" => ctx.path_value(tail)?.clone().into_json().ok(),
+      "args" => ctx.arg(tail)?.clone().into_json().ok(),
+      "headers" => ctx.header(tail[0].as_ref()).map(|v| json!(v)),
+      "vars" => ctx.var(tail[0].as_ref()).map(|v| json!(v)),
+      _ => None,
+    })
+  }
+}
+
+impl<'a, Ctx: ResolverContextLike<'a>> PathGraphql for EvaluationContext<'a, Ctx> {
+  fn path_graphql<T: AsRef<str>>(&self, path: &[T]) -> Option<String> {
+    let ctx = self;
+
+    if path.len() < 2 {
+      return None;
+    }
+
+    path.split_first().and_then(|(head, tail)| match head.as_ref() {
+      "value" | "input" => Some(ctx.path_value(tail)?.to_string()),
+      "args" => Some(ctx.arg(tail)?.to_string()),
+      "headers" => ctx
+        .header(tail[0].as_ref())
+        .map(|v| async_graphql::Value::String(v.to_string()).to_string()),
+      "vars" => ctx
+        .var(tail[0].as_ref())
+        .map(|v| async_graphql::Value::String(v.to_string()).to_string()),
+      _ => None,
+    })
+  }
 }
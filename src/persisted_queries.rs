@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use async_graphql::futures_util::future::ready;
+use async_graphql::futures_util::stream::{self, BoxStream};
+use async_graphql::futures_util::StreamExt;
+use async_graphql::{Data, ErrorExtensionValues, Executor, Request, Response, ServerError, Value};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::config;
+
+/// PersistedQueries resolves the queries of Automatic Persisted Queries from their SHA-256 hash.
+/// Queries are registered by the clients that send them along with their hash, and are kept in a bounded store.
+/// The operations of the manifest are always known, and in strict mode they are the only ones that are accepted.
+#[derive(Clone, Debug)]
+pub struct PersistedQueries {
+  manifest: Arc<HashMap<String, String>>,
+  store: moka::future::Cache<String, String>,
+  strict: bool,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+  format: String,
+  operations: Vec<ManifestOperation>,
+}
+
+#[derive(Deserialize)]
+struct ManifestOperation {
+  id: String,
+  body: String,
+}
+
+impl PersistedQueries {
+  pub fn new(max_size: u64, manifest: HashMap<String, String>, strict: bool) -> Self {
+    Self { manifest: Arc::new(manifest), store: moka::future::Cache::new(max_size), strict }
+  }
+
+  pub fn from_config(persisted_queries: &config::PersistedQueries) -> Result<Self> {
+    let manifest = match &persisted_queries.manifest {
+      Some(path) => {
+        let content =
+          std::fs::read_to_string(path).with_context(|| format!("Couldn't read persisted query manifest '{path}'"))?;
+        Self::parse_manifest(&content)?
+      }
+      None if persisted_queries.strict => bail!("A manifest is required in strict mode"),
+      None => HashMap::new(),
+    };
+    Ok(Self::new(
+      persisted_queries.max_size,
+      manifest,
+      persisted_queries.strict,
+    ))
+  }
+
+  /// Parses an Apollo persisted query manifest into the queries of its operations, by id.
+  pub fn parse_manifest(content: &str) -> Result<HashMap<String, String>> {
+    let manifest: Manifest = serde_json::from_str(content)?;
+    if manifest.format != "apollo-persisted-query-manifest" {
+      bail!("Unsupported manifest format '{}'", manifest.format);
+    }
+    Ok(
+      manifest
+        .operations
+        .into_iter()
+        .map(|operation| (operation.id, operation.body))
+        .collect(),
+    )
+  }
+
+  /// Fills in the query of a request that only carries the hash of its query,
+  /// and registers the queries that are sent along with their hash.
+  pub async fn resolve(&self, mut request: Request) -> Result<Request, ServerError> {
+    let Some(hash) = persisted_query_hash(&request) else {
+      if self.strict && self.manifest.get(&sha256(&request.query)) != Some(&request.query) {
+        return Err(error("PersistedQueryNotInList", "PERSISTED_QUERY_NOT_IN_LIST"));
+      }
+      return Ok(request);
+    };
+
+    if request.query.is_empty() {
+      request.query = match self.manifest.get(&hash) {
+        Some(query) => query.clone(),
+        None if self.strict => return Err(error("PersistedQueryNotInList", "PERSISTED_QUERY_NOT_IN_LIST")),
+        None => self
+          .store
+          .get(&hash)
+          .await
+          .ok_or_else(|| error("PersistedQueryNotFound", "PERSISTED_QUERY_NOT_FOUND"))?,
+      };
+      return Ok(request);
+    }
+
+    if sha256(&request.query) != hash {
+      return Err(error("provided sha does not match query", "BAD_REQUEST"));
+    }
+    if self.strict {
+      if !self.manifest.contains_key(&hash) {
+        return Err(error("PersistedQueryNotInList", "PERSISTED_QUERY_NOT_IN_LIST"));
+      }
+    } else {
+      self.store.insert(hash, request.query.clone()).await;
+    }
+    Ok(request)
+  }
+}
+
+/// PersistedQueryExecutor resolves the Automatic Persisted Queries of every request before it reaches the executor.
+/// It is used where requests are executed by async-graphql itself, such as the operations of a WebSocket.
+#[derive(Clone)]
+pub struct PersistedQueryExecutor<E> {
+  executor: E,
+  persisted_queries: Option<PersistedQueries>,
+}
+
+impl<E: Executor> PersistedQueryExecutor<E> {
+  pub fn new(executor: E, persisted_queries: Option<PersistedQueries>) -> Self {
+    Self { executor, persisted_queries }
+  }
+}
+
+#[async_trait::async_trait]
+impl<E: Executor> Executor for PersistedQueryExecutor<E> {
+  async fn execute(&self, request: Request) -> Response {
+    let Some(persisted_queries) = &self.persisted_queries else {
+      return self.executor.execute(request).await;
+    };
+    match persisted_queries.resolve(request).await {
+      Ok(request) => self.executor.execute(request).await,
+      Err(error) => Response::from_errors(vec![error]),
+    }
+  }
+
+  fn execute_stream(&self, request: Request, session_data: Option<Arc<Data>>) -> BoxStream<'static, Response> {
+    let Some(persisted_queries) = self.persisted_queries.clone() else {
+      return self.executor.execute_stream(request, session_data);
+    };
+    let executor = self.executor.clone();
+    stream::once(async move { persisted_queries.resolve(request).await })
+      .flat_map(move |request| match request {
+        Ok(request) => executor.execute_stream(request, session_data.clone()),
+        Err(error) => stream::once(ready(Response::from_errors(vec![error]))).boxed(),
+      })
+      .boxed()
+  }
+}
+
+/// Reads the `sha256Hash` of the `persistedQuery` extension of the request.
+fn persisted_query_hash(request: &Request) -> Option<String> {
+  match request.extensions.get("persistedQuery")? {
+    Value::Object(persisted_query) => match persisted_query.get("sha256Hash")? {
+      Value::String(hash) => Some(hash.to_lowercase()),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+/// Hex encoded SHA-256 hash of the query.
+pub fn sha256(query: &str) -> String {
+  format!("{:x}", Sha256::digest(query.as_bytes()))
+}
+
+fn error(message: &str, code: &str) -> ServerError {
+  let mut extensions = ErrorExtensionValues::default();
+  extensions.set("code", code);
+  let mut error = ServerError::new(message, None);
+  error.extensions = Some(extensions);
+  error
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  const QUERY: &str = "query { user { name } }";
+
+  fn request(query: &str, hash: &str) -> Request {
+    let request = json!({
+      "query": query,
+      "extensions": {"persistedQuery": {"version": 1, "sha256Hash": hash}}
+    });
+    serde_json::from_value(request).unwrap()
+  }
+
+  fn manifest() -> HashMap<String, String> {
+    let content = json!({
+      "format": "apollo-persisted-query-manifest",
+      "version": 1,
+      "operations": [{"id": sha256(QUERY), "name": "User", "type": "query", "body": QUERY}]
+    });
+    PersistedQueries::parse_manifest(&content.to_string()).unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_register_and_resolve() {
+    let persisted_queries = PersistedQueries::new(10, HashMap::new(), false);
+    let hash = sha256(QUERY);
+
+    let error = persisted_queries.resolve(request("", &hash)).await.unwrap_err();
+    assert_eq!(error.message, "PersistedQueryNotFound");
+
+    persisted_queries.resolve(request(QUERY, &hash)).await.unwrap();
+    let resolved = persisted_queries.resolve(request("", &hash)).await.unwrap();
+    assert_eq!(resolved.query, QUERY);
+  }
+
+  #[tokio::test]
+  async fn test_hash_mismatch() {
+    let persisted_queries = PersistedQueries::new(10, HashMap::new(), false);
+    let error = persisted_queries
+      .resolve(request(QUERY, &sha256("query { other }")))
+      .await
+      .unwrap_err();
+    assert_eq!(error.message, "provided sha does not match query");
+  }
+
+  #[tokio::test]
+  async fn test_strict() {
+    let persisted_queries = PersistedQueries::new(10, manifest(), true);
+
+    let resolved = persisted_queries.resolve(request("", &sha256(QUERY))).await.unwrap();
+    assert_eq!(resolved.query, QUERY);
+    assert!(persisted_queries.resolve(Request::new(QUERY)).await.is_ok());
+
+    let other = "query { other }";
+    let error = persisted_queries.resolve(Request::new(other)).await.unwrap_err();
+    assert_eq!(error.message, "PersistedQueryNotInList");
+    let error = persisted_queries
+      .resolve(request(other, &sha256(other)))
+      .await
+      .unwrap_err();
+    assert_eq!(error.message, "PersistedQueryNotInList");
+  }
+}
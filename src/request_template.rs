@@ -69,7 +69,7 @@ impl RequestTemplate {
   /// Returns true if there are not templates
   pub fn is_const(&self) -> bool {
     self.root_url.is_const()
-      && self.body.as_ref().map_or(true, Mustache::is_const)
+      && self.body.as_ref().is_none_or(Mustache::is_const)
       && self.query.iter().all(|(_, v)| v.is_const())
       && self.headers.iter().all(|(_, v)| v.is_const())
   }
@@ -0,0 +1,417 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Result};
+use async_graphql::parser::parse_query;
+use async_graphql::parser::types::{
+  BaseType, Directive, DocumentOperations, ExecutableDocument, OperationDefinition, Selection, SelectionSet, Type,
+};
+use async_graphql::{Positioned, Variables};
+use async_graphql_value::{ConstValue, Name};
+use serde::{Deserialize, Serialize};
+
+use crate::directive::DirectiveCodec;
+use crate::http::Method;
+
+/// Arguments of the `@rest` directive of an operation.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Rest {
+  #[serde(default)]
+  pub method: Method,
+  pub path: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+  Literal(String),
+  Param(String),
+}
+
+/// RestEndpoint serves a GraphQL operation at a REST path such as `GET /users/:id`.
+/// Path params and query params are passed to the operation as variables of the same name.
+#[derive(Clone, Debug)]
+pub struct RestEndpoint {
+  method: Method,
+  path: Vec<Segment>,
+  query: String,
+  operation_name: String,
+  variables: BTreeMap<String, Type>,
+}
+
+impl RestEndpoint {
+  /// Creates an endpoint for every operation of the document that has a `@rest` directive.
+  pub fn try_from_document(document: &str) -> Result<Vec<Self>> {
+    let mut doc = parse_query(document)?;
+    let mut endpoints = Vec::new();
+
+    for (name, operation) in doc.operations.iter() {
+      let Some(directive) = operation
+        .node
+        .directives
+        .iter()
+        .find(|directive| directive.node.name.node == "rest")
+      else {
+        continue;
+      };
+      let name = name.ok_or_else(|| anyhow!("Operations with @rest must be named"))?;
+      let rest = directive
+        .node
+        .clone()
+        .into_const()
+        .ok_or_else(|| anyhow!("@rest on {name} can't use variables"))
+        .and_then(|directive| Ok(Rest::from_directive(&directive).to_result()?))?;
+      let variables = operation
+        .node
+        .variable_definitions
+        .iter()
+        .map(|variable| (variable.node.name.node.to_string(), variable.node.var_type.node.clone()))
+        .collect::<BTreeMap<_, _>>();
+      let path = parse_path(&rest.path)?;
+      for segment in path.iter() {
+        if let Segment::Param(param) = segment {
+          if !variables.contains_key(param) {
+            bail!("Path param '{param}' of {name} is not a variable of the operation");
+          }
+        }
+      }
+
+      endpoints.push(RestEndpoint {
+        method: rest.method,
+        path,
+        query: String::new(),
+        operation_name: name.to_string(),
+        variables,
+      });
+    }
+
+    // The `@rest` directives are unknown to the schema, so they are removed from the executed query
+    let operations = match &mut doc.operations {
+      DocumentOperations::Single(operation) => vec![operation],
+      DocumentOperations::Multiple(operations) => operations.values_mut().collect(),
+    };
+    for operation in operations {
+      operation
+        .node
+        .directives
+        .retain(|directive| directive.node.name.node != "rest");
+    }
+    let query = print_document(&doc);
+    for endpoint in endpoints.iter_mut() {
+      endpoint.query = query.clone();
+    }
+
+    Ok(endpoints)
+  }
+
+  /// Returns the request for the operation, if the endpoint serves the given method and path.
+  pub fn matches(&self, method: &hyper::Method, uri: &hyper::Uri) -> Option<async_graphql::Request> {
+    if reqwest::Method::from(&self.method) != method {
+      return None;
+    }
+
+    let parts = uri
+      .path()
+      .split('/')
+      .filter(|part| !part.is_empty())
+      .collect::<Vec<_>>();
+    if parts.len() != self.path.len() {
+      return None;
+    }
+
+    let mut params = Vec::new();
+    for (segment, part) in self.path.iter().zip(parts) {
+      match segment {
+        Segment::Literal(literal) if literal == part => {}
+        Segment::Literal(_) => return None,
+        Segment::Param(name) => {
+          let value = percent_encoding::percent_decode_str(part).decode_utf8().ok()?;
+          params.push((name.clone(), value.to_string()));
+        }
+      }
+    }
+    let query_params = serde_urlencoded::from_str::<Vec<(String, String)>>(uri.query().unwrap_or_default()).ok()?;
+
+    let mut variables = Variables::default();
+    for (name, value) in query_params.into_iter().chain(params) {
+      if let Some(type_of) = self.variables.get(&name) {
+        variables.insert(Name::new(&name), to_variable(type_of, value));
+      }
+    }
+
+    Some(
+      async_graphql::Request::new(self.query.clone())
+        .operation_name(self.operation_name.clone())
+        .variables(variables),
+    )
+  }
+}
+
+fn parse_path(path: &str) -> Result<Vec<Segment>> {
+  if !path.starts_with('/') {
+    bail!("Path '{path}' must start with '/'");
+  }
+
+  path
+    .split('/')
+    .filter(|part| !part.is_empty())
+    .map(|part| match part.strip_prefix(':') {
+      Some("") => bail!("Path '{path}' has a param without a name"),
+      Some(param) => Ok(Segment::Param(param.to_string())),
+      None => Ok(Segment::Literal(part.to_string())),
+    })
+    .collect()
+}
+
+/// Parses the value of a param according to the type of its variable.
+/// Values that don't fit the type are passed as strings, so that the operation reports the error.
+fn to_variable(type_of: &Type, value: String) -> ConstValue {
+  let parsed = match &type_of.base {
+    BaseType::Named(name) => match name.as_str() {
+      "Int" => value.parse::<i64>().ok().map(ConstValue::from),
+      "Float" => value
+        .parse::<f64>()
+        .ok()
+        .and_then(|value| serde_json::Number::from_f64(value).map(ConstValue::Number)),
+      "Boolean" => value.parse::<bool>().ok().map(ConstValue::Boolean),
+      _ => None,
+    },
+    BaseType::List(_) => None,
+  };
+
+  parsed.unwrap_or(ConstValue::String(value))
+}
+
+/// Prints the document back as a query, without its comments.
+fn print_document(doc: &ExecutableDocument) -> String {
+  let operations = match &doc.operations {
+    DocumentOperations::Single(operation) => vec![print_operation(None, &operation.node)],
+    DocumentOperations::Multiple(operations) => operations
+      .iter()
+      .map(|(name, operation)| print_operation(Some(name.as_str()), &operation.node))
+      .collect(),
+  };
+  let fragments = doc.fragments.iter().map(|(name, fragment)| {
+    format!(
+      "fragment {} on {}{} {}",
+      name,
+      fragment.node.type_condition.node.on.node,
+      print_directives(&fragment.node.directives),
+      print_selection_set(&fragment.node.selection_set.node)
+    )
+  });
+
+  operations.into_iter().chain(fragments).collect::<Vec<_>>().join("\n")
+}
+
+fn print_operation(name: Option<&str>, operation: &OperationDefinition) -> String {
+  let variables = operation
+    .variable_definitions
+    .iter()
+    .map(|variable| {
+      let variable = &variable.node;
+      let default_value = variable
+        .default_value
+        .as_ref()
+        .map_or(String::new(), |value| format!(" = {}", value.node));
+      format!(
+        "${}: {}{}{}",
+        variable.name.node,
+        variable.var_type.node,
+        default_value,
+        print_directives(&variable.directives)
+      )
+    })
+    .collect::<Vec<_>>();
+  let variables = if variables.is_empty() {
+    String::new()
+  } else {
+    format!("({})", variables.join(", "))
+  };
+
+  format!(
+    "{} {}{}{} {}",
+    operation.ty,
+    name.unwrap_or_default(),
+    variables,
+    print_directives(&operation.directives),
+    print_selection_set(&operation.selection_set.node)
+  )
+}
+
+fn print_selection_set(selection_set: &SelectionSet) -> String {
+  let selections = selection_set
+    .items
+    .iter()
+    .map(|selection| match &selection.node {
+      Selection::Field(field) => {
+        let field = &field.node;
+        let alias = field
+          .alias
+          .as_ref()
+          .map_or(String::new(), |alias| format!("{}: ", alias.node));
+        let selection_set = if field.selection_set.node.items.is_empty() {
+          String::new()
+        } else {
+          format!(" {}", print_selection_set(&field.selection_set.node))
+        };
+        format!(
+          "{}{}{}{}{}",
+          alias,
+          field.name.node,
+          print_arguments(&field.arguments),
+          print_directives(&field.directives),
+          selection_set
+        )
+      }
+      Selection::FragmentSpread(spread) => format!(
+        "...{}{}",
+        spread.node.fragment_name.node,
+        print_directives(&spread.node.directives)
+      ),
+      Selection::InlineFragment(fragment) => {
+        let type_condition = fragment
+          .node
+          .type_condition
+          .as_ref()
+          .map_or(String::new(), |condition| format!(" on {}", condition.node.on.node));
+        format!(
+          "...{}{} {}",
+          type_condition,
+          print_directives(&fragment.node.directives),
+          print_selection_set(&fragment.node.selection_set.node)
+        )
+      }
+    })
+    .collect::<Vec<_>>();
+
+  format!("{{ {} }}", selections.join(" "))
+}
+
+fn print_directives(directives: &[Positioned<Directive>]) -> String {
+  directives
+    .iter()
+    .map(|directive| {
+      format!(
+        " @{}{}",
+        directive.node.name.node,
+        print_arguments(&directive.node.arguments)
+      )
+    })
+    .collect()
+}
+
+fn print_arguments(arguments: &[(Positioned<Name>, Positioned<async_graphql_value::Value>)]) -> String {
+  if arguments.is_empty() {
+    return String::new();
+  }
+  let arguments = arguments
+    .iter()
+    .map(|(name, value)| format!("{}: {}", name.node, value.node))
+    .collect::<Vec<_>>();
+  format!("({})", arguments.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  use super::*;
+
+  const DOCUMENT: &str = r#"
+    query GetUser($id: Int!, $verbose: Boolean) @rest(method: GET, path: "/users/:id") {
+      user(id: $id) { name }
+    }
+
+    query GetUsers @rest(path: "/users") { users { name } }
+
+    mutation CreateUser($name: String) { createUser(name: $name) { name } }
+  "#;
+
+  fn endpoint(name: &str) -> RestEndpoint {
+    RestEndpoint::try_from_document(DOCUMENT)
+      .unwrap()
+      .into_iter()
+      .find(|endpoint| endpoint.operation_name == name)
+      .unwrap()
+  }
+
+  #[test]
+  fn test_operations_with_rest() {
+    let endpoints = RestEndpoint::try_from_document(DOCUMENT).unwrap();
+    let mut names = endpoints
+      .iter()
+      .map(|endpoint| endpoint.operation_name.as_str())
+      .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec!["GetUser", "GetUsers"]);
+  }
+
+  #[test]
+  fn test_query_without_directives() {
+    let query = endpoint("GetUser").query;
+    assert!(!query.contains("@rest"));
+    assert!(query.contains("query GetUser($id: Int!, $verbose: Boolean) { user(id: $id) { name } }"));
+    assert!(query.contains("query GetUsers { users { name } }"));
+    assert!(parse_query(query).is_ok());
+  }
+
+  #[test]
+  fn test_query_with_strings_and_comments() {
+    let document = r#"
+      # @rest(path: "/comment")
+      query GetUser @rest(path: "/users") @include(if: true) {
+        user(name: "@rest(path: \")\"") { name ...Names @skip(if: false) ... on User { id } }
+      }
+
+      fragment Names on User { name }
+    "#;
+    let query = RestEndpoint::try_from_document(document).unwrap().remove(0).query;
+
+    assert!(query.contains(
+      r#"query GetUser @include(if: true) { user(name: "@rest(path: \")\"") { name ...Names @skip(if: false) ... on User { id } } }"#
+    ));
+    assert!(query.contains("fragment Names on User { name }"));
+    assert!(parse_query(query).is_ok());
+  }
+
+  #[test]
+  fn test_matches() {
+    let endpoint = endpoint("GetUser");
+    let request = endpoint
+      .matches(&hyper::Method::GET, &"/users/1?verbose=true&other=1".parse().unwrap())
+      .unwrap();
+
+    assert_eq!(request.operation_name.as_deref(), Some("GetUser"));
+    assert_eq!(
+      request.variables.into_value().into_json().unwrap(),
+      json!({"id": 1, "verbose": true})
+    );
+  }
+
+  #[test]
+  fn test_does_not_match() {
+    let endpoint = endpoint("GetUser");
+    assert!(endpoint
+      .matches(&hyper::Method::POST, &"/users/1".parse().unwrap())
+      .is_none());
+    assert!(endpoint
+      .matches(&hyper::Method::GET, &"/users".parse().unwrap())
+      .is_none());
+    assert!(endpoint
+      .matches(&hyper::Method::GET, &"/posts/1".parse().unwrap())
+      .is_none());
+  }
+
+  #[test]
+  fn test_unknown_path_param() {
+    let document = r#"query GetUser @rest(path: "/users/:id") { user { name } }"#;
+    assert!(RestEndpoint::try_from_document(document).is_err());
+  }
+
+  #[test]
+  fn test_to_variable() {
+    let int = Type::new("Int!").unwrap();
+    assert_eq!(to_variable(&int, "1".to_string()), ConstValue::from(1));
+    assert_eq!(to_variable(&int, "one".to_string()), ConstValue::from("one"));
+  }
+}
@@ -0,0 +1,117 @@
+#![allow(clippy::too_many_arguments)]
+
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use async_graphql::futures_util::future::BoxFuture;
+use hyper::HeaderMap;
+use opentelemetry::trace::{FutureExt, SpanKind, Status, TraceContextExt, TraceError, Tracer};
+use opentelemetry::{global, Context, KeyValue};
+use opentelemetry_http::{HeaderExtractor, HeaderInjector};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::TracerProvider;
+use opentelemetry_sdk::{runtime, Resource};
+
+use crate::config;
+
+/// Exports the spans to the OTLP collector of the config, in batches.
+pub fn init_otlp(telemetry: &config::Telemetry) -> Result<()> {
+  let exporter = opentelemetry_otlp::new_exporter()
+    .http()
+    .with_endpoint(telemetry.otlp.url.clone())
+    .with_headers(telemetry.otlp.headers.0.clone().into_iter().collect())
+    .build_span_exporter()?;
+  let provider = TracerProvider::builder()
+    .with_batch_exporter(exporter, runtime::Tokio)
+    .with_config(
+      opentelemetry_sdk::trace::config().with_resource(Resource::new(vec![KeyValue::new(
+        "service.name",
+        telemetry.get_service_name(),
+      )])),
+    )
+    .build();
+  install(provider);
+  Ok(())
+}
+
+/// Exports every span to the exporter as soon as it ends.
+/// The returned provider can be flushed to wait for the spans to be exported.
+pub fn init_tracer(exporter: impl SpanExporter + 'static) -> TracerProvider {
+  let provider = TracerProvider::builder().with_simple_exporter(exporter).build();
+  install(provider.clone());
+  provider
+}
+
+fn install(provider: TracerProvider) {
+  global::set_text_map_propagator(TraceContextPropagator::new());
+  global::set_tracer_provider(provider);
+}
+
+/// Reads the context of the trace that a request belongs to, from its `traceparent` header.
+pub fn extract(headers: &HeaderMap) -> Context {
+  global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)))
+}
+
+/// Adds the `traceparent` header of the context to a request.
+pub fn inject(cx: &Context, headers: &mut HeaderMap) {
+  global::get_text_map_propagator(|propagator| propagator.inject_context(cx, &mut HeaderInjector(headers)))
+}
+
+/// Starts a span as a child of the span of `parent`, and returns the context of the new span.
+pub fn start_span(
+  parent: &Context,
+  name: impl Into<Cow<'static, str>>,
+  kind: SpanKind,
+  attributes: Vec<KeyValue>,
+) -> Context {
+  let tracer = global::tracer("tailcall");
+  let span = tracer
+    .span_builder(name)
+    .with_kind(kind)
+    .with_attributes(attributes)
+    .start_with_context(&tracer, parent);
+  parent.with_span(span)
+}
+
+/// Runs the future in the span of the context, and ends the span once the future completes.
+/// Errors are recorded as the status of the span.
+pub async fn in_span<T, E: Display>(cx: Context, future: impl Future<Output = Result<T, E>>) -> Result<T, E> {
+  let result = future.with_context(cx.clone()).await;
+  if let Err(error) = &result {
+    cx.span().set_status(Status::error(error.to_string()));
+  }
+  cx.span().end();
+  result
+}
+
+/// Keeps the exported spans in memory, so that tests can inspect them.
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryExporter {
+  spans: Arc<Mutex<Vec<SpanData>>>,
+}
+
+impl InMemoryExporter {
+  pub fn spans(&self) -> Vec<SpanData> {
+    self.spans.lock().unwrap().clone()
+  }
+
+  pub fn reset(&self) {
+    self.spans.lock().unwrap().clear();
+  }
+}
+
+impl SpanExporter for InMemoryExporter {
+  fn export(&mut self, batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+    let result = self
+      .spans
+      .lock()
+      .map(|mut spans| spans.extend(batch))
+      .map_err(|e| TraceError::from(e.to_string()));
+    Box::pin(std::future::ready(result))
+  }
+}
@@ -4,7 +4,9 @@ use crate::valid::Valid;
 ///
 /// `TryFolding` describes a composable folding operation that can potentially fail.
 /// It can optionally consume an input to transform the provided value.
-pub struct TryFold<'a, I: 'a, O: 'a, E: 'a>(Box<dyn Fn(&I, O) -> Valid<O, E> + 'a>);
+type TryFoldFn<'a, I, O, E> = Box<dyn Fn(&I, O) -> Valid<O, E> + 'a>;
+
+pub struct TryFold<'a, I: 'a, O: 'a, E: 'a>(TryFoldFn<'a, I, O, E>);
 
 impl<'a, I, O: Clone + 'a, E> TryFold<'a, I, O, E> {
   /// Try to fold the value with the input.
@@ -57,6 +59,7 @@ impl<'a, I, O: Clone + 'a, E> TryFold<'a, I, O, E> {
   /// # Returns
   /// Returns a `Collect` instance that can be used to perform a folding operation
   /// over all the items in the list.
+  #[allow(clippy::should_implement_trait)]
   pub fn from_iter<F: IntoIterator<Item = TryFold<'a, I, O, E>>>(items: F) -> TryFold<'a, I, O, E> {
     let mut iter = items.into_iter();
     let head = iter.next();
@@ -78,7 +81,7 @@ impl<'a, I, O: Clone + 'a, E> TryFold<'a, I, O, E> {
     down: impl Fn(O1) -> Valid<O, E> + 'a,
   ) -> TryFold<'a, I, O1, E> {
     TryFold(Box::new(move |input, o1| {
-      down(o1).and_then(|o| self.try_fold(input, o)).and_then(|o| up(o))
+      down(o1).and_then(|o| self.try_fold(input, o)).and_then(&up)
     }))
   }
 
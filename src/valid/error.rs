@@ -60,7 +60,7 @@ impl<E> ValidationError<E> {
   }
 
   pub fn transform<E1>(self, f: impl Fn(E) -> E1) -> ValidationError<E1> {
-    ValidationError(self.0.into_iter().map(|cause| cause.transform(|a| f(a))).collect())
+    ValidationError(self.0.into_iter().map(|cause| cause.transform(&f)).collect())
   }
 }
 
@@ -15,6 +15,7 @@ pub fn setup_mocks(mock_server: &mut mockito::Server) {
   let user_posts: serde_json::Value = serde_json::from_str(users_posts_json.as_str()).unwrap();
   let user_posts_2: serde_json::Value = serde_json::from_str(users_posts_json.as_str()).unwrap();
   let posts_json = fs::read_to_string("tests/data/posts.json").unwrap();
+  let users_3: Vec<serde_json::Value> = serde_json::from_str(users_json.as_str()).unwrap();
 
   mock_server
     .mock("GET", "/users")
@@ -82,12 +83,51 @@ pub fn setup_mocks(mock_server: &mut mockito::Server) {
     })
     .create();
 
+  mock_server
+    .mock("GET", mockito::Matcher::Regex(r"/user-agent$".to_string()))
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body_from_request(|req| {
+      let user_agent = req.header("user-agent").first().copied();
+      serde_json::json!({ "path": req.path(), "userAgent": user_agent })
+        .to_string()
+        .into()
+    })
+    .create();
+
   mock_server
     .mock("GET", "/defaultPropertyResolver")
     .with_status(200)
     .with_header("content-type", "application/json")
     .with_body(r#"{"a": 1}"#)
     .create();
+
+  mock_server
+    .mock("POST", "/graphql")
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body_from_request(move |req| {
+      let body: Value = serde_json::from_slice(req.body().unwrap()).unwrap();
+      let resolve = |request: &Value| {
+        let query = request["query"].as_str().unwrap_or_default();
+        // Like any GraphQL server, fields of an object type require a selection set
+        let user_by_id = Regex::new(r"user\(id: (\d+)\) \{").unwrap();
+        if let Some(caps) = user_by_id.captures(query) {
+          let id = caps[1].parse::<u64>().unwrap();
+          let user = users_3.iter().find(|x| x["id"].as_u64().unwrap() == id);
+          serde_json::json!({ "data": { "user": user } })
+        } else if query.contains("users {") {
+          serde_json::json!({ "data": { "users": users_3 } })
+        } else {
+          serde_json::json!({ "data": null, "errors": [{ "message": "Unknown operation" }] })
+        }
+      };
+      match &body {
+        Value::Array(requests) => Value::Array(requests.iter().map(resolve).collect()).to_string().into(),
+        request => resolve(request).to_string().into(),
+      }
+    })
+    .create();
 }
 
 pub fn get_id_from_path(path: &str, re: Regex) -> u64 {
@@ -72,6 +72,7 @@ impl GraphQLSpec {
 
     let mut spec = GraphQLSpec::default().path(path);
     let mut server_sdl = Vec::new();
+    let regex = Regex::new(r"@expect.*\) ").unwrap();
     for component in content.split("#>") {
       if component.contains(CLIENT_SDL) {
         let trimmed = component.replace(CLIENT_SDL, "").trim().to_string();
@@ -102,7 +103,6 @@ impl GraphQLSpec {
         spec = spec.merged_server_sdl(component.replace(MERGED_SDL, "").trim().to_string());
       }
       if component.contains(CLIENT_QUERY) {
-        let regex = Regex::new(r"@expect.*\) ").unwrap();
         let query_string = component.replace(CLIENT_QUERY, "");
         let parsed_query = async_graphql::parser::parse_query(query_string.clone()).unwrap();
 
@@ -211,7 +211,7 @@ async fn test_execution() -> std::io::Result<()> {
       .trace(spec.path.to_str().unwrap_or_default())
       .to_result()
       .unwrap();
-    let server_ctx = ServerContext::new(blueprint);
+    let server_ctx = ServerContext::new(blueprint).unwrap();
     let schema = server_ctx.schema.clone();
 
     for q in spec.test_queries {
@@ -220,9 +220,8 @@ async fn test_execution() -> std::io::Result<()> {
       let req_ctx = Arc::new(RequestContext::from(&server_ctx).req_headers(headers));
       let req = Request::from(q.query.as_str()).data(req_ctx.clone());
       let res = schema.execute(req).await;
-      let json = serde_json::to_string(&res).unwrap();
-      let expected = serde_json::to_string(&q.expected).unwrap();
-      assert_eq!(json, expected, "QueryExecution: {}", spec.path.display());
+      let json = serde_json::to_value(&res).unwrap();
+      assert_eq!(json, q.expected, "QueryExecution: {}", spec.path.display());
       log::info!("QueryExecution: {} ... ok", spec.path.display());
     }
   }
@@ -0,0 +1,98 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use serde_json::json;
+
+use crate::common::{self, json_response, start_upstream};
+
+/// Starts tailcall on the given port and waits until it accepts connections
+async fn start_tailcall(port: u16) {
+  let addr =
+    start_upstream(|_| json_response(json!({"id": 1, "name": "Leanne Graham", "email": "leanne@example.com"}))).await;
+  let sdl = format!(
+    r#"
+    schema
+      @server(port: {port}, auth: {{jwks: {{file: "tests/auth/jwks.json", issuer: "tailcall"}}, htpasswd: {{file: "tests/auth/.htpasswd"}}}})
+      @upstream(baseURL: "http://{addr}") {{
+      query: Query
+    }}
+
+    type User {{
+      id: Int
+      name: String
+      email: String @protected
+    }}
+
+    type Query {{
+      user: User @http(path: "/users/1")
+      me: User @http(path: "/users/1") @protected
+    }}
+    "#
+  );
+  common::start_tailcall(common::config(&sdl), port).await;
+}
+
+fn token(issuer: &str) -> String {
+  let header = Header { kid: Some("tailcall".to_string()), ..Header::new(Algorithm::HS256) };
+  let claims = json!({"sub": "alice", "iss": issuer, "exp": 4102444800u64});
+  encode(&header, &claims, &EncodingKey::from_secret(b"tailcall-secret")).unwrap()
+}
+
+async fn execute(port: u16, query: &str, authorization: Option<String>) -> serde_json::Value {
+  let mut request = reqwest::Client::new()
+    .post(format!("http://127.0.0.1:{port}/graphql"))
+    .json(&json!({"query": query}));
+  if let Some(authorization) = authorization {
+    request = request.header("authorization", authorization);
+  }
+  request.send().await.unwrap().json().await.unwrap()
+}
+
+fn errors(response: &serde_json::Value) -> Vec<&str> {
+  response["errors"]
+    .as_array()
+    .map(|errors| errors.iter().filter_map(|error| error["message"].as_str()).collect())
+    .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn test_unauthenticated() {
+  start_tailcall(8817).await;
+
+  let response = execute(8817, "query { user { name } }", None).await;
+  assert_eq!(response, json!({"data": {"user": {"name": "Leanne Graham"}}}));
+
+  for query in ["query { me { name } }", "query { user { email } }"] {
+    let response = execute(8817, query, None).await;
+    assert_eq!(errors(&response), vec!["Unauthenticated"]);
+  }
+}
+
+#[tokio::test]
+async fn test_bearer_token() {
+  start_tailcall(8818).await;
+  let query = "query { me { name email } }";
+
+  let response = execute(8818, query, Some(format!("Bearer {}", token("tailcall")))).await;
+  assert_eq!(
+    response,
+    json!({"data": {"me": {"name": "Leanne Graham", "email": "leanne@example.com"}}})
+  );
+
+  let response = execute(8818, query, Some(format!("Bearer {}", token("other")))).await;
+  assert_eq!(errors(&response), vec!["Unauthenticated"]);
+}
+
+#[tokio::test]
+async fn test_basic_credentials() {
+  start_tailcall(8819).await;
+  let query = "query { me { email } }";
+
+  let credentials = STANDARD.encode("alice:secret");
+  let response = execute(8819, query, Some(format!("Basic {credentials}"))).await;
+  assert_eq!(response, json!({"data": {"me": {"email": "leanne@example.com"}}}));
+
+  let credentials = STANDARD.encode("alice:wrong");
+  let response = execute(8819, query, Some(format!("Basic {credentials}"))).await;
+  assert_eq!(errors(&response), vec!["Unauthenticated"]);
+}
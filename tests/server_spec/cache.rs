@@ -0,0 +1,95 @@
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use async_graphql::Request;
+use serde_json::json;
+use tailcall::http::{RequestContext, ServerContext};
+
+use crate::common::{self, json_response, start_upstream};
+
+/// Starts an upstream that returns the user with the requested id and counts the calls it receives
+async fn start_user_server() -> (SocketAddr, Arc<AtomicUsize>) {
+  let calls = Arc::new(AtomicUsize::new(0));
+  let counter = calls.clone();
+  let addr = start_upstream(move |req| {
+    counter.fetch_add(1, Ordering::SeqCst);
+    let id = req.uri().path().rsplit('/').next().unwrap_or_default();
+    json_response(json!({"id": id.parse::<i32>().unwrap_or_default(), "name": format!("User {id}")}))
+  })
+  .await;
+
+  (addr, calls)
+}
+
+fn server_context(addr: SocketAddr) -> ServerContext {
+  let sdl = format!(
+    r#"
+    schema @server(enableCacheControlHeader: true) @upstream(baseURL: "http://{addr}") {{
+      query: Query
+    }}
+
+    type User {{
+      id: Int
+      name: String
+    }}
+
+    type Query {{
+      user(id: Int!): User @http(path: "/users/{{{{args.id}}}}") @cache(maxAge: 60)
+      uncachedUser(id: Int!): User @http(path: "/users/{{{{args.id}}}}")
+    }}
+    "#
+  );
+  common::server_context(&sdl)
+}
+
+async fn execute(server_ctx: &ServerContext, query: &str) -> (serde_json::Value, Option<u64>) {
+  let req_ctx = Arc::new(RequestContext::from(server_ctx));
+  let res = server_ctx
+    .schema
+    .execute(Request::new(query).data(req_ctx.clone()))
+    .await;
+
+  (serde_json::to_value(res).unwrap(), req_ctx.get_min_max_age())
+}
+
+#[tokio::test]
+async fn test_cache_hit() {
+  let (addr, calls) = start_user_server().await;
+  let server_ctx = server_context(addr);
+
+  let (first, first_max_age) = execute(&server_ctx, "query { user(id: 1) { name } }").await;
+  let (second, second_max_age) = execute(&server_ctx, "query { user(id: 1) { name } }").await;
+
+  assert_eq!(first, json!({"data": {"user": {"name": "User 1"}}}));
+  assert_eq!(second, first);
+  assert_eq!(
+    calls.load(Ordering::SeqCst),
+    1,
+    "The second request should be served from the cache"
+  );
+  assert_eq!(first_max_age, Some(60));
+  assert!(second_max_age.is_some_and(|max_age| max_age <= 60));
+}
+
+#[tokio::test]
+async fn test_cache_key_uses_rendered_request() {
+  let (addr, calls) = start_user_server().await;
+  let server_ctx = server_context(addr);
+
+  let (res, _) = execute(&server_ctx, "query { a: user(id: 1) { name } b: user(id: 2) { name } }").await;
+
+  assert_eq!(res, json!({"data": {"a": {"name": "User 1"}, "b": {"name": "User 2"}}}));
+  assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_uncached_field() {
+  let (addr, calls) = start_user_server().await;
+  let server_ctx = server_context(addr);
+
+  execute(&server_ctx, "query { uncachedUser(id: 1) { name } }").await;
+  execute(&server_ctx, "query { uncachedUser(id: 1) { name } }").await;
+
+  assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
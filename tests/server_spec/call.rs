@@ -0,0 +1,100 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use serde_json::json;
+use tailcall::http::ServerContext;
+
+use crate::common::{self, execute, json_response, start_upstream};
+
+/// Starts an upstream of posts and users, that keeps the URIs of the requests it receives
+async fn start_recording_upstream(requests: Arc<Mutex<Vec<String>>>) -> SocketAddr {
+  start_upstream(move |req| {
+    requests.lock().unwrap().push(req.uri().to_string());
+    match req.uri().path() {
+      "/posts" => json_response(json!([{"id": 1, "userId": 1}, {"id": 2, "userId": 2}, {"id": 3, "userId": 1}])),
+      _ => {
+        let ids = req.uri().query().unwrap_or_default().split('&');
+        let users = ids
+          .filter_map(|param| param.strip_prefix("id="))
+          .map(|id| json!({"id": id.parse::<i64>().unwrap(), "name": format!("User {id}")}))
+          .collect::<Vec<_>>();
+        json_response(json!(users))
+      }
+    }
+  })
+  .await
+}
+
+fn server_context(addr: SocketAddr) -> ServerContext {
+  let sdl = format!(
+    r#"
+    schema @upstream(baseURL: "http://{addr}", batch: {{delay: 10}}) {{
+      query: Query
+    }}
+
+    type Post {{
+      id: Int!
+      userId: Int!
+      user: User @call(query: "user", args: {{id: "{{{{value.userId}}}}"}})
+      author: User @call(query: "userById", args: {{id: "{{{{value.userId}}}}"}})
+    }}
+
+    type User {{
+      id: Int!
+      name: String
+    }}
+
+    type Query {{
+      posts: [Post] @http(path: "/posts")
+      firstUser: User @call(query: "user", args: {{id: 1}})
+      me: User @call(query: "firstUser")
+      userById(id: Int!): User @call(query: "user", args: {{id: "{{{{args.id}}}}"}})
+      user(id: Int!): User @http(path: "/users", query: [{{key: "id", value: "{{{{args.id}}}}"}}], groupBy: ["id"])
+    }}
+    "#
+  );
+  common::server_context(&sdl)
+}
+
+#[tokio::test]
+async fn test_call() {
+  let requests = Arc::new(Mutex::new(Vec::new()));
+  let server_ctx = server_context(start_recording_upstream(requests.clone()).await);
+
+  assert_eq!(
+    execute(&server_ctx, "query { user(id: 2) { name } }").await,
+    json!({"data": {"user": {"name": "User 2"}}})
+  );
+  assert_eq!(
+    execute(&server_ctx, "query { posts { id user { name } } }").await,
+    json!({"data": {"posts": [
+      {"id": 1, "user": {"name": "User 1"}},
+      {"id": 2, "user": {"name": "User 2"}},
+      {"id": 3, "user": {"name": "User 1"}}
+    ]}})
+  );
+
+  assert_eq!(
+    execute(&server_ctx, "query { me { name } }").await,
+    json!({"data": {"me": {"name": "User 1"}}})
+  );
+
+  // Chained calls share the data loader of the field they end up calling
+  assert_eq!(
+    execute(&server_ctx, "query { posts { user { name } author { name } } }").await,
+    json!({"data": {"posts": [
+      {"user": {"name": "User 1"}, "author": {"name": "User 1"}},
+      {"user": {"name": "User 2"}, "author": {"name": "User 2"}},
+      {"user": {"name": "User 1"}, "author": {"name": "User 1"}}
+    ]}})
+  );
+
+  // The users of the posts are loaded with a single batched request
+  let requests = requests.lock().unwrap().clone();
+  assert_eq!(requests.len(), 6);
+  for i in [2, 5] {
+    assert_eq!(requests[i - 1], "/posts");
+    assert!(requests[i].starts_with("/users?"));
+    assert!(requests[i].contains("id=1") && requests[i].contains("id=2"));
+  }
+}
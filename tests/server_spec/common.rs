@@ -0,0 +1,66 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_graphql::Request;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Response};
+use tailcall::blueprint::Blueprint;
+use tailcall::config::Config;
+use tailcall::http::{start_server, RequestContext, ServerContext};
+
+/// Starts an upstream on a free port, that responds to every request with the handler.
+pub async fn start_upstream<F>(handler: F) -> SocketAddr
+where
+  F: Fn(hyper::Request<Body>) -> Response<Body> + Send + Sync + 'static,
+{
+  let handler = Arc::new(handler);
+  let make_svc = make_service_fn(move |_| {
+    let handler = handler.clone();
+    async move {
+      Ok::<_, Infallible>(service_fn(move |req: hyper::Request<Body>| {
+        let response = handler(req);
+        async move { Ok::<_, Infallible>(response) }
+      }))
+    }
+  });
+
+  let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+  let addr = server.local_addr();
+  tokio::spawn(server);
+
+  addr
+}
+
+/// A response with the value as its JSON body.
+pub fn json_response(body: serde_json::Value) -> Response<Body> {
+  Response::new(Body::from(body.to_string()))
+}
+
+pub fn config(sdl: &str) -> Config {
+  Config::from_sdl(sdl).to_result().unwrap()
+}
+
+pub fn server_context(sdl: &str) -> ServerContext {
+  ServerContext::new(Blueprint::try_from(&config(sdl)).unwrap()).unwrap()
+}
+
+/// Executes the query against the schema of the server, without going through HTTP.
+pub async fn execute(server_ctx: &ServerContext, query: &str) -> serde_json::Value {
+  let req_ctx = Arc::new(RequestContext::from(server_ctx));
+  let res = server_ctx.schema.execute(Request::new(query).data(req_ctx)).await;
+  serde_json::to_value(res).unwrap()
+}
+
+/// Starts tailcall with the config on the given port and waits until it accepts connections.
+pub async fn start_tailcall(config: Config, port: u16) {
+  tokio::spawn(start_server(config));
+  for _ in 0..50 {
+    if tokio::net::TcpStream::connect(("127.0.0.1", port)).await.is_ok() {
+      return;
+    }
+    tokio::time::sleep(Duration::from_millis(20)).await;
+  }
+  panic!("Server didn't start on port {port}");
+}
@@ -0,0 +1,86 @@
+use serde_json::json;
+
+use crate::common;
+
+const PORT: u16 = 8828;
+
+/// Starts tailcall and waits until it accepts connections
+async fn start_tailcall() {
+  let sdl = format!(
+    r#"
+    schema @server(port: {PORT}, cors: {{allowOrigins: ["https://tailcall.run"], allowHeaders: ["content-type", "authorization"], exposeHeaders: ["x-request-id"], maxAge: 600, allowCredentials: true}}) {{
+      query: Query
+    }}
+
+    type Query {{
+      hello: String @const(data: "Hello")
+    }}
+    "#
+  );
+  common::start_tailcall(common::config(&sdl), PORT).await;
+}
+
+fn url() -> String {
+  format!("http://127.0.0.1:{PORT}/graphql")
+}
+
+#[tokio::test]
+async fn test_cors() {
+  start_tailcall().await;
+  let client = reqwest::Client::new();
+
+  let response = client
+    .request(reqwest::Method::OPTIONS, url())
+    .header("origin", "https://tailcall.run")
+    .header("access-control-request-method", "POST")
+    .header("access-control-request-headers", "content-type")
+    .send()
+    .await
+    .unwrap();
+  assert_eq!(response.status(), 204);
+  let headers = response.headers();
+  assert_eq!(headers["access-control-allow-origin"], "https://tailcall.run");
+  assert_eq!(headers["access-control-allow-methods"], "GET, POST, OPTIONS");
+  assert_eq!(headers["access-control-allow-headers"], "content-type, authorization");
+  assert_eq!(headers["access-control-max-age"], "600");
+  assert_eq!(headers["access-control-allow-credentials"], "true");
+  assert_eq!(headers["vary"], "origin");
+
+  let response = client
+    .post(url())
+    .header("origin", "https://tailcall.run")
+    .json(&json!({"query": "query { hello }"}))
+    .send()
+    .await
+    .unwrap();
+  let headers = response.headers();
+  assert_eq!(headers["access-control-allow-origin"], "https://tailcall.run");
+  assert_eq!(headers["access-control-expose-headers"], "x-request-id");
+  assert!(!headers.contains_key("access-control-allow-methods"));
+
+  // Requests that fail get the CORS headers too
+  let response = client
+    .post(url())
+    .header("origin", "https://tailcall.run")
+    .header("content-type", "application/json")
+    .body("{")
+    .send()
+    .await
+    .unwrap();
+  assert_eq!(response.status(), 400);
+  assert_eq!(
+    response.headers()["access-control-allow-origin"],
+    "https://tailcall.run"
+  );
+
+  // Other origins aren't allowed
+  let response = client
+    .request(reqwest::Method::OPTIONS, url())
+    .header("origin", "https://example.com")
+    .header("access-control-request-method", "POST")
+    .send()
+    .await
+    .unwrap();
+  assert!(!response.headers().contains_key("access-control-allow-origin"));
+  assert!(!response.headers().contains_key("access-control-allow-methods"));
+}
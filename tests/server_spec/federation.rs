@@ -0,0 +1,129 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use serde_json::json;
+use tailcall::http::ServerContext;
+
+use crate::common::{self, execute, json_response, start_upstream};
+
+/// Starts an upstream that returns the users and posts with the requested ids and records the requests it receives
+async fn start_recording_upstream() -> (SocketAddr, Arc<Mutex<Vec<String>>>) {
+  let requests = Arc::new(Mutex::new(Vec::new()));
+  let recorder = requests.clone();
+  let addr = start_upstream(move |req| {
+    recorder.lock().unwrap().push(req.uri().to_string());
+    let ids = serde_urlencoded::from_str::<Vec<(String, i32)>>(req.uri().query().unwrap_or_default())
+      .unwrap()
+      .into_iter()
+      .map(|(_, id)| id)
+      .collect::<Vec<_>>();
+    match req.uri().path() {
+      "/users" => json_response(json!(ids
+        .iter()
+        .map(|id| json!({"id": id, "name": format!("User {id}")}))
+        .collect::<Vec<_>>())),
+      _ => json_response(json!({"id": ids[0], "title": format!("Post {}", ids[0])})),
+    }
+  })
+  .await;
+
+  (addr, requests)
+}
+
+fn server_context(addr: SocketAddr) -> ServerContext {
+  let sdl = format!(
+    r#"
+    schema @server(federation: true) @upstream(baseURL: "http://{addr}", batch: {{delay: 1}}) {{
+      query: Query
+    }}
+
+    type Post @key(fields: "id") {{
+      id: Int!
+      title: String
+    }}
+
+    type User @key(fields: "id") {{
+      id: Int!
+      name: String
+    }}
+
+    type Query {{
+      post(id: Int): Post @http(path: "/posts", query: [{{key: "id", value: "{{{{args.id}}}}"}}])
+      user(id: Int): User @http(path: "/users", query: [{{key: "id", value: "{{{{args.id}}}}"}}], groupBy: ["id"])
+    }}
+    "#
+  );
+  common::server_context(&sdl)
+}
+
+#[tokio::test]
+async fn test_service_sdl() {
+  let (addr, _) = start_recording_upstream().await;
+  let server_ctx = server_context(addr);
+
+  let res = execute(&server_ctx, "query { _service { sdl } }").await;
+  let sdl = res["data"]["_service"]["sdl"].as_str().unwrap();
+
+  assert!(sdl.contains("type Post @key(fields: \"id\")"));
+  assert!(sdl.contains("type User @key(fields: \"id\")"));
+}
+
+#[tokio::test]
+async fn test_entities_are_batched() {
+  let (addr, requests) = start_recording_upstream().await;
+  let server_ctx = server_context(addr);
+
+  let query = r#"
+    query {
+      _entities(representations: [{__typename: "User", id: 2}, {__typename: "User", id: 1}]) {
+        ... on User { id name }
+      }
+    }
+  "#;
+  let res = execute(&server_ctx, query).await;
+
+  assert_eq!(
+    res,
+    json!({"data": {"_entities": [{"id": 2, "name": "User 2"}, {"id": 1, "name": "User 1"}]}})
+  );
+  assert_eq!(
+    requests.lock().unwrap().len(),
+    1,
+    "Users should be resolved in a single request"
+  );
+}
+
+#[tokio::test]
+async fn test_entities_of_different_types() {
+  let (addr, requests) = start_recording_upstream().await;
+  let server_ctx = server_context(addr);
+
+  let query = r#"
+    query {
+      _entities(representations: [{__typename: "Post", id: 3}, {__typename: "User", id: 1}]) {
+        ... on Post { title }
+        ... on User { name }
+      }
+    }
+  "#;
+  let res = execute(&server_ctx, query).await;
+
+  assert_eq!(
+    res,
+    json!({"data": {"_entities": [{"title": "Post 3"}, {"name": "User 1"}]}})
+  );
+  let mut requests = requests.lock().unwrap().clone();
+  requests.sort();
+  assert_eq!(requests, vec!["/posts?id=3", "/users?id=1"]);
+}
+
+#[tokio::test]
+async fn test_unknown_entity() {
+  let (addr, _) = start_recording_upstream().await;
+  let server_ctx = server_context(addr);
+
+  let query = r#"query { _entities(representations: [{__typename: "Comment", id: 1}]) { __typename } }"#;
+  let res = execute(&server_ctx, query).await;
+
+  assert_eq!(res["errors"][0]["message"], "Comment is not an entity");
+}
@@ -0,0 +1,134 @@
+use hyper::{Body, Response};
+use serde_json::json;
+
+use crate::common::{self, start_upstream};
+
+const PORT: u16 = 8827;
+
+/// Starts tailcall and waits until it accepts connections
+async fn start_tailcall() {
+  // Serves a user, that can be cached for a minute
+  let addr = start_upstream(|_| {
+    Response::builder()
+      .header("cache-control", "max-age=60")
+      .body(Body::from(json!({"name": "Leanne"}).to_string()))
+      .unwrap()
+  })
+  .await;
+  let sdl = format!(
+    r#"
+    schema @server(port: {PORT}, enableCacheControlHeader: true) @upstream(baseURL: "http://{addr}") {{
+      query: Query
+      mutation: Mutation
+    }}
+
+    type User {{
+      name: String
+    }}
+
+    type Query {{
+      user(id: Int!): User @http(path: "/users/{{{{args.id}}}}")
+      hello: String @const(data: "Hello")
+      unreachable: User! @http(baseURL: "http://127.0.0.1:1", path: "/users/1")
+    }}
+
+    type Mutation {{
+      greet: String @const(data: "Greeted")
+    }}
+    "#
+  );
+  common::start_tailcall(common::config(&sdl), PORT).await;
+}
+
+fn url() -> String {
+  format!("http://127.0.0.1:{PORT}/graphql")
+}
+
+#[tokio::test]
+async fn test_graphql_over_http() {
+  start_tailcall().await;
+  let client = reqwest::Client::new();
+
+  // Queries can be sent with GET, and their responses can be cached
+  let response = client
+    .get(url())
+    .query(&[
+      ("query", "query GetUser($id: Int!) { user(id: $id) { name } }"),
+      ("variables", r#"{"id": 1}"#),
+      ("operationName", "GetUser"),
+    ])
+    .send()
+    .await
+    .unwrap();
+  assert_eq!(response.status(), 200);
+  assert_eq!(response.headers()["cache-control"], "max-age=60");
+  assert_eq!(
+    response.json::<serde_json::Value>().await.unwrap(),
+    json!({"data": {"user": {"name": "Leanne"}}})
+  );
+
+  // Mutations can't
+  let response = client
+    .get(url())
+    .query(&[("query", "mutation { greet }")])
+    .send()
+    .await
+    .unwrap();
+  assert_eq!(response.status(), 405);
+  assert_eq!(response.headers()["allow"], "POST");
+
+  // Requests that can't be executed are bad requests for the clients that accept the GraphQL response media type
+  let response = client
+    .get(url())
+    .query(&[("query", "query { hello")])
+    .header("accept", "application/graphql-response+json")
+    .send()
+    .await
+    .unwrap();
+  assert_eq!(response.status(), 400);
+  assert_eq!(response.headers()["content-type"], "application/graphql-response+json");
+  let response = client
+    .get(url())
+    .query(&[("query", "query { hello")])
+    .send()
+    .await
+    .unwrap();
+  assert_eq!(response.status(), 200);
+  assert_eq!(response.headers()["content-type"], "application/json");
+
+  // Errors of the execution aren't
+  let response = client
+    .get(url())
+    .query(&[("query", "query { unreachable { name } }")])
+    .header("accept", "application/graphql-response+json")
+    .send()
+    .await
+    .unwrap();
+  assert_eq!(response.status(), 200);
+  assert_eq!(
+    response.json::<serde_json::Value>().await.unwrap()["data"],
+    serde_json::Value::Null
+  );
+
+  // Arrays of requests are executed as a batch
+  let response = client
+    .post(url())
+    .json(&json!([{"query": "query { hello }"}, {"query": "mutation { greet }"}]))
+    .send()
+    .await
+    .unwrap();
+  assert_eq!(
+    response.json::<serde_json::Value>().await.unwrap(),
+    json!([{"data": {"hello": "Hello"}}, {"data": {"greet": "Greeted"}}])
+  );
+
+  // Bodies that can't be read are bad requests, with or without CORS
+  let response = client
+    .post(url())
+    .header("content-type", "application/json")
+    .body("{")
+    .send()
+    .await
+    .unwrap();
+  assert_eq!(response.status(), 400);
+}
@@ -0,0 +1,207 @@
+use std::convert::Infallible;
+use std::future::{ready, Ready};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use hyper::service::{make_service_fn, service_fn};
+use prost::Message;
+use prost_reflect::{DynamicMessage, MessageDescriptor};
+use serde_json::json;
+use tailcall::grpc::ProtobufSet;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::server::{Grpc, UnaryService};
+use tonic::Status;
+
+use crate::common;
+
+const NEWS_PROTO: &str = "tests/grpc/news.proto";
+
+/// Codec that reads and writes messages described by a `.proto` file at runtime
+struct DynamicCodec {
+  input: MessageDescriptor,
+}
+
+struct DynamicEncoder;
+
+struct DynamicDecoder {
+  input: MessageDescriptor,
+}
+
+impl Codec for DynamicCodec {
+  type Encode = DynamicMessage;
+  type Decode = DynamicMessage;
+  type Encoder = DynamicEncoder;
+  type Decoder = DynamicDecoder;
+
+  fn encoder(&mut self) -> Self::Encoder {
+    DynamicEncoder
+  }
+
+  fn decoder(&mut self) -> Self::Decoder {
+    DynamicDecoder { input: self.input.clone() }
+  }
+}
+
+impl Encoder for DynamicEncoder {
+  type Item = DynamicMessage;
+  type Error = Status;
+
+  fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+    item.encode(dst).map_err(|e| Status::internal(e.to_string()))
+  }
+}
+
+impl Decoder for DynamicDecoder {
+  type Item = DynamicMessage;
+  type Error = Status;
+
+  fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+    let message = DynamicMessage::decode(self.input.clone(), src).map_err(|e| Status::internal(e.to_string()))?;
+    Ok(Some(message))
+  }
+}
+
+/// Stand-in for a NewsService written with tonic
+struct NewsService {
+  method: String,
+  output: MessageDescriptor,
+}
+
+fn news(id: i32) -> serde_json::Value {
+  json!({"id": id, "title": format!("Note {}", id), "body": format!("Content {}", id)})
+}
+
+impl UnaryService<DynamicMessage> for NewsService {
+  type Response = DynamicMessage;
+  type Future = Ready<Result<tonic::Response<DynamicMessage>, Status>>;
+
+  fn call(&mut self, request: tonic::Request<DynamicMessage>) -> Self::Future {
+    let input = serde_json::to_value(request.get_ref()).unwrap();
+    let output = match self.method.as_str() {
+      "GetAllNews" => Ok(json!({"news": [news(1), news(2), news(3)]})),
+      "GetNews" => match input["id"].as_i64().unwrap_or_default() {
+        id @ 1..=3 => Ok(news(id as i32)),
+        _ => Err(Status::not_found("News not found")),
+      },
+      "GetMultipleNews" => {
+        let ids = input["ids"].as_array().cloned().unwrap_or_default();
+        let news = ids
+          .iter()
+          .map(|id| news(id.as_i64().unwrap() as i32))
+          .collect::<Vec<_>>();
+        Ok(json!({ "news": news }))
+      }
+      _ => Err(Status::unimplemented("Unknown method")),
+    };
+
+    ready(output.map(|output| tonic::Response::new(DynamicMessage::deserialize(self.output.clone(), output).unwrap())))
+  }
+}
+
+/// Starts the stand-in server and returns its address along with the number of calls it received
+async fn start_news_server() -> (SocketAddr, Arc<AtomicUsize>) {
+  let protobuf = ProtobufSet::from_proto_file(NEWS_PROTO).unwrap();
+  let calls = Arc::new(AtomicUsize::new(0));
+  let counter = calls.clone();
+
+  let make_svc = make_service_fn(move |_| {
+    let protobuf = protobuf.clone();
+    let counter = counter.clone();
+    async move {
+      Ok::<_, Infallible>(service_fn(move |req: hyper::Request<hyper::Body>| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        let method = req.uri().path().rsplit('/').next().unwrap_or_default().to_string();
+        let operation = protobuf.find_operation("news.NewsService", &method).unwrap();
+        let service = NewsService { method, output: operation.output_type.clone() };
+        let mut grpc = Grpc::new(DynamicCodec { input: operation.input_type.clone() });
+        async move { Ok::<_, Infallible>(grpc.unary(service, req).await) }
+      }))
+    }
+  });
+
+  let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into())
+    .http2_only(true)
+    .serve(make_svc);
+  let addr = server.local_addr();
+  tokio::spawn(server);
+
+  (addr, calls)
+}
+
+async fn execute(addr: SocketAddr, query: &str) -> serde_json::Value {
+  let sdl = format!(
+    r#"
+    schema @server @upstream(baseURL: "http://{addr}", batch: {{delay: 1}}) {{
+      query: Query
+    }}
+
+    type News {{
+      id: Int
+      title: String
+      body: String
+    }}
+
+    type NewsData {{
+      news: [News]
+    }}
+
+    type Post {{
+      id: Int
+      news: News @grpc(service: "news.NewsService", method: "GetMultipleNews", protoPath: "{NEWS_PROTO}", body: "{{\"ids\": [{{{{value.id}}}}]}}", groupBy: ["news", "id"])
+    }}
+
+    type Query {{
+      news: NewsData @grpc(service: "news.NewsService", method: "GetAllNews", protoPath: "{NEWS_PROTO}")
+      newsById(id: Int!): News @grpc(service: "news.NewsService", method: "GetNews", protoPath: "{NEWS_PROTO}", body: "{{\"id\": {{{{args.id}}}}}}")
+      posts: [Post] @const(data: [{{id: 1}}, {{id: 3}}])
+    }}
+    "#
+  );
+  common::execute(&common::server_context(&sdl), query).await
+}
+
+#[tokio::test]
+async fn test_grpc_unary() {
+  let (addr, _) = start_news_server().await;
+  let res = execute(addr, "query { newsById(id: 2) { id title body } }").await;
+
+  assert_eq!(
+    res,
+    json!({"data": {"newsById": {"id": 2, "title": "Note 2", "body": "Content 2"}}})
+  );
+}
+
+#[tokio::test]
+async fn test_grpc_empty_input() {
+  let (addr, _) = start_news_server().await;
+  let res = execute(addr, "query { news { news { id } } }").await;
+
+  assert_eq!(
+    res,
+    json!({"data": {"news": {"news": [{"id": 1}, {"id": 2}, {"id": 3}]}}})
+  );
+}
+
+#[tokio::test]
+async fn test_grpc_error() {
+  let (addr, _) = start_news_server().await;
+  let res = execute(addr, "query { newsById(id: 4) { id } }").await;
+
+  assert_eq!(
+    res["errors"][0]["message"],
+    "IOException: gRPC error: status 5 News not found"
+  );
+}
+
+#[tokio::test]
+async fn test_grpc_batching() {
+  let (addr, calls) = start_news_server().await;
+  let res = execute(addr, "query { posts { id news { title } } }").await;
+
+  assert_eq!(
+    res,
+    json!({"data": {"posts": [{"id": 1, "news": {"title": "Note 1"}}, {"id": 3, "news": {"title": "Note 3"}}]}})
+  );
+  assert_eq!(calls.load(Ordering::SeqCst), 1, "Only one batched call should be made");
+}
@@ -0,0 +1,18 @@
+// Integration tests that run tailcall against upstreams started by the tests themselves.
+mod auth;
+mod cache;
+mod call;
+mod common;
+mod cors;
+mod federation;
+mod graphql_over_http;
+mod grpc;
+mod metrics;
+mod persisted_queries;
+mod reload;
+mod rest;
+mod retry;
+mod subscription;
+mod telemetry;
+mod tls;
+mod upstream_tls;
@@ -0,0 +1,57 @@
+use serde_json::json;
+
+use crate::common::{self, json_response, start_upstream};
+
+/// Starts tailcall on the given port and waits until it accepts connections
+async fn start_tailcall(port: u16) {
+  let addr = start_upstream(|_| json_response(json!({"name": "Leanne"}))).await;
+  let sdl = format!(
+    r#"
+    schema @server(port: {port}, metrics: {{path: "/metrics"}}) @upstream(baseURL: "http://{addr}") {{
+      query: Query
+    }}
+
+    type User {{
+      name: String
+    }}
+
+    type Query {{
+      user: User @http(path: "/users/1") @cache(maxAge: 60)
+    }}
+    "#
+  );
+  common::start_tailcall(common::config(&sdl), port).await;
+}
+
+#[tokio::test]
+async fn test_metrics() {
+  start_tailcall(8822).await;
+  let client = reqwest::Client::new();
+
+  // The first request of an operation name is counted as `other`, so that names sent once don't get a series
+  for _ in 0..3 {
+    let response = client
+      .post("http://127.0.0.1:8822/graphql")
+      .json(&json!({"query": "query GetMetricsUser { user { name } }"}))
+      .send()
+      .await
+      .unwrap();
+    assert_eq!(response.status(), 200);
+  }
+
+  let response = client.get("http://127.0.0.1:8822/metrics").send().await.unwrap();
+  assert_eq!(response.status(), 200);
+  assert!(response.headers()["content-type"]
+    .to_str()
+    .unwrap()
+    .starts_with("text/plain"));
+
+  let text = response.text().await.unwrap();
+  assert!(text.contains("tailcall_graphql_operations_total{operation=\"GetMetricsUser\"} 2"));
+  assert!(text.contains("tailcall_upstream_requests_total{host=\"127.0.0.1\",method=\"GET\",status=\"200\"}"));
+  assert!(text.contains("tailcall_upstream_request_duration_seconds_count"));
+  assert!(text.contains("tailcall_data_loader_batch_size_count"));
+  assert!(text.contains("tailcall_cache_hits_total"));
+  assert!(text.contains("tailcall_cache_misses_total"));
+  assert!(text.contains("tailcall_global_timeouts_total 0"));
+}
@@ -0,0 +1,138 @@
+use async_graphql::futures_util::{SinkExt, StreamExt};
+use serde_json::json;
+use tailcall::persisted_queries::sha256;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::common;
+
+const QUERY: &str = "query { hello }";
+
+/// Starts tailcall on the given port and waits until it accepts connections
+async fn start_tailcall(port: u16, persisted_queries: &str) {
+  let sdl = format!(
+    r#"
+    schema @server(port: {port}, persistedQueries: {persisted_queries}) {{
+      query: Query
+    }}
+
+    type Query {{
+      hello: String @const(data: "Hello")
+      goodbye: String @const(data: "Goodbye")
+    }}
+    "#
+  );
+  common::start_tailcall(common::config(&sdl), port).await;
+}
+
+fn persisted_query(hash: &str) -> serde_json::Value {
+  json!({"persistedQuery": {"version": 1, "sha256Hash": hash}})
+}
+
+async fn post(port: u16, body: serde_json::Value) -> serde_json::Value {
+  reqwest::Client::new()
+    .post(format!("http://127.0.0.1:{port}/graphql"))
+    .json(&body)
+    .send()
+    .await
+    .unwrap()
+    .json()
+    .await
+    .unwrap()
+}
+
+async fn get(port: u16, params: &[(&str, String)]) -> serde_json::Value {
+  reqwest::Client::new()
+    .get(format!("http://127.0.0.1:{port}/graphql"))
+    .query(params)
+    .send()
+    .await
+    .unwrap()
+    .json()
+    .await
+    .unwrap()
+}
+
+/// Sends the query over a graphql-transport-ws WebSocket and returns the messages until the operation ends
+async fn subscribe(port: u16, query: &str) -> Vec<serde_json::Value> {
+  let mut request = format!("ws://127.0.0.1:{port}/graphql").into_client_request().unwrap();
+  request
+    .headers_mut()
+    .insert("sec-websocket-protocol", "graphql-transport-ws".parse().unwrap());
+  let (mut socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+
+  let init = json!({"type": "connection_init"});
+  let subscribe = json!({"type": "subscribe", "id": "1", "payload": {"query": query}});
+  for message in [init, subscribe] {
+    socket.send(Message::Text(message.to_string())).await.unwrap();
+  }
+
+  let mut messages = Vec::new();
+  while let Some(Ok(Message::Text(text))) = socket.next().await {
+    let message: serde_json::Value = serde_json::from_str(&text).unwrap();
+    let done = message["type"] == "complete" || message["type"] == "error";
+    messages.push(message);
+    if done {
+      break;
+    }
+  }
+  messages
+}
+
+#[tokio::test]
+async fn test_automatic_persisted_queries() {
+  let port = 8825;
+  start_tailcall(port, "{maxSize: 10}").await;
+  let extensions = persisted_query(&sha256(QUERY));
+
+  // The client first sends the hash alone, and the query along with its hash when it isn't known yet
+  let response = post(port, json!({"extensions": extensions})).await;
+  assert_eq!(response["errors"][0]["message"], "PersistedQueryNotFound");
+  assert_eq!(response["errors"][0]["extensions"]["code"], "PERSISTED_QUERY_NOT_FOUND");
+
+  let response = post(port, json!({"query": QUERY, "extensions": extensions})).await;
+  assert_eq!(response, json!({"data": {"hello": "Hello"}}));
+
+  let response = post(port, json!({"extensions": extensions})).await;
+  assert_eq!(response, json!({"data": {"hello": "Hello"}}));
+
+  let response = get(port, &[("extensions", extensions.to_string())]).await;
+  assert_eq!(response, json!({"data": {"hello": "Hello"}}));
+
+  let response = post(port, json!({"query": "query { goodbye }", "extensions": extensions})).await;
+  assert_eq!(response["errors"][0]["message"], "provided sha does not match query");
+}
+
+#[tokio::test]
+async fn test_allowlist() {
+  let port = 8826;
+  let manifest = std::env::temp_dir().join(format!("tailcall-manifest-{}.json", std::process::id()));
+  let content = json!({
+    "format": "apollo-persisted-query-manifest",
+    "version": 1,
+    "operations": [{"id": sha256(QUERY), "name": "Hello", "type": "query", "body": QUERY}]
+  });
+  std::fs::write(&manifest, content.to_string()).unwrap();
+  start_tailcall(
+    port,
+    &format!("{{manifest: {:?}, strict: true}}", manifest.to_string_lossy()),
+  )
+  .await;
+
+  let response = post(port, json!({"extensions": persisted_query(&sha256(QUERY))})).await;
+  assert_eq!(response, json!({"data": {"hello": "Hello"}}));
+
+  let response = post(port, json!({"query": QUERY})).await;
+  assert_eq!(response, json!({"data": {"hello": "Hello"}}));
+
+  let response = post(port, json!({"query": "query { goodbye }"})).await;
+  assert_eq!(response["errors"][0]["message"], "PersistedQueryNotInList");
+
+  let messages = subscribe(port, "query { goodbye }").await;
+  assert_eq!(
+    messages[1]["payload"]["errors"][0]["message"],
+    "PersistedQueryNotInList"
+  );
+
+  std::fs::remove_file(manifest).unwrap();
+}
@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use serde_json::json;
+use tailcall::cli::watch;
+use tailcall::config::Config;
+use tailcall::http::start_server_with_reload;
+use tokio::sync::mpsc;
+
+fn config(port: u16, hello: &str) -> Config {
+  let sdl = format!(
+    r#"
+    schema @server(port: {port}) {{
+      query: Query
+    }}
+
+    type Query {{
+      hello: {hello}
+    }}
+    "#
+  );
+  Config::from_sdl(&sdl).to_result().unwrap()
+}
+
+async fn hello(port: u16) -> serde_json::Value {
+  let response = reqwest::Client::new()
+    .post(format!("http://127.0.0.1:{port}/graphql"))
+    .json(&json!({"query": "query { hello }"}))
+    .send()
+    .await
+    .unwrap();
+  response.json::<serde_json::Value>().await.unwrap()["data"]["hello"].clone()
+}
+
+/// Waits until the server responds with the expected greeting
+async fn wait_for_hello(port: u16, expected: &str) {
+  for _ in 0..50 {
+    if tokio::net::TcpStream::connect(("127.0.0.1", port)).await.is_ok() && hello(port).await == expected {
+      return;
+    }
+    tokio::time::sleep(Duration::from_millis(20)).await;
+  }
+  panic!("The server never responded with {expected}");
+}
+
+#[tokio::test]
+async fn test_reload() {
+  let port = 8824;
+  let (reloads, receiver) = mpsc::channel(1);
+  tokio::spawn(start_server_with_reload(
+    config(port, r#"String @const(data: "Hello")"#),
+    receiver,
+  ));
+  wait_for_hello(port, "Hello").await;
+
+  reloads
+    .send(config(port, r#"String @const(data: "Bonjour")"#))
+    .await
+    .unwrap();
+  wait_for_hello(port, "Bonjour").await;
+
+  // The config is invalid, as the type of the field doesn't exist
+  reloads
+    .send(config(port, r#"Greeting @const(data: "Hola")"#))
+    .await
+    .unwrap();
+  tokio::time::sleep(Duration::from_millis(100)).await;
+  assert_eq!(hello(port).await, "Bonjour");
+}
+
+#[tokio::test]
+async fn test_watch() {
+  let directory = std::env::temp_dir().join(format!("tailcall-watch-{}", std::process::id()));
+  std::fs::create_dir_all(&directory).unwrap();
+  let file = directory.join("server.graphql");
+  let operations = directory.join("operations.graphql");
+  let sdl = |greeting: &str| {
+    format!(
+      "schema @link(src: \"{}\", type: \"Operation\") {{ query: Query }} type Query {{ hello: String @const(data: \"{greeting}\") }}",
+      operations.display()
+    )
+  };
+  std::fs::write(&file, sdl("Hello")).unwrap();
+  std::fs::write(&operations, "query { hello }").unwrap();
+
+  let file_paths = vec![file.to_string_lossy().to_string()];
+  let mut configs = watch(
+    file_paths.clone(),
+    &Config::from_file_paths(file_paths.iter()).await.unwrap(),
+  )
+  .unwrap();
+  // Changes to the files linked to the config are reloaded as well
+  std::fs::write(&operations, "query { hello hello }").unwrap();
+  tokio::time::timeout(Duration::from_secs(5), configs.recv())
+    .await
+    .unwrap()
+    .unwrap();
+
+  std::fs::write(&file, sdl("Bonjour")).unwrap();
+  let config = tokio::time::timeout(Duration::from_secs(5), configs.recv())
+    .await
+    .unwrap()
+    .unwrap();
+  let field = &config.find_type("Query").unwrap().fields["hello"];
+  assert_eq!(field.const_field.as_ref().unwrap().data, json!("Bonjour"));
+
+  std::fs::remove_dir_all(directory).unwrap();
+}
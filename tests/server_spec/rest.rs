@@ -0,0 +1,101 @@
+use hyper::{Body, Response};
+use serde_json::json;
+
+use crate::common::{self, start_upstream};
+
+/// An upstream that serves users and the posts of a user
+fn upstream(req: hyper::Request<Body>) -> Response<Body> {
+  let query = req.uri().query().unwrap_or_default();
+  let body = match (req.method().as_str(), req.uri().path()) {
+    ("GET", "/users") => {
+      let id = query.trim_start_matches("id=");
+      json!({"id": id.parse::<i32>().unwrap_or_default(), "name": format!("User {id}")})
+    }
+    ("GET", "/posts") => json!([{"id": 1, "title": format!("Post of {query}")}]),
+    ("POST", "/posts") => json!({"id": 101}),
+    _ => json!(null),
+  };
+  Response::builder()
+    .header("cache-control", "max-age=60")
+    .body(Body::from(body.to_string()))
+    .unwrap()
+}
+
+/// Starts tailcall on the given port and waits until it accepts connections
+async fn start_tailcall(port: u16) {
+  let addr = start_upstream(upstream).await;
+  let sdl = format!(
+    r#"
+    schema
+      @server(port: {port}, enableCacheControlHeader: true)
+      @upstream(baseURL: "http://{addr}")
+      @link(src: "tests/rest/operations.graphql", type: Operation) {{
+      query: Query
+      mutation: Mutation
+    }}
+
+    type User {{
+      id: Int
+      name: String
+    }}
+
+    type Post {{
+      id: Int
+      title: String
+    }}
+
+    type Query {{
+      user(id: Int): User @http(path: "/users", query: [{{key: "id", value: "{{{{args.id}}}}"}}])
+      posts(userId: Int): [Post] @http(path: "/posts", query: [{{key: "userId", value: "{{{{args.userId}}}}"}}])
+    }}
+
+    type Mutation {{
+      createPost(title: String): Post @http(path: "/posts", method: "POST", body: "{{{{args.title}}}}")
+    }}
+    "#
+  );
+  common::start_tailcall(common::config(&sdl), port).await;
+}
+
+#[tokio::test]
+async fn test_rest_endpoints() {
+  start_tailcall(8820).await;
+  let client = reqwest::Client::new();
+
+  let response = client.get("http://127.0.0.1:8820/users/1").send().await.unwrap();
+  assert_eq!(response.status(), 200);
+  assert_eq!(response.headers().get("cache-control").unwrap(), "max-age=60");
+  assert_eq!(
+    response.json::<serde_json::Value>().await.unwrap(),
+    json!({"user": {"id": 1, "name": "User 1"}})
+  );
+
+  let response = client.get("http://127.0.0.1:8820/posts?userId=2").send().await.unwrap();
+  assert_eq!(
+    response.json::<serde_json::Value>().await.unwrap(),
+    json!({"posts": [{"id": 1, "title": "Post of userId=2"}]})
+  );
+
+  let response = client
+    .post("http://127.0.0.1:8820/posts?title=Hello")
+    .send()
+    .await
+    .unwrap();
+  assert_eq!(
+    response.json::<serde_json::Value>().await.unwrap(),
+    json!({"createPost": {"id": 101}})
+  );
+}
+
+#[tokio::test]
+async fn test_rest_errors() {
+  start_tailcall(8821).await;
+  let client = reqwest::Client::new();
+
+  let response = client.get("http://127.0.0.1:8821/users/one").send().await.unwrap();
+  assert_eq!(response.status(), 400);
+  assert!(response.json::<serde_json::Value>().await.unwrap()["errors"].is_array());
+
+  let response = client.get("http://127.0.0.1:8821/comments").send().await.unwrap();
+  assert_eq!(response.status(), 404);
+}
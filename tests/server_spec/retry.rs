@@ -0,0 +1,111 @@
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use hyper::{Body, Response, StatusCode};
+use serde_json::json;
+use tailcall::http::ServerContext;
+
+use crate::common::{self, execute, json_response, start_upstream};
+
+/// Starts an upstream that fails the first `failures` requests with a 503, and counts the requests it receives
+async fn start_failing_upstream(failures: usize) -> (SocketAddr, Arc<AtomicUsize>) {
+  let requests = Arc::new(AtomicUsize::new(0));
+  let counter = requests.clone();
+  let addr = start_upstream(move |_| {
+    let count = counter.fetch_add(1, Ordering::SeqCst) + 1;
+    if count <= failures {
+      Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .body(Body::empty())
+        .unwrap()
+    } else {
+      json_response(json!({"id": 1, "name": "Leanne"}))
+    }
+  })
+  .await;
+
+  (addr, requests)
+}
+
+fn server_context(addr: SocketAddr, upstream: &str) -> ServerContext {
+  let sdl = format!(
+    r#"
+    schema @upstream(baseURL: "http://{addr}", {upstream}) {{
+      query: Query
+      mutation: Mutation
+    }}
+
+    type User {{
+      id: Int
+      name: String
+    }}
+
+    type Query {{
+      user: User @http(path: "/users/1")
+    }}
+
+    type Mutation {{
+      createUser: User @http(path: "/users", method: "POST", body: "{{}}")
+      upsertUser: User @http(path: "/users", method: "POST", body: "{{}}", idempotent: true)
+    }}
+    "#
+  );
+  common::server_context(&sdl)
+}
+
+#[tokio::test]
+async fn test_retry_until_success() {
+  let (addr, requests) = start_failing_upstream(2).await;
+  let server_ctx = server_context(addr, "retry: {maxAttempts: 3, backoffMs: 1}");
+
+  let res = execute(&server_ctx, "query { user { name } }").await;
+
+  assert_eq!(res, json!({"data": {"user": {"name": "Leanne"}}}));
+  assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_retry_gives_up_after_max_attempts() {
+  let (addr, requests) = start_failing_upstream(5).await;
+  let server_ctx = server_context(addr, "retry: {maxAttempts: 2, backoffMs: 1}");
+
+  let res = execute(&server_ctx, "query { user { name } }").await;
+
+  assert!(res["errors"].is_array());
+  assert_eq!(requests.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_post_is_retried_only_when_idempotent() {
+  let (addr, requests) = start_failing_upstream(1).await;
+  let server_ctx = server_context(addr, "retry: {maxAttempts: 3, backoffMs: 1}");
+
+  let res = execute(&server_ctx, "mutation { createUser { name } }").await;
+  assert!(res["errors"].is_array());
+  assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+  let (addr, requests) = start_failing_upstream(1).await;
+  let server_ctx = server_context(addr, "retry: {maxAttempts: 3, backoffMs: 1}");
+
+  let res = execute(&server_ctx, "mutation { upsertUser { name } }").await;
+  assert_eq!(res, json!({"data": {"upsertUser": {"name": "Leanne"}}}));
+  assert_eq!(requests.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_opens() {
+  let (addr, requests) = start_failing_upstream(usize::MAX).await;
+  let server_ctx = server_context(addr, "circuitBreaker: {failureThreshold: 2, openMs: 60000}");
+
+  for _ in 0..2 {
+    execute(&server_ctx, "query { user { name } }").await;
+  }
+  let res = execute(&server_ctx, "query { user { name } }").await;
+
+  assert!(res["errors"][0]["message"]
+    .as_str()
+    .unwrap()
+    .contains("Circuit breaker is open"));
+  assert_eq!(requests.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,169 @@
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use async_graphql::futures_util::{SinkExt, StreamExt};
+use async_graphql::Request;
+use hyper::{Body, Response};
+use serde_json::json;
+use tailcall::blueprint::Blueprint;
+use tailcall::config::Config;
+use tailcall::http::{RequestContext, ServerContext};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::common::{self, json_response, start_upstream};
+
+/// Starts an upstream that serves the latest news for polling and a stream of news as server-sent events
+async fn start_news_server() -> SocketAddr {
+  let calls = AtomicUsize::new(0);
+  start_upstream(move |req| match req.uri().path() {
+    // Changes only every other call, so that repeated values can be observed
+    "/news/latest" => {
+      let id = calls.fetch_add(1, Ordering::SeqCst) / 2 + 1;
+      json_response(json!({"id": id, "title": format!("News {id}")}))
+    }
+    "/news/events" => {
+      let id = req.uri().query().unwrap_or_default().trim_start_matches("id=");
+      let events = format!(
+        ": connected\n\ndata: {{\"id\": {id}, \"title\": \"Draft\"}}\n\ndata: {{\"id\": {id}, \"title\": \"Published\"}}\n\n"
+      );
+      Response::builder()
+        .header("content-type", "text/event-stream")
+        .body(Body::from(events))
+        .unwrap()
+    }
+    _ => Response::builder().status(404).body(Body::empty()).unwrap(),
+  })
+  .await
+}
+
+fn config(addr: SocketAddr, port: u16) -> Config {
+  let sdl = format!(
+    r#"
+    schema @server(port: {port}) @upstream(baseURL: "http://{addr}") {{
+      query: Query
+      subscription: Subscription
+    }}
+
+    type News {{
+      id: Int
+      title: String
+    }}
+
+    type Query {{
+      latestNews: News @http(path: "/news/latest")
+    }}
+
+    type Subscription {{
+      latestNews: News @http(path: "/news/latest", pollInterval: 10)
+      newsById(id: Int!): News @http(path: "/news/events", query: [{{key: "id", value: "{{{{args.id}}}}"}}])
+    }}
+    "#
+  );
+  common::config(&sdl)
+}
+
+async fn subscribe(addr: SocketAddr, query: &str, count: usize) -> Vec<serde_json::Value> {
+  let blueprint = Blueprint::try_from(&config(addr, 8000)).unwrap();
+  let server_ctx = ServerContext::new(blueprint).unwrap();
+  let req_ctx = Arc::new(RequestContext::from(&server_ctx));
+  server_ctx
+    .schema
+    .execute_stream(Request::new(query).data(req_ctx))
+    .take(count)
+    .map(|res| serde_json::to_value(res).unwrap())
+    .collect()
+    .await
+}
+
+#[tokio::test]
+async fn test_poll_subscription() {
+  let addr = start_news_server().await;
+  let responses = subscribe(addr, "subscription { latestNews { id title } }", 2).await;
+
+  assert_eq!(
+    responses,
+    vec![
+      json!({"data": {"latestNews": {"id": 1, "title": "News 1"}}}),
+      json!({"data": {"latestNews": {"id": 2, "title": "News 2"}}}),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn test_event_stream_subscription() {
+  let addr = start_news_server().await;
+  let responses = subscribe(addr, "subscription { newsById(id: 7) { id title } }", 3).await;
+
+  assert_eq!(
+    responses,
+    vec![
+      json!({"data": {"newsById": {"id": 7, "title": "Draft"}}}),
+      json!({"data": {"newsById": {"id": 7, "title": "Published"}}}),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn test_graphql_ws() {
+  let addr = start_news_server().await;
+  common::start_tailcall(config(addr, 8815), 8815).await;
+
+  let mut request = "ws://127.0.0.1:8815/graphql".into_client_request().unwrap();
+  request
+    .headers_mut()
+    .insert("sec-websocket-protocol", "graphql-transport-ws".parse().unwrap());
+  let (mut socket, response) = tokio_tungstenite::connect_async(request).await.unwrap();
+  assert_eq!(
+    response.headers().get("sec-websocket-protocol").unwrap(),
+    "graphql-transport-ws"
+  );
+
+  let init = json!({"type": "connection_init"});
+  let subscribe =
+    json!({"type": "subscribe", "id": "1", "payload": {"query": "subscription { newsById(id: 3) { title } }"}});
+  for message in [init, subscribe] {
+    socket.send(Message::Text(message.to_string())).await.unwrap();
+  }
+
+  let mut messages = Vec::new();
+  while let Some(Ok(Message::Text(text))) = socket.next().await {
+    let message: serde_json::Value = serde_json::from_str(&text).unwrap();
+    let complete = message["type"] == "complete";
+    messages.push(message);
+    if complete {
+      break;
+    }
+  }
+
+  assert_eq!(
+    messages,
+    vec![
+      json!({"type": "connection_ack"}),
+      json!({"type": "next", "id": "1", "payload": {"data": {"newsById": {"title": "Draft"}}}}),
+      json!({"type": "next", "id": "1", "payload": {"data": {"newsById": {"title": "Published"}}}}),
+      json!({"type": "complete", "id": "1"}),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn test_server_sent_events() {
+  let addr = start_news_server().await;
+  common::start_tailcall(config(addr, 8816), 8816).await;
+
+  let response = reqwest::Client::new()
+    .post("http://127.0.0.1:8816/graphql")
+    .header("accept", "text/event-stream")
+    .json(&json!({"query": "subscription { newsById(id: 5) { title } }"}))
+    .send()
+    .await
+    .unwrap();
+
+  assert_eq!(response.headers().get("content-type").unwrap(), "text/event-stream");
+  assert_eq!(
+    response.text().await.unwrap(),
+    "data: {\"data\":{\"newsById\":{\"title\":\"Draft\"}}}\n\ndata: {\"data\":{\"newsById\":{\"title\":\"Published\"}}}\n\n"
+  );
+}
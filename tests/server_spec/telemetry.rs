@@ -0,0 +1,91 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use opentelemetry_sdk::export::trace::SpanData;
+use serde_json::json;
+use tailcall::telemetry::{init_tracer, InMemoryExporter};
+
+use crate::common::{self, json_response, start_upstream};
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+/// Starts an upstream that serves a user and records the `traceparent` headers it receives
+async fn start_recording_upstream() -> (SocketAddr, Arc<Mutex<Vec<String>>>) {
+  let traceparents = Arc::new(Mutex::new(Vec::new()));
+  let recorder = traceparents.clone();
+  let addr = start_upstream(move |req| {
+    if let Some(traceparent) = req.headers().get("traceparent") {
+      recorder.lock().unwrap().push(traceparent.to_str().unwrap().to_string());
+    }
+    json_response(json!({"name": "Leanne"}))
+  })
+  .await;
+
+  (addr, traceparents)
+}
+
+/// Starts tailcall on the given port and waits until it accepts connections
+async fn start_tailcall(port: u16, addr: SocketAddr) {
+  let sdl = format!(
+    r#"
+    schema @server(port: {port}) @upstream(baseURL: "http://{addr}", batch: {{delay: 1}}) {{
+      query: Query
+    }}
+
+    type User {{
+      name: String
+    }}
+
+    type Query {{
+      user: User @http(path: "/users/1")
+    }}
+    "#
+  );
+  common::start_tailcall(common::config(&sdl), port).await;
+}
+
+/// Finds the span in the trace of the test, as the tracer also records the spans of the other tests
+fn find<'a>(spans: &'a [SpanData], name: &str) -> &'a SpanData {
+  spans
+    .iter()
+    .find(|span| span.name == name && span.span_context.trace_id().to_string() == TRACE_ID)
+    .unwrap_or_else(|| panic!("Missing span {name}"))
+}
+
+#[tokio::test]
+async fn test_trace_is_propagated() {
+  let exporter = InMemoryExporter::default();
+  let provider = init_tracer(exporter.clone());
+  let (addr, traceparents) = start_recording_upstream().await;
+  start_tailcall(8823, addr).await;
+
+  let response = reqwest::Client::new()
+    .post("http://127.0.0.1:8823/graphql")
+    .header("traceparent", format!("00-{TRACE_ID}-00f067aa0ba902b7-01"))
+    .json(&json!({"query": "query GetUser { user { name } }"}))
+    .send()
+    .await
+    .unwrap();
+  assert_eq!(
+    response.json::<serde_json::Value>().await.unwrap(),
+    json!({"data": {"user": {"name": "Leanne"}}})
+  );
+  provider.force_flush();
+
+  let spans = exporter.spans();
+  let operation = find(&spans, "graphql GetUser");
+  let resolver = find(&spans, "Query.user");
+  let batch = find(&spans, "HttpDataLoader.load");
+  let upstream = find(&spans, "HTTP GET");
+
+  assert_eq!(operation.parent_span_id.to_string(), "00f067aa0ba902b7");
+  assert_eq!(resolver.parent_span_id, operation.span_context.span_id());
+  assert_eq!(batch.parent_span_id, resolver.span_context.span_id());
+  assert_eq!(upstream.parent_span_id, batch.span_context.span_id());
+
+  let traceparents = traceparents.lock().unwrap();
+  assert_eq!(
+    *traceparents,
+    vec![format!("00-{TRACE_ID}-{}-01", upstream.span_context.span_id())]
+  );
+}
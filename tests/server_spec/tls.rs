@@ -0,0 +1,72 @@
+use reqwest::{Certificate, Version};
+use serde_json::json;
+
+use crate::common;
+
+/// Starts tailcall with the given `@server` arguments and waits until it accepts connections
+async fn start_tailcall(port: u16, server: &str) {
+  let sdl = format!(
+    r#"
+    schema @server(port: {port}, {server}) {{
+      query: Query
+    }}
+
+    type Query {{
+      hello: String @const(data: "Hello")
+    }}
+    "#
+  );
+  common::start_tailcall(common::config(&sdl), port).await;
+}
+
+async fn hello(client: reqwest::Client, url: String) -> (Version, serde_json::Value) {
+  let response = client
+    .post(url)
+    .json(&json!({"query": "query { hello }"}))
+    .send()
+    .await
+    .unwrap();
+  (response.version(), response.json().await.unwrap())
+}
+
+#[tokio::test]
+async fn test_tls() {
+  start_tailcall(8829, r#"tls: {cert: "tests/tls/cert.pem", key: "tests/tls/key.pem"}"#).await;
+  let ca = Certificate::from_pem(&std::fs::read("tests/tls/ca.pem").unwrap()).unwrap();
+  let url = "https://localhost:8829/graphql".to_string();
+  let expected = json!({"data": {"hello": "Hello"}});
+
+  // HTTP/2 is negotiated with the clients that support it
+  let client = reqwest::Client::builder()
+    .add_root_certificate(ca.clone())
+    .build()
+    .unwrap();
+  assert_eq!(hello(client, url.clone()).await, (Version::HTTP_2, expected.clone()));
+
+  let client = reqwest::Client::builder()
+    .add_root_certificate(ca)
+    .http1_only()
+    .build()
+    .unwrap();
+  assert_eq!(hello(client, url).await, (Version::HTTP_11, expected));
+}
+
+#[tokio::test]
+async fn test_h2c() {
+  // HTTP/2 without TLS is accepted unless it's turned off
+  start_tailcall(8830, "").await;
+  let client = reqwest::Client::builder().http2_prior_knowledge().build().unwrap();
+  assert_eq!(
+    hello(client, "http://127.0.0.1:8830/graphql".to_string()).await,
+    (Version::HTTP_2, json!({"data": {"hello": "Hello"}}))
+  );
+
+  start_tailcall(8831, "h2c: false").await;
+  let client = reqwest::Client::builder().http2_prior_knowledge().build().unwrap();
+  let response = client
+    .post("http://127.0.0.1:8831/graphql")
+    .json(&json!({"query": "query { hello }"}))
+    .send()
+    .await;
+  assert!(response.is_err());
+}
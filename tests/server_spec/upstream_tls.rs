@@ -0,0 +1,138 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Response};
+use rustls::server::AllowAnyAuthenticatedClient;
+use rustls::{RootCertStore, ServerConfig};
+use serde_json::json;
+use tailcall::blueprint::Blueprint;
+use tailcall::http::{load_certs, load_private_key, ServerContext};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+
+use crate::common::{self, execute};
+
+/// Starts an HTTPS upstream that only accepts the clients with a certificate signed by the test CA
+async fn start_upstream() -> SocketAddr {
+  let mut roots = RootCertStore::empty();
+  for cert in load_certs("tests/tls/ca.pem").unwrap() {
+    roots.add(&cert).unwrap();
+  }
+  let config = ServerConfig::builder()
+    .with_safe_defaults()
+    .with_client_cert_verifier(Arc::new(AllowAnyAuthenticatedClient::new(roots)))
+    .with_single_cert(
+      load_certs("tests/tls/cert.pem").unwrap(),
+      load_private_key("tests/tls/key.pem").unwrap(),
+    )
+    .unwrap();
+  let acceptor = TlsAcceptor::from(Arc::new(config));
+
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+  tokio::spawn(async move {
+    loop {
+      let (stream, _) = listener.accept().await.unwrap();
+      let acceptor = acceptor.clone();
+      tokio::spawn(async move {
+        let Ok(stream) = acceptor.accept(stream).await else {
+          return;
+        };
+        let service = service_fn(|_: hyper::Request<Body>| async {
+          Ok::<_, Infallible>(Response::new(Body::from(
+            json!({"id": 1, "name": "Leanne"}).to_string(),
+          )))
+        });
+        let _ = Http::new().serve_connection(stream, service).await;
+      });
+    }
+  });
+
+  addr
+}
+
+const CLIENT_TLS: &str = r#"tls: {caCert: "tests/tls/ca.pem", clientCert: "tests/tls/client-cert.pem", clientKey: "tests/tls/client-key.pem"}"#;
+
+fn server_context(upstream: &str, http: &str) -> ServerContext {
+  let sdl = format!(
+    r#"
+    schema @upstream({upstream}) {{
+      query: Query
+    }}
+
+    type User {{
+      id: Int
+      name: String
+    }}
+
+    type Query {{
+      user: User @http(path: "/users/1"{http})
+    }}
+    "#
+  );
+  common::server_context(&sdl)
+}
+
+#[tokio::test]
+async fn test_upstream_mtls() {
+  let addr = start_upstream().await;
+  let base_url = format!("baseURL: \"https://localhost:{}\"", addr.port());
+
+  let server_ctx = server_context(&format!("{base_url}, {CLIENT_TLS}"), "");
+  assert_eq!(
+    execute(&server_ctx, "query { user { name } }").await,
+    json!({"data": {"user": {"name": "Leanne"}}})
+  );
+
+  // The upstream rejects the clients without a certificate
+  let server_ctx = server_context(&format!("{base_url}, tls: {{caCert: \"tests/tls/ca.pem\"}}"), "");
+  assert!(execute(&server_ctx, "query { user { name } }").await["errors"].is_array());
+}
+
+#[tokio::test]
+async fn test_http_tls_override() {
+  let addr = start_upstream().await;
+  let base_url = format!("baseURL: \"https://localhost:{}\"", addr.port());
+
+  let server_ctx = server_context("baseURL: \"http://localhost\"", &format!(", {base_url}, {CLIENT_TLS}"));
+  assert_eq!(
+    execute(&server_ctx, "query { user { name } }").await,
+    json!({"data": {"user": {"name": "Leanne"}}})
+  );
+
+  // The settings of the field apply to every request to its host
+  let server_ctx = server_context(
+    &format!("{base_url}, tls: {{caCert: \"tests/tls/ca.pem\"}}"),
+    &format!(", {CLIENT_TLS}"),
+  );
+  assert_eq!(
+    execute(&server_ctx, "query { user { name } }").await,
+    json!({"data": {"user": {"name": "Leanne"}}})
+  );
+}
+
+#[tokio::test]
+async fn test_missing_certificate() {
+  // The certificate disappears between the validation of the config and the creation of the clients
+  let ca_cert = std::env::temp_dir().join("tailcall-upstream-tls-ca.pem");
+  std::fs::copy("tests/tls/ca.pem", &ca_cert).unwrap();
+  let sdl = format!(
+    r#"
+    schema @upstream(baseURL: "https://localhost", tls: {{caCert: "{}"}}) {{
+      query: Query
+    }}
+
+    type Query {{
+      hello: String @const(data: "Hello")
+    }}
+    "#,
+    ca_cert.display()
+  );
+  let blueprint = Blueprint::try_from(&common::config(&sdl)).unwrap();
+  std::fs::remove_file(&ca_cert).unwrap();
+
+  assert!(ServerContext::new(blueprint).is_err());
+}